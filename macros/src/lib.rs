@@ -0,0 +1,41 @@
+//! Procedural macros used by `hoops-app`
+mod model;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+/// Derive the database plumbing for a model struct.
+///
+/// Given a struct with named fields & a `#[model(table = "...")]` attribute, this generates:
+///
+/// - an impl of `hoops_app::models::Table` using the given table name
+/// - a `{Name}Sql` struct deriving `sqlx::FromRow`, where each field is stored as the type SQLite
+///   will hold it (`Uuid`, `Decimal`, & `DateTime<Utc>` values are all stored as `TEXT`)
+/// - `From<{Name}> for {Name}Sql` & `TryFrom<{Name}Sql> for {Name}` conversions between the two
+/// - an impl of `hoops_app::models::SqlRow` on `{Name}Sql` with the column list used by the
+///   default `Create::create_one`
+///
+/// All generated items are only compiled with the `ssr` feature, so the model itself can still
+/// be shared with the client.
+///
+/// ```ignore
+/// use hoops_macros::Model;
+///
+/// #[derive(Model)]
+/// #[model(table = "transactions")]
+/// pub struct Transaction {
+///     pub id: Uuid,
+///     pub amount: Decimal,
+///     pub description: Option<String>,
+/// }
+///
+/// // generates `TransactionSql { id: String, amount: String, description: Option<String> }`
+/// ```
+#[proc_macro_derive(Model, attributes(model))]
+pub fn derive_model(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    model::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
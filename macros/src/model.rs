@@ -0,0 +1,270 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    Data, DeriveInput, Field, Fields, GenericArgument, Ident, LitStr, PathArguments, Type,
+    Visibility,
+};
+
+/// How a field's type is stored in the database
+enum SqlKind {
+    /// Stored as is, e.g. `String` or `bool`
+    Same,
+    /// `uuid::Uuid`, stored as a hyphenated, lowercase string
+    Uuid,
+    /// `rust_decimal::Decimal`, stored as a string to avoid losing precision
+    Decimal,
+    /// `chrono::DateTime<Utc>`, stored as an RFC 3339 string
+    DateTime,
+}
+
+/// A single field on the model being derived
+struct ModelField {
+    vis: Visibility,
+    ident: Ident,
+    ty: Type,
+    kind: SqlKind,
+    optional: bool,
+}
+
+impl ModelField {
+    fn parse(field: &Field) -> syn::Result<Self> {
+        let ident = field
+            .ident
+            .clone()
+            .ok_or_else(|| syn::Error::new_spanned(field, "Model fields must be named"))?;
+        let (kind, optional) = match option_inner(&field.ty) {
+            Some(inner) => (SqlKind::of(inner), true),
+            None => (SqlKind::of(&field.ty), false),
+        };
+
+        Ok(Self {
+            vis: field.vis.clone(),
+            ident,
+            ty: field.ty.clone(),
+            kind,
+            optional,
+        })
+    }
+
+    /// The type used for this field on the generated `*Sql` struct
+    fn sql_ty(&self) -> TokenStream {
+        match (&self.kind, self.optional) {
+            (SqlKind::Same, _) => {
+                let ty = &self.ty;
+                quote! { #ty }
+            }
+            (_, false) => quote! { ::std::string::String },
+            (_, true) => quote! { ::std::option::Option<::std::string::String> },
+        }
+    }
+
+    /// Expression converting the model's value for this field into its sql value
+    fn sql_value(&self) -> TokenStream {
+        let ident = &self.ident;
+        let convert = |value: TokenStream| match self.kind {
+            SqlKind::Same => value,
+            SqlKind::Uuid | SqlKind::Decimal => quote! { #value.to_string() },
+            SqlKind::DateTime => quote! { #value.to_rfc3339() },
+        };
+
+        match (&self.kind, self.optional) {
+            (SqlKind::Same, _) => quote! { #ident },
+            (_, false) => convert(quote! { #ident }),
+            (_, true) => {
+                let value = convert(quote! { value });
+                quote! { #ident.map(|value| #value) }
+            }
+        }
+    }
+
+    /// Expression converting the sql value for this field back into the model's, returning early
+    /// with an error if it can't be parsed
+    fn model_value(&self) -> TokenStream {
+        let ident = &self.ident;
+        let parse = |value: TokenStream| match self.kind {
+            SqlKind::Same => value,
+            SqlKind::Uuid => quote! { ::uuid::Uuid::parse_str(&#value) },
+            SqlKind::Decimal => quote! { ::rust_decimal::Decimal::from_str_exact(&#value) },
+            SqlKind::DateTime => quote! {
+                ::chrono::DateTime::parse_from_rfc3339(&#value).map(::chrono::DateTime::from)
+            },
+        };
+
+        match (&self.kind, self.optional) {
+            (SqlKind::Same, _) => quote! { #ident },
+            (_, false) => {
+                let value = parse(quote! { #ident });
+                quote! { #value? }
+            }
+            (_, true) => {
+                let value = parse(quote! { value });
+                quote! { #ident.map(|value| #value).transpose()? }
+            }
+        }
+    }
+}
+
+impl SqlKind {
+    /// Pick how to store a type based on the last segment of its path, since the full path isn't
+    /// always written out
+    fn of(ty: &Type) -> Self {
+        match last_segment(ty).map(|segment| segment.ident.to_string()) {
+            Some(name) if name == "Uuid" => SqlKind::Uuid,
+            Some(name) if name == "Decimal" => SqlKind::Decimal,
+            Some(name) if name == "DateTime" => SqlKind::DateTime,
+            _ => SqlKind::Same,
+        }
+    }
+}
+
+fn last_segment(ty: &Type) -> Option<&syn::PathSegment> {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.segments.last(),
+        _ => None,
+    }
+}
+
+/// If the given type is an `Option<T>`, get `T`
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let segment = last_segment(ty).filter(|segment| segment.ident == "Option")?;
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first() {
+            Some(GenericArgument::Type(inner)) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Get the table name from the `#[model(table = "...")]` attribute
+fn table_name(input: &DeriveInput) -> syn::Result<LitStr> {
+    let mut table = None;
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("model"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                table = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported model attribute, expected `table`"))
+            }
+        })?;
+    }
+
+    table.ok_or_else(|| {
+        syn::Error::new_spanned(
+            &input.ident,
+            "deriving Model requires a table name, e.g. `#[model(table = \"my_table\")]`",
+        )
+    })
+}
+
+pub fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let table = table_name(&input)?;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields
+                .named
+                .iter()
+                .map(ModelField::parse)
+                .collect::<syn::Result<Vec<_>>>()?,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "Model can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "Model can only be derived for structs",
+            ))
+        }
+    };
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let sql_name = format_ident!("{}Sql", name);
+    let sql_doc = format!("Database row representation of [`{name}`], generated by `Model`");
+
+    let idents: Vec<_> = fields.iter().map(|field| &field.ident).collect();
+    let columns = idents.iter().map(|ident| {
+        let column = ident.to_string();
+        quote! { ::hoops_app::models::Ident::new(#column) }
+    });
+    let sql_fields = fields.iter().map(|field| {
+        let vis = &field.vis;
+        let ident = &field.ident;
+        let ty = field.sql_ty();
+        quote! { #vis #ident: #ty }
+    });
+    let to_sql = fields.iter().map(|field| {
+        let ident = &field.ident;
+        let value = field.sql_value();
+        quote! { #ident: #value }
+    });
+    let from_sql = fields.iter().map(|field| {
+        let ident = &field.ident;
+        let value = field.model_value();
+        quote! { #ident: #value }
+    });
+
+    Ok(quote! {
+        #[cfg(feature = "ssr")]
+        impl ::hoops_app::models::Table for #name {
+            const TABLE: ::hoops_app::models::Ident = ::hoops_app::models::Ident::new(#table);
+        }
+
+        #[cfg(feature = "ssr")]
+        #[doc = #sql_doc]
+        #[derive(::sqlx::FromRow, Clone)]
+        #vis struct #sql_name {
+            #(#sql_fields,)*
+        }
+
+        #[cfg(feature = "ssr")]
+        impl ::std::convert::From<#name> for #sql_name {
+            fn from(value: #name) -> Self {
+                let #name { #(#idents,)* } = value;
+
+                Self { #(#to_sql,)* }
+            }
+        }
+
+        #[cfg(feature = "ssr")]
+        impl ::std::convert::TryFrom<#sql_name> for #name {
+            type Error = ::anyhow::Error;
+
+            fn try_from(value: #sql_name) -> ::std::result::Result<Self, Self::Error> {
+                let #sql_name { #(#idents,)* } = value;
+
+                ::std::result::Result::Ok(Self { #(#from_sql,)* })
+            }
+        }
+
+        #[cfg(feature = "ssr")]
+        impl ::hoops_app::models::SqlRow for #sql_name {
+            const COLUMNS: &'static [::hoops_app::models::Ident] = &[#(#columns),*];
+
+            fn push_values(
+                self,
+                values: &mut ::sqlx::query_builder::Separated<
+                    '_,
+                    '_,
+                    ::sqlx::Sqlite,
+                    &'static str,
+                >,
+            ) {
+                let #sql_name { #(#idents,)* } = self;
+
+                #(values.push_bind(#idents);)*
+            }
+        }
+    })
+}
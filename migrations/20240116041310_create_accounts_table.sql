@@ -0,0 +1,31 @@
+CREATE TABLE IF NOT EXISTS "accounts" (
+    "id" TEXT PRIMARY KEY NOT NULL,
+    "name" TEXT NOT NULL,
+    "bank" TEXT,
+    "closed" INTEGER NOT NULL DEFAULT 0
+) STRICT;
+
+-- every transaction now belongs to an account, so any existing transactions are moved to a
+-- placeholder account that can be renamed later
+INSERT INTO "accounts" ("id", "name")
+SELECT '00000000-0000-0000-0000-000000000000', 'Unassigned'
+WHERE EXISTS (SELECT 1 FROM "transactions");
+
+CREATE TABLE "transactions_with_account" (
+    "id" TEXT PRIMARY KEY NOT NULL,
+    "amount" TEXT NOT NULL,
+    "description" TEXT,
+    "payee" TEXT NOT NULL,
+    "timestamp" TEXT NOT NULL,
+    "account_id" TEXT NOT NULL REFERENCES "accounts" ("id")
+) STRICT;
+
+INSERT INTO "transactions_with_account"
+SELECT "id", "amount", "description", "payee", "timestamp", '00000000-0000-0000-0000-000000000000'
+FROM "transactions";
+
+DROP TABLE "transactions";
+
+ALTER TABLE "transactions_with_account" RENAME TO "transactions";
+
+CREATE INDEX "transactions_account_id" ON "transactions" ("account_id");
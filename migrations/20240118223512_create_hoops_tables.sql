@@ -0,0 +1,33 @@
+CREATE TABLE IF NOT EXISTS "hoops" (
+    "id" TEXT PRIMARY KEY NOT NULL,
+    "name" TEXT NOT NULL
+) STRICT;
+
+-- ledger of every movement of money into or out of a hoop; a hoop's allocated balance is the sum
+-- of its entries, so rows are only ever added
+CREATE TABLE IF NOT EXISTS "hoop_allocations" (
+    "id" TEXT PRIMARY KEY NOT NULL,
+    "hoop_id" TEXT NOT NULL REFERENCES "hoops" ("id"),
+    -- positive amounts move money into the hoop, negative ones move it out
+    "amount" TEXT NOT NULL,
+    -- the hoop the money came from or went to, or NULL for Safe-to-Spend
+    "counterpart_hoop_id" TEXT REFERENCES "hoops" ("id"),
+    "timestamp" TEXT NOT NULL
+) STRICT;
+
+CREATE INDEX "hoop_allocations_hoop_id" ON "hoop_allocations" ("hoop_id");
+
+CREATE TRIGGER "hoop_allocations_no_update" BEFORE UPDATE ON "hoop_allocations"
+BEGIN
+    SELECT RAISE(ABORT, 'hoop allocations are append-only');
+END;
+
+CREATE TRIGGER "hoop_allocations_no_delete" BEFORE DELETE ON "hoop_allocations"
+BEGIN
+    SELECT RAISE(ABORT, 'hoop allocations are append-only');
+END;
+
+-- the hoop a transaction was spent from, if any
+ALTER TABLE "transactions" ADD COLUMN "spent_from" TEXT REFERENCES "hoops" ("id");
+
+CREATE INDEX "transactions_spent_from" ON "transactions" ("spent_from");
@@ -0,0 +1,3 @@
+-- pending transactions haven't cleared the bank yet, so they aren't part of an account's
+-- available balance
+ALTER TABLE "transactions" ADD COLUMN "pending" INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,7 @@
+-- archived hoops are hidden from the list of hoops & can't be spent from
+ALTER TABLE "hoops" ADD COLUMN "archived" INTEGER NOT NULL DEFAULT 0;
+
+-- what caused an allocation, so the sweep made when archiving a hoop can be told apart from money
+-- moved back to Safe-to-Spend by hand
+ALTER TABLE "hoop_allocations" ADD COLUMN "kind" TEXT NOT NULL DEFAULT 'move'
+    CHECK ("kind" IN ('move', 'archive', 'unarchive'));
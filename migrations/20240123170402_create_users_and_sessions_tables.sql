@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS "users" (
+    "id" TEXT PRIMARY KEY NOT NULL,
+    "username" TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    -- argon2 hash in PHC string format, including its salt & parameters
+    "password_hash" TEXT NOT NULL
+) STRICT;
+
+-- a session is looked up by the SHA-256 hash of the token kept in the user's cookie, so the
+-- tokens themselves are never stored
+CREATE TABLE IF NOT EXISTS "sessions" (
+    "id" TEXT PRIMARY KEY NOT NULL,
+    "user_id" TEXT NOT NULL REFERENCES "users" ("id") ON DELETE CASCADE,
+    "expires_at" TEXT NOT NULL
+) STRICT;
+
+CREATE INDEX "sessions_user_id" ON "sessions" ("user_id");
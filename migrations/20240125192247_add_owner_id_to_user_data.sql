@@ -0,0 +1,27 @@
+-- every row of a user's data belongs to the user who made it. SQLite can't add a NOT NULL column
+-- without a default, so rows made before users existed are left without an owner until they're
+-- claimed by running `hoops-app claim-unowned <username>`
+ALTER TABLE "accounts" ADD COLUMN "owner_id" TEXT REFERENCES "users" ("id");
+ALTER TABLE "hoops" ADD COLUMN "owner_id" TEXT REFERENCES "users" ("id");
+ALTER TABLE "hoop_allocations" ADD COLUMN "owner_id" TEXT REFERENCES "users" ("id");
+ALTER TABLE "transactions" ADD COLUMN "owner_id" TEXT REFERENCES "users" ("id");
+
+CREATE INDEX "accounts_owner_id" ON "accounts" ("owner_id");
+CREATE INDEX "hoops_owner_id" ON "hoops" ("owner_id");
+CREATE INDEX "hoop_allocations_owner_id" ON "hoop_allocations" ("owner_id");
+CREATE INDEX "transactions_owner_id" ON "transactions" ("owner_id");
+
+-- allocations are still append-only, but unowned ones need to be claimed
+DROP TRIGGER "hoop_allocations_no_update";
+
+CREATE TRIGGER "hoop_allocations_no_update" BEFORE UPDATE ON "hoop_allocations"
+WHEN OLD."owner_id" IS NOT NULL
+    OR NEW."id" IS NOT OLD."id"
+    OR NEW."hoop_id" IS NOT OLD."hoop_id"
+    OR NEW."amount" IS NOT OLD."amount"
+    OR NEW."counterpart_hoop_id" IS NOT OLD."counterpart_hoop_id"
+    OR NEW."timestamp" IS NOT OLD."timestamp"
+    OR NEW."kind" IS NOT OLD."kind"
+BEGIN
+    SELECT RAISE(ABORT, 'hoop allocations are append-only');
+END;
@@ -0,0 +1,3 @@
+-- IANA name of the time zone a user enters & reads timestamps in, e.g. "Australia/Sydney".
+-- Timestamps themselves are still saved in UTC
+ALTER TABLE "users" ADD COLUMN "time_zone" TEXT NOT NULL DEFAULT 'UTC';
@@ -0,0 +1,6 @@
+-- amounts are stored as text so they never lose precision, which sorts & compares them as
+-- strings. This numeric copy is only used for sorting & filtering by amount
+ALTER TABLE "transactions" ADD COLUMN "amount_value" REAL GENERATED ALWAYS AS (CAST("amount" AS REAL)) VIRTUAL;
+
+CREATE INDEX "transactions_owner_id_amount_value" ON "transactions" ("owner_id", "amount_value");
+CREATE INDEX "transactions_owner_id_timestamp" ON "transactions" ("owner_id", "timestamp");
@@ -0,0 +1,30 @@
+-- full-text index of every transaction's payee & description, for searching transactions. The
+-- text is copied in instead of read from "transactions" by rowid, since a VACUUM can renumber the
+-- rowids of a table without an INTEGER PRIMARY KEY
+CREATE VIRTUAL TABLE "transactions_fts" USING fts5(
+    "transaction_id" UNINDEXED,
+    "payee_text",
+    "description_text",
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO "transactions_fts" ("transaction_id", "payee_text", "description_text")
+SELECT "id", "payee", "description" FROM "transactions";
+
+CREATE TRIGGER "transactions_fts_insert" AFTER INSERT ON "transactions"
+BEGIN
+    INSERT INTO "transactions_fts" ("transaction_id", "payee_text", "description_text")
+    VALUES (new."id", new."payee", new."description");
+END;
+
+CREATE TRIGGER "transactions_fts_update" AFTER UPDATE OF "id", "payee", "description" ON "transactions"
+BEGIN
+    UPDATE "transactions_fts"
+    SET "transaction_id" = new."id", "payee_text" = new."payee", "description_text" = new."description"
+    WHERE "transaction_id" = old."id";
+END;
+
+CREATE TRIGGER "transactions_fts_delete" AFTER DELETE ON "transactions"
+BEGIN
+    DELETE FROM "transactions_fts" WHERE "transaction_id" = old."id";
+END;
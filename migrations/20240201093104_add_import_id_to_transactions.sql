@@ -0,0 +1,7 @@
+-- the ID a bank gave a transaction in the file it was imported from, like an OFX FITID. Banks
+-- only promise these are unique within an account, & a transaction imported again is skipped
+ALTER TABLE "transactions" ADD COLUMN "import_id" TEXT;
+
+CREATE UNIQUE INDEX "transactions_owner_id_account_id_import_id"
+ON "transactions" ("owner_id", "account_id", "import_id")
+WHERE "import_id" IS NOT NULL;
@@ -0,0 +1,37 @@
+-- rows of the full-text index are found by their rowid, since the index can only find a row by
+-- its "transaction_id" by reading every row. The rowids are kept in a table of their own instead
+-- of matching the transactions' rowids, which a VACUUM can renumber
+CREATE TABLE "transactions_fts_rowids" (
+    "transaction_id" TEXT PRIMARY KEY NOT NULL,
+    "fts_rowid" INTEGER NOT NULL UNIQUE
+) STRICT;
+
+INSERT INTO "transactions_fts_rowids" ("transaction_id", "fts_rowid")
+SELECT "transaction_id", "rowid" FROM "transactions_fts";
+
+DROP TRIGGER "transactions_fts_insert";
+DROP TRIGGER "transactions_fts_update";
+DROP TRIGGER "transactions_fts_delete";
+
+CREATE TRIGGER "transactions_fts_insert" AFTER INSERT ON "transactions"
+BEGIN
+    INSERT INTO "transactions_fts" ("transaction_id", "payee_text", "description_text")
+    VALUES (new."id", new."payee", new."description");
+    INSERT INTO "transactions_fts_rowids" ("transaction_id", "fts_rowid")
+    VALUES (new."id", last_insert_rowid());
+END;
+
+CREATE TRIGGER "transactions_fts_update" AFTER UPDATE OF "id", "payee", "description" ON "transactions"
+BEGIN
+    UPDATE "transactions_fts"
+    SET "transaction_id" = new."id", "payee_text" = new."payee", "description_text" = new."description"
+    WHERE "rowid" = (SELECT "fts_rowid" FROM "transactions_fts_rowids" WHERE "transaction_id" = old."id");
+    UPDATE "transactions_fts_rowids" SET "transaction_id" = new."id" WHERE "transaction_id" = old."id";
+END;
+
+CREATE TRIGGER "transactions_fts_delete" AFTER DELETE ON "transactions"
+BEGIN
+    DELETE FROM "transactions_fts"
+    WHERE "rowid" = (SELECT "fts_rowid" FROM "transactions_fts_rowids" WHERE "transaction_id" = old."id");
+    DELETE FROM "transactions_fts_rowids" WHERE "transaction_id" = old."id";
+END;
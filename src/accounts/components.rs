@@ -0,0 +1,347 @@
+use leptos::*;
+use leptos_router::*;
+use uuid::Uuid;
+
+use crate::accounts::model::{Account, AccountSummary, Balances};
+use crate::components::{errors::action_error, input::Input};
+
+#[cfg(feature = "ssr")]
+use crate::{
+    events::{publish, Change, DataEvent, Entity},
+    state::pool,
+    users::scope,
+};
+
+/// add Account server endpoint
+///
+/// Takes the user back to the list of accounts once the new account is saved.
+#[server(prefix = "/api", endpoint = "account/new")]
+pub async fn account_new(name: String, bank: String) -> Result<(), ServerFnError> {
+    use crate::models::Create;
+
+    // convert empty strings to None, otherwise pass as Some(..)
+    let bank = match bank.as_str() {
+        "" => None,
+        _ => Some(bank),
+    };
+    let scope = scope()?;
+    let pool = &pool()?;
+    let account = Account::new(name, bank);
+    let id = account.id;
+
+    Account::create_one(pool, scope, account)
+        .await
+        .map_err(|err| {
+            logging::log!("There was an error saving the account: {}", err);
+            ServerFnError::ServerError(err.to_string())
+        })?;
+    publish(scope, DataEvent::new(Entity::Account, Change::Created, id));
+
+    leptos_axum::redirect("/accounts");
+    Ok(())
+}
+
+/// Server endpoint for reading all open (or all closed) accounts with their balances
+#[server(prefix = "/api", endpoint = "accounts/read/all")]
+pub async fn accounts_read_many(closed: bool) -> Result<Vec<AccountSummary>, ServerFnError> {
+    use crate::accounts::model::db_read_many_summaries;
+
+    let scope = scope()?;
+    let pool = &pool()?;
+
+    db_read_many_summaries(pool, scope, closed)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// Server endpoint for reading a single account with its balances
+#[server(prefix = "/api", endpoint = "account/read/one")]
+pub async fn account_read_one(id: Uuid) -> Result<AccountSummary, ServerFnError> {
+    use crate::accounts::model::db_read_one_summary;
+
+    let scope = scope()?;
+    let pool = &pool()?;
+
+    db_read_one_summary(pool, scope, id)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?
+        .ok_or_else(|| ServerFnError::ServerError(format!("No account found with id {id}")))
+}
+
+/// Server endpoint for changing an account's name
+#[server(prefix = "/api", endpoint = "account/rename")]
+pub async fn account_rename(id: Uuid, name: String) -> Result<(), ServerFnError> {
+    use crate::accounts::model::AccountPatch;
+
+    let scope = scope()?;
+    let pool = &pool()?;
+    let patch = AccountPatch {
+        name: Some(name),
+        ..Default::default()
+    };
+
+    patch_account(pool, scope, id, patch).await
+}
+
+/// Server endpoint for closing an account, hiding it from the list of accounts
+///
+/// Takes the user back to the list of accounts, where the closed account no longer appears.
+#[server(prefix = "/api", endpoint = "account/close")]
+pub async fn account_close(id: Uuid) -> Result<(), ServerFnError> {
+    use crate::accounts::model::AccountPatch;
+
+    let scope = scope()?;
+    let pool = &pool()?;
+    let patch = AccountPatch {
+        closed: Some(true),
+        ..Default::default()
+    };
+
+    patch_account(pool, scope, id, patch).await?;
+
+    leptos_axum::redirect("/accounts");
+    Ok(())
+}
+
+/// Server endpoint for reopening a closed account
+#[server(prefix = "/api", endpoint = "account/reopen")]
+pub async fn account_reopen(id: Uuid) -> Result<(), ServerFnError> {
+    use crate::accounts::model::AccountPatch;
+
+    let scope = scope()?;
+    let pool = &pool()?;
+    let patch = AccountPatch {
+        closed: Some(false),
+        ..Default::default()
+    };
+
+    patch_account(pool, scope, id, patch).await
+}
+
+/// Apply the given patch, treating a missing account as an error, & let the user's other tabs &
+/// devices know it changed
+#[cfg(feature = "ssr")]
+async fn patch_account(
+    pool: &sqlx::SqlitePool,
+    scope: crate::models::Scope,
+    id: Uuid,
+    patch: crate::accounts::model::AccountPatch,
+) -> Result<(), ServerFnError> {
+    use crate::models::Update;
+
+    Account::patch_one_by_id(pool, scope, id, patch)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?
+        .ok_or_else(|| ServerFnError::ServerError(format!("No account found with id {id}")))?;
+
+    publish(scope, DataEvent::new(Entity::Account, Change::Updated, id));
+    Ok(())
+}
+
+/// Lists every open account with its bank & available balance
+///
+/// Closed accounts are listed instead when the `closed` query param is `true`.
+#[component]
+pub fn All() -> impl IntoView {
+    let query = use_query_map();
+    let closed = move || query.with(|q| q.get("closed").map(|c| c == "true").unwrap_or(false));
+
+    let accounts = create_resource(closed, accounts_read_many);
+
+    view! {
+        <h1>{move || if closed() { "Closed accounts" } else { "Accounts" }}</h1>
+        <Suspense fallback=move || view! {<p>Loading...</p>}.into_view()>
+            {move || accounts.get().map(|accounts| match accounts {
+                Err(err) => {
+                    view! { <pre>Error fetching accounts: {err.to_string()}</pre>}.into_view()
+                },
+                Ok(accounts) if accounts.is_empty() => {
+                    view! {<p>No accounts yet...</p>}.into_view()
+                },
+                Ok(accounts) => {
+                    view! {
+                        <ul>
+                            {accounts.into_iter().map(|summary| view! { <Item summary /> }).collect_view()}
+                        </ul>
+                    }.into_view()
+                },
+            })}
+        </Suspense>
+        <A href="/accounts/new">Add new account</A>
+        {move || if closed() {
+            view! { <A href="/accounts">View open accounts</A> }
+        } else {
+            view! { <A href="/accounts?closed=true">View closed accounts</A> }
+        }}
+    }
+}
+
+/// Component for rendering a single item in a list of accounts
+#[component]
+fn Item(summary: AccountSummary) -> impl IntoView {
+    let AccountSummary {
+        account: Account { id, name, bank, .. },
+        balances: Balances { available, .. },
+    } = summary;
+    let href = format!("/accounts/{id}");
+
+    view! {
+        <li>
+            <ul>
+                <li><A href=href.clone()>{name}</A></li>
+                <li>{bank}</li>
+                <li><A href=href>{available.to_string()}</A></li>
+            </ul>
+        </li>
+    }
+}
+
+/// Form for adding a new account
+#[component]
+pub fn New() -> impl IntoView {
+    let action = create_server_action::<AccountNew>();
+
+    view! {
+        <h1>New account</h1>
+        {action_error(action)}
+        <ActionForm action>
+            <Input name="name".to_string() label="Name:".to_string() attr:required=true />
+            <Input name="bank".to_string() label="Bank:".to_string() />
+            <button type="submit">Create</button>
+        </ActionForm>
+    }
+}
+
+/// Shows a single account's details, with options to rename, close, or reopen it, & to import
+/// transactions into it while it's open
+#[component]
+pub fn Detail() -> impl IntoView {
+    let params = use_params_map();
+    let id = move || params.with(|p| p.get("id").cloned().unwrap_or_default());
+
+    let rename = create_server_action::<AccountRename>();
+    let close = create_server_action::<AccountClose>();
+    let reopen = create_server_action::<AccountReopen>();
+
+    // refetch the account every time it is changed
+    let account = create_resource(
+        move || (id(), rename.version().get(), reopen.version().get()),
+        |(id, _, _)| async move {
+            let id = Uuid::parse_str(&id).map_err(|e| ServerFnError::Args(e.to_string()))?;
+            account_read_one(id).await
+        },
+    );
+
+    view! {
+        <Suspense fallback=move || view! {<p>Loading...</p>}.into_view()>
+            {move || account.get().map(|account| match account {
+                Err(err) => {
+                    view! { <pre>Error fetching account: {err.to_string()}</pre>}.into_view()
+                },
+                Ok(AccountSummary { account: Account { id, name, bank, closed }, balances }) => {
+                    // closed accounts can be reopened, while open ones can be closed
+                    let status_form = if closed {
+                        view! {
+                            {action_error(reopen)}
+                            <ActionForm action=reopen>
+                                <input type="hidden" name="id" value=id.to_string() />
+                                <button type="submit">Reopen account</button>
+                            </ActionForm>
+                        }.into_view()
+                    } else {
+                        view! {
+                            {action_error(close)}
+                            <ActionForm action=close>
+                                <input type="hidden" name="id" value=id.to_string() />
+                                <button type="submit">Close account</button>
+                            </ActionForm>
+                            <A href=format!("/accounts/{id}/import")>Import transactions</A>
+                        }.into_view()
+                    };
+
+                    view! {
+                        <h1>{name.clone()}</h1>
+                        <ul>
+                            <li>Bank: {bank}</li>
+                            <li>Available balance: {balances.available.to_string()}</li>
+                            <li>Scheduled transactions: {balances.scheduled.to_string()}</li>
+                            <li>Pending transactions: {balances.pending.to_string()}</li>
+                        </ul>
+                        {action_error(rename)}
+                        <ActionForm action=rename>
+                            <input type="hidden" name="id" value=id.to_string() />
+                            <Input name="name".to_string() label="Name:".to_string() value=name attr:required=true />
+                            <button type="submit">Rename</button>
+                        </ActionForm>
+                        {status_form}
+                    }.into_view()
+                },
+            })}
+        </Suspense>
+        <A href="/accounts">Back to accounts</A>
+    }
+}
+
+/// A `<select>` input for choosing one of the open accounts
+///
+/// When given a `none_label`, an extra option with an empty value is listed first, letting the
+/// user choose no account at all. The account selected by default is always listed, even once it's
+/// been closed, so a form showing it doesn't lose it when sent.
+#[component]
+pub fn Select(
+    name: String,
+    label: String,
+    /// ID of the account selected by default, if any
+    #[prop(optional_no_strip)]
+    value: Option<Uuid>,
+    /// Label for the option standing in for no account, if one can be chosen
+    #[prop(optional, into)]
+    none_label: Option<String>,
+) -> impl IntoView {
+    let accounts = create_resource(
+        move || value,
+        |value| async move {
+            let mut accounts = accounts_read_many(false).await?;
+            if let Some(id) =
+                value.filter(|id| !accounts.iter().any(|summary| summary.account.id == *id))
+            {
+                let closed = accounts_read_many(true).await?;
+                accounts.extend(
+                    closed
+                        .into_iter()
+                        .filter(|summary| summary.account.id == id),
+                );
+            }
+            Ok::<_, ServerFnError>(accounts)
+        },
+    );
+    let required = none_label.is_none();
+
+    view! {
+        <label for=&name>{&label}</label>
+        <select id=&name name=&name required=required>
+            {none_label.map(|none_label| view! {
+                <option value="" selected=value.is_none()>{none_label}</option>
+            })}
+            <Suspense fallback=move || view! {<option disabled>Loading...</option>}.into_view()>
+                {move || accounts.get().map(|accounts| {
+                    accounts
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|AccountSummary { account, .. }| {
+                            let name = match account.closed {
+                                true => format!("{} (closed)", account.name),
+                                false => account.name,
+                            };
+                            view! {
+                                <option value=account.id.to_string() selected=value == Some(account.id)>
+                                    {name}
+                                </option>
+                            }
+                        })
+                        .collect_view()
+                })}
+            </Suspense>
+        </select>
+    }
+}
@@ -0,0 +1,240 @@
+use cfg_if::cfg_if;
+use chrono::{DateTime, Utc};
+use hoops_macros::Model;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Data type for modeling a bank account's information
+#[derive(Clone, Deserialize, Serialize, Model)]
+#[model(table = "accounts")]
+pub struct Account {
+    pub id: Uuid,
+    pub name: String,
+    /// Name of the bank holding the account, if given
+    pub bank: Option<String>,
+    /// Closed accounts are hidden from the list of accounts & can't be given new transactions
+    pub closed: bool,
+}
+
+impl Account {
+    pub fn new(name: String, bank: Option<String>) -> Self {
+        Account {
+            id: Uuid::new_v4(),
+            name,
+            bank,
+            closed: false,
+        }
+    }
+}
+
+/// A partial set of an account's information, used for changing only some of a saved account's
+/// fields. Any field left as `None` is not changed.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct AccountPatch {
+    pub name: Option<String>,
+    /// `Some(None)` clears the bank, while `None` leaves it as is
+    pub bank: Option<Option<String>>,
+    pub closed: Option<bool>,
+}
+
+/// An account along with its balances, as computed from its transactions
+#[derive(Clone, Deserialize, Serialize)]
+pub struct AccountSummary {
+    pub account: Account,
+    pub balances: Balances,
+}
+
+/// An account's balances, split up by whether the transactions making them up have cleared
+///
+/// Money coming into an account isn't counted until it clears, so `scheduled` & `pending` only
+/// ever hold money leaving it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Balances {
+    /// Sum of every cleared transaction made up to now
+    pub available: Decimal,
+    /// Sum of the money leaving the account in cleared transactions dated in the future
+    pub scheduled: Decimal,
+    /// Sum of the money leaving the account in transactions that haven't cleared yet
+    pub pending: Decimal,
+}
+
+impl Balances {
+    /// Add a single transaction to the balances
+    ///
+    /// ```
+    /// use chrono::{Duration, Utc};
+    /// use rust_decimal::Decimal;
+    ///
+    /// use hoops_app::accounts::model::Balances;
+    ///
+    /// let now = Utc::now();
+    /// let mut balances = Balances::default();
+    /// balances.add(Decimal::new(100, 0), now - Duration::days(1), false, now);
+    /// balances.add(Decimal::new(-30, 0), now + Duration::days(1), false, now);
+    /// balances.add(Decimal::new(-20, 0), now, true, now);
+    /// // money coming in isn't counted until it has cleared
+    /// balances.add(Decimal::new(500, 0), now, true, now);
+    ///
+    /// assert_eq!(balances.available, Decimal::new(100, 0));
+    /// assert_eq!(balances.scheduled, Decimal::new(-30, 0));
+    /// assert_eq!(balances.pending, Decimal::new(-20, 0));
+    /// assert_eq!(balances.remaining(), Decimal::new(50, 0));
+    /// ```
+    pub fn add(
+        &mut self,
+        amount: Decimal,
+        timestamp: DateTime<Utc>,
+        pending: bool,
+        now: DateTime<Utc>,
+    ) {
+        if pending {
+            self.pending += amount.min(Decimal::ZERO);
+        } else if timestamp > now {
+            self.scheduled += amount.min(Decimal::ZERO);
+        } else {
+            self.available += amount;
+        }
+    }
+
+    /// The available balance, less everything scheduled or pending to leave the account
+    pub fn remaining(&self) -> Decimal {
+        self.available + self.scheduled + self.pending
+    }
+}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use std::collections::HashMap;
+        use std::convert::TryFrom;
+        use sqlx::{Executor, Sqlite};
+
+        use crate::models::{Create, Ident, OrderBy, Read, Scope, Update};
+
+        impl Create for Account {
+            type SqlType = AccountSql;
+        }
+
+        impl Read for Account {
+            type SqlType = AccountSql;
+
+            const ORDER_BY: &'static [OrderBy] = &[OrderBy::asc(Ident::new("name"))];
+        }
+
+        impl Update for Account {
+            type Patch = AccountPatch;
+
+            fn apply_patch(&mut self, patch: AccountPatch) {
+                let AccountPatch { name, bank, closed } = patch;
+
+                if let Some(name) = name {
+                    self.name = name;
+                }
+                if let Some(bank) = bank {
+                    self.bank = bank;
+                }
+                if let Some(closed) = closed {
+                    self.closed = closed;
+                }
+            }
+        }
+
+        /// Read every account owned by the scope's user, whether open or closed
+        pub async fn db_read_all<'c, E>(executor: E, scope: Scope) -> Result<Vec<Account>, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite>,
+        {
+            sqlx::query_as::<_, AccountSql>("SELECT * FROM accounts WHERE owner_id = ? ORDER BY name ASC;")
+                .bind(scope.owner_id().to_string())
+                .fetch_all(executor)
+                .await?
+                .into_iter()
+                .map(Account::try_from)
+                .collect()
+        }
+
+        /// Read every account owned by the scope's user that is either open or closed, along with
+        /// its balances
+        pub async fn db_read_many_summaries<'c, E>(
+            executor: E,
+            scope: Scope,
+            closed: bool,
+        ) -> Result<Vec<AccountSummary>, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite> + Copy,
+        {
+            let accounts = sqlx::query_as::<_, AccountSql>(
+                r#"
+                SELECT * FROM accounts
+                WHERE owner_id = ? AND closed = ?
+                ORDER BY name ASC;
+                "#
+            )
+                .bind(scope.owner_id().to_string())
+                .bind(closed)
+                .fetch_all(executor)
+                .await?;
+            let mut balances = db_balances(executor, scope).await?;
+
+            accounts
+                .into_iter()
+                .map(|row| {
+                    let account = Account::try_from(row)?;
+                    let balances = balances.remove(&account.id).unwrap_or_default();
+
+                    Ok(AccountSummary { account, balances })
+                })
+                .collect()
+        }
+
+        /// Read one account with given ID along with its balances, if it exists & is owned by the
+        /// scope's user
+        pub async fn db_read_one_summary<'c, E>(
+            executor: E,
+            scope: Scope,
+            id: Uuid,
+        ) -> Result<Option<AccountSummary>, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite> + Copy,
+        {
+            let Some(account) = Account::read_one_by_id(executor, scope, id).await? else {
+                return Ok(None);
+            };
+            let balances = db_balances(executor, scope)
+                .await?
+                .remove(&account.id)
+                .unwrap_or_default();
+
+            Ok(Some(AccountSummary { account, balances }))
+        }
+
+        /// Sum the transaction amounts for every account owned by the scope's user, split up by
+        /// whether they've cleared
+        pub async fn db_balances<'c, E>(
+            executor: E,
+            scope: Scope,
+        ) -> Result<HashMap<Uuid, Balances>, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite>,
+        {
+            let rows = sqlx::query_as::<_, (String, String, String, bool)>(
+                "SELECT account_id, amount, timestamp, pending FROM transactions WHERE owner_id = ?;"
+            )
+                .bind(scope.owner_id().to_string())
+                .fetch_all(executor)
+                .await?;
+
+            let now = Utc::now();
+            let mut balances = HashMap::<Uuid, Balances>::new();
+            for (id, amount, timestamp, pending) in rows {
+                let id = Uuid::parse_str(&id)?;
+                let amount = Decimal::from_str_exact(&amount)?;
+                let timestamp = DateTime::parse_from_rfc3339(&timestamp)?.with_timezone(&Utc);
+
+                balances.entry(id).or_default().add(amount, timestamp, pending, now);
+            }
+
+            Ok(balances)
+        }
+    }
+}
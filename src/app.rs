@@ -3,12 +3,25 @@ use leptos::*;
 use leptos_meta::*;
 use leptos_router::*;
 
-use crate::transactions::All as TransactionsAll;
+use crate::accounts::{All as AccountsAll, Detail as AccountsDetail, New as AccountsNew};
+use crate::backups::Backup;
+use crate::exports::Export;
+use crate::hoops::All as HoopsAll;
+use crate::imports::Import;
+use crate::safe_to_spend::{provide_balances_changed, Widget as SafeToSpendWidget};
+use crate::transactions::{
+    All as TransactionsAll, Detail as TransactionsDetail, Edit as TransactionsEdit,
+};
+use crate::users::{provide_time_zone, Login, Menu as UserMenu, Register, Settings};
 
 #[component]
 pub fn App() -> impl IntoView {
     // Provides context that manages stylesheets, titles, meta tags, etc.
     provide_meta_context();
+    // lets the Safe-to-Spend widget know when money has been moved
+    provide_balances_changed();
+    // lets timestamps be entered & shown in the user's time zone
+    provide_time_zone();
 
     view! {
 
@@ -29,9 +42,34 @@ pub fn App() -> impl IntoView {
             }
             .into_view()
         }>
+            <header>
+                <A href="/">Hoops</A>
+                <SafeToSpendWidget />
+                <nav>
+                    <A href="/transactions">Transactions</A>
+                    <A href="/accounts">Accounts</A>
+                    <A href="/hoops">Hoops</A>
+                </nav>
+                <UserMenu />
+            </header>
             <main>
                 <Routes>
                     <Route path="" view=HomePage/>
+                    <Route path="/register" view=Register/>
+                    <Route path="/login" view=Login/>
+                    <Route path="/settings" view=Settings/>
+                    <Route path="/export" view=Export/>
+                    <Route path="/backup" view=Backup/>
+                    <Route path="/transactions" view=Outlet>
+                        <Route path="" view=HomePage/>
+                        <Route path=":id" view=TransactionsDetail/>
+                        <Route path=":id/edit" view=TransactionsEdit/>
+                    </Route>
+                    <Route path="/accounts" view=AccountsAll/>
+                    <Route path="/accounts/new" view=AccountsNew/>
+                    <Route path="/accounts/:id" view=AccountsDetail/>
+                    <Route path="/accounts/:id/import" view=Import/>
+                    <Route path="/hoops" view=HoopsAll/>
                 </Routes>
             </main>
         </Router>
@@ -0,0 +1,170 @@
+use leptos::*;
+use leptos_router::*;
+
+use crate::backups::model::{Conflict, RestoreReport, BACKUP_PATH};
+use crate::components::errors::{action_error, error_message};
+use crate::components::input::read_file;
+
+#[cfg(feature = "ssr")]
+use crate::{
+    errors::ValidationErrors,
+    events::{publish, Change, DataEvent, Entity},
+    state::pool,
+    users::scope,
+};
+
+/// Server endpoint for restoring a backup into the logged in user's data, in a single database
+/// transaction
+///
+/// Rows keep the IDs they were saved with, unless `new_ids` is set, so a backup can only be
+/// restored as it is into a fresh account or after its data's been deleted. The backup's settings
+/// are only restored along with its IDs, since a copy is added to what's already saved. Nothing is
+/// saved for a dry run, or when any row conflicts with one already saved, & the report lists what
+/// would be restored instead.
+#[server(prefix = "/api", endpoint = "backup/restore")]
+pub async fn restore_backup(
+    contents: String,
+    new_ids: Option<bool>,
+    dry_run: Option<bool>,
+) -> Result<RestoreReport, ServerFnError> {
+    use crate::backups::model::{db_restore, Backup};
+
+    let backup =
+        Backup::parse(&contents).map_err(|message| ValidationErrors::field("file", message))?;
+    let new_ids = new_ids.unwrap_or_default();
+    let backup = match new_ids {
+        true => backup.with_new_ids(),
+        false => backup,
+    };
+    // a single event per list is enough for the user's other tabs to read them again
+    let last_account = backup.accounts.last().map(|account| account.id);
+    let last_hoop = backup.hoops.last().map(|hoop| hoop.id);
+    let last_transaction = backup.transactions.last().map(|transaction| transaction.id);
+    let scope = scope()?;
+    let pool = pool()?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    let report = db_restore(&mut tx, scope, backup, new_ids, dry_run.unwrap_or_default())
+        .await
+        .map_err(|err| {
+            logging::log!("There was an error restoring a backup: {}", err);
+            ServerFnError::ServerError(err.to_string())
+        })?;
+    if !report.restored {
+        return Ok(report);
+    }
+    tx.commit()
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+
+    let events = [
+        (Entity::Account, last_account),
+        (Entity::Hoop, last_hoop),
+        (Entity::Transaction, last_transaction),
+    ];
+    for (entity, id) in events {
+        if let Some(id) = id {
+            publish(scope, DataEvent::new(entity, Change::Created, id));
+        }
+    }
+    Ok(report)
+}
+
+/// Page for downloading a backup of everything the user's saved, & restoring one
+///
+/// A chosen backup is checked with a dry run straight away, listing what it holds & any rows
+/// already saved, before the user restores it.
+#[component]
+pub fn Backup() -> impl IntoView {
+    let contents = create_rw_signal(String::new());
+    let new_ids = create_rw_signal(false);
+    let restore = create_server_action::<RestoreBackup>();
+    // checked again once restored, so restoring it twice is reported as conflicting
+    let preview = create_resource(
+        move || (contents.get(), new_ids.get(), restore.version().get()),
+        |(contents, new_ids, _)| async move {
+            match contents.trim().is_empty() {
+                true => Ok(None),
+                false => restore_backup(contents, Some(new_ids), Some(true))
+                    .await
+                    .map(Some),
+            }
+        },
+    );
+    let restored = move || {
+        restore.value().get().and_then(|report| match report {
+            Ok(RestoreReport { restored: true, .. }) => Some(view! { <p>Backup restored</p> }),
+            _ => None,
+        })
+    };
+
+    view! {
+        <h1>Backup</h1>
+        <p>
+            "Download everything you've saved, your accounts, hoops, the money moved between
+            them, transactions, & settings, as a single file that can be restored later."
+        </p>
+        <a href=BACKUP_PATH download>Download a backup</a>
+        <h2>Restore</h2>
+        <label for="file">"Backup file:"</label>
+        <input type="file" id="file" accept=".json,application/json" on:change=move |ev| read_file(ev, move |text| contents.set(text)) />
+        <label>
+            <input type="checkbox" on:change=move |ev| new_ids.set(event_target_checked(&ev)) />
+            "Restore it as a copy, alongside what's already saved"
+        </label>
+        <Transition fallback=move || view! {<p>Loading...</p>}.into_view()>
+            {move || preview.get().map(|preview| match preview {
+                Err(err) => view! { <p class="error">{error_message(&err)}</p> }.into_view(),
+                Ok(None) => ().into_view(),
+                Ok(Some(report)) => {
+                    let RestoreReport { accounts, hoops, allocations, transactions, time_zone, conflicts, .. } = report;
+                    let blocked = !conflicts.is_empty();
+                    view! {
+                        <p>
+                            {format!(
+                                "The backup holds {accounts} accounts, {hoops} hoops, {allocations} \
+                                moves of money between hoops, & {transactions} transactions."
+                            )}
+                        </p>
+                        {time_zone.map(|time_zone| view! {
+                            <p>{format!("Your time zone will be set to {time_zone}.")}</p>
+                        })}
+                        <Conflicts conflicts />
+                        {action_error(restore)}
+                        {restored}
+                        <ActionForm action=restore>
+                            <input type="hidden" name="contents" prop:value=contents />
+                            <input type="hidden" name="new_ids" prop:value=move || new_ids.get().to_string() />
+                            <button type="submit" disabled=blocked>Restore</button>
+                        </ActionForm>
+                    }.into_view()
+                },
+            })}
+        </Transition>
+    }
+}
+
+/// The rows of a backup that are already saved, which stop it being restored
+#[component]
+fn Conflicts(conflicts: Vec<Conflict>) -> impl IntoView {
+    if conflicts.is_empty() {
+        return ().into_view();
+    }
+
+    view! {
+        <p class="error">
+            "These are already saved, so the backup can't be restored as it is. Delete them first,
+            or restore the backup as a copy."
+        </p>
+        <ul>
+            {conflicts
+                .into_iter()
+                .map(|Conflict { kind, name, .. }| view! { <li>{format!("{kind}: {name}")}</li> })
+                .collect_view()}
+        </ul>
+    }
+    .into_view()
+}
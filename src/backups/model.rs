@@ -0,0 +1,477 @@
+use std::collections::{HashMap, HashSet};
+
+use cfg_if::cfg_if;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::accounts::model::Account;
+use crate::hoops::model::{Allocation, Hoop};
+use crate::transactions::model::Transaction;
+
+/// Path of the endpoint sending the logged in user's backup as a file
+pub const BACKUP_PATH: &str = "/api/backup";
+
+/// Version of the backup's layout, bumped whenever it changes, so a backup is never read as a
+/// layout it wasn't written in
+pub const BACKUP_VERSION: u32 = 1;
+
+/// Everything a user has saved, as written to a backup file
+///
+/// Every row is kept as it's saved, IDs included, so restoring a backup gives back exactly the
+/// data it was made from.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Backup {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub settings: BackupSettings,
+    pub accounts: Vec<Account>,
+    pub hoops: Vec<Hoop>,
+    /// The ledger of money moved into & out of the hoops, oldest first
+    pub allocations: Vec<Allocation>,
+    /// Every transaction, oldest first
+    pub transactions: Vec<Transaction>,
+}
+
+/// The user's settings, as kept in a backup
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct BackupSettings {
+    pub time_zone: Tz,
+}
+
+/// Just the version of a backup, read before the rest to know whether the rest can be read
+#[derive(Deserialize)]
+struct Versioned {
+    version: u32,
+}
+
+impl Backup {
+    /// Read a backup file, or describe why it can't be restored
+    ///
+    /// Only backups in the current version's layout can be read. Every transaction & allocation
+    /// has to belong to an account or hoop in the backup, so a backup that's been edited by hand
+    /// can't leave them pointing at nothing.
+    ///
+    /// ```
+    /// use chrono::Utc;
+    /// use chrono_tz::Tz;
+    /// use rust_decimal::Decimal;
+    ///
+    /// use hoops_app::accounts::model::Account;
+    /// use hoops_app::backups::model::{Backup, BackupSettings, BACKUP_VERSION};
+    /// use hoops_app::transactions::model::Transaction;
+    ///
+    /// let account = Account::new("Checking".into(), None);
+    /// let transaction = Transaction::new(Decimal::new(-450, 2), "Corner Store".into(), Utc::now(), None, account.id, None);
+    /// let backup = Backup {
+    ///     version: BACKUP_VERSION,
+    ///     created_at: Utc::now(),
+    ///     settings: BackupSettings { time_zone: Tz::UTC },
+    ///     accounts: vec![account],
+    ///     hoops: vec![],
+    ///     allocations: vec![],
+    ///     transactions: vec![transaction.clone()],
+    /// };
+    ///
+    /// let json = serde_json::to_string(&backup).unwrap();
+    /// let read = Backup::parse(&json).unwrap();
+    /// assert_eq!(read.transactions[0].id, transaction.id);
+    /// // amounts keep their decimal places
+    /// assert_eq!(read.transactions[0].amount.to_string(), "-4.50");
+    ///
+    /// let newer = json.replacen(&format!("\"version\":{BACKUP_VERSION}"), "\"version\":99", 1);
+    /// assert_eq!(
+    ///     Backup::parse(&newer).err().as_deref(),
+    ///     Some("This backup was made by a newer version of Hoops, so it can't be restored"),
+    /// );
+    /// assert_eq!(Backup::parse("Date,Payee,Amount").err().as_deref(), Some("This isn't a Hoops backup"));
+    ///
+    /// let orphaned = Backup { accounts: vec![], ..backup };
+    /// assert_eq!(
+    ///     Backup::parse(&serde_json::to_string(&orphaned).unwrap()).err(),
+    ///     Some(format!("Transaction {} is from an account that isn't in the backup", transaction.id)),
+    /// );
+    /// ```
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let Versioned { version } =
+            serde_json::from_str(json).map_err(|_| "This isn't a Hoops backup".to_string())?;
+        if version > BACKUP_VERSION {
+            return Err(
+                "This backup was made by a newer version of Hoops, so it can't be restored".into(),
+            );
+        }
+        if version != BACKUP_VERSION {
+            return Err(format!(
+                "Version {version} backups can't be restored, only version {BACKUP_VERSION}"
+            ));
+        }
+
+        let backup: Backup =
+            serde_json::from_str(json).map_err(|err| format!("The backup can't be read: {err}"))?;
+        backup.check()?;
+
+        Ok(backup)
+    }
+
+    /// The same backup with every row given a new ID, & every reference to a row changed to
+    /// match, so it can be restored alongside the data it was made from
+    ///
+    /// ```
+    /// use chrono::Utc;
+    /// use chrono_tz::Tz;
+    /// use rust_decimal::Decimal;
+    ///
+    /// use hoops_app::accounts::model::Account;
+    /// use hoops_app::backups::model::{Backup, BackupSettings, BACKUP_VERSION};
+    /// use hoops_app::hoops::model::Hoop;
+    /// use hoops_app::transactions::model::Transaction;
+    ///
+    /// let account = Account::new("Checking".into(), None);
+    /// let hoop = Hoop::new("Groceries".into());
+    /// let transaction = Transaction::new(Decimal::new(-450, 2), "Corner Store".into(), Utc::now(), None, account.id, Some(hoop.id));
+    /// let backup = Backup {
+    ///     version: BACKUP_VERSION,
+    ///     created_at: Utc::now(),
+    ///     settings: BackupSettings { time_zone: Tz::UTC },
+    ///     accounts: vec![account.clone()],
+    ///     hoops: vec![hoop.clone()],
+    ///     allocations: vec![],
+    ///     transactions: vec![transaction.clone()],
+    /// };
+    ///
+    /// let copy = backup.with_new_ids();
+    /// let copied = &copy.transactions[0];
+    /// assert_ne!(copied.id, transaction.id);
+    /// assert_ne!(copy.accounts[0].id, account.id);
+    /// assert_eq!(copied.account_id, copy.accounts[0].id);
+    /// assert_eq!(copied.spent_from, Some(copy.hoops[0].id));
+    /// assert_eq!(copied.payee, "Corner Store");
+    /// ```
+    pub fn with_new_ids(self) -> Self {
+        let mut ids = HashMap::new();
+        let mut new_id = |id: Uuid| *ids.entry(id).or_insert_with(Uuid::new_v4);
+
+        Self {
+            accounts: self
+                .accounts
+                .into_iter()
+                .map(|account| Account {
+                    id: new_id(account.id),
+                    ..account
+                })
+                .collect(),
+            hoops: self
+                .hoops
+                .into_iter()
+                .map(|hoop| Hoop {
+                    id: new_id(hoop.id),
+                    ..hoop
+                })
+                .collect(),
+            allocations: self
+                .allocations
+                .into_iter()
+                .map(|allocation| Allocation {
+                    id: new_id(allocation.id),
+                    hoop_id: new_id(allocation.hoop_id),
+                    counterpart_hoop_id: allocation.counterpart_hoop_id.map(&mut new_id),
+                    ..allocation
+                })
+                .collect(),
+            transactions: self
+                .transactions
+                .into_iter()
+                .map(|transaction| Transaction {
+                    id: new_id(transaction.id),
+                    account_id: new_id(transaction.account_id),
+                    spent_from: transaction.spent_from.map(&mut new_id),
+                    ..transaction
+                })
+                .collect(),
+            ..self
+        }
+    }
+
+    /// Make sure no two rows share an ID, & every row only refers to rows in the backup
+    fn check(&self) -> Result<(), String> {
+        let mut ids = HashSet::new();
+        let all_ids = self
+            .accounts
+            .iter()
+            .map(|account| account.id)
+            .chain(self.hoops.iter().map(|hoop| hoop.id))
+            .chain(self.allocations.iter().map(|allocation| allocation.id))
+            .chain(self.transactions.iter().map(|transaction| transaction.id));
+        for id in all_ids {
+            if !ids.insert(id) {
+                return Err(format!("More than one row in the backup has ID {id}"));
+            }
+        }
+
+        let accounts: HashSet<Uuid> = self.accounts.iter().map(|account| account.id).collect();
+        let hoops: HashSet<Uuid> = self.hoops.iter().map(|hoop| hoop.id).collect();
+        for allocation in &self.allocations {
+            let moved = Some(allocation.hoop_id)
+                .into_iter()
+                .chain(allocation.counterpart_hoop_id);
+            for hoop_id in moved {
+                if !hoops.contains(&hoop_id) {
+                    return Err(format!(
+                        "Allocation {} moved money for a hoop that isn't in the backup",
+                        allocation.id
+                    ));
+                }
+            }
+        }
+        let mut imported = HashSet::new();
+        for transaction in &self.transactions {
+            if !accounts.contains(&transaction.account_id) {
+                return Err(format!(
+                    "Transaction {} is from an account that isn't in the backup",
+                    transaction.id
+                ));
+            }
+            if transaction
+                .spent_from
+                .is_some_and(|hoop_id| !hoops.contains(&hoop_id))
+            {
+                return Err(format!(
+                    "Transaction {} was spent from a hoop that isn't in the backup",
+                    transaction.id
+                ));
+            }
+            if let Some(import_id) = &transaction.import_id {
+                if !imported.insert((transaction.account_id, import_id)) {
+                    return Err(format!(
+                        "More than one transaction in an account has import ID {import_id}"
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A row in a backup that can't be restored with its ID, since a row with that ID is already saved
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Conflict {
+    /// What kind of row it is, like `account` or `transaction`
+    pub kind: String,
+    pub id: Uuid,
+    /// The row's name or payee, to show the user which it is
+    pub name: String,
+}
+
+/// What restoring a backup did, or would do for a dry run
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct RestoreReport {
+    pub accounts: usize,
+    pub hoops: usize,
+    pub allocations: usize,
+    pub transactions: usize,
+    /// The time zone the user's setting is changed to, or `None` when a copy leaves it alone
+    pub time_zone: Option<Tz>,
+    /// Every row that can't be restored with its ID, which stops the whole backup being restored
+    pub conflicts: Vec<Conflict>,
+    /// Whether the backup was saved, which it isn't for a dry run or when there are conflicts
+    pub restored: bool,
+}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use sqlx::{Executor, QueryBuilder, Sqlite, SqliteConnection};
+
+        use crate::models::{Create, Scope, Table};
+
+        /// Read everything the scope's user has saved as a backup, along with their settings
+        pub async fn db_read_backup<'c, E>(
+            executor: E,
+            scope: Scope,
+            time_zone: Tz,
+        ) -> Result<Backup, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite> + Copy,
+        {
+            use crate::accounts::model::db_read_all as db_read_all_accounts;
+            use crate::hoops::model::{db_read_all as db_read_all_hoops, db_read_all_allocations};
+            use crate::transactions::model::db_read_all as db_read_all_transactions;
+
+            Ok(Backup {
+                version: BACKUP_VERSION,
+                created_at: Utc::now(),
+                settings: BackupSettings { time_zone },
+                accounts: db_read_all_accounts(executor, scope).await?,
+                hoops: db_read_all_hoops(executor, scope).await?,
+                allocations: db_read_all_allocations(executor, scope).await?,
+                transactions: db_read_all_transactions(executor, scope).await?,
+            })
+        }
+
+        /// Restore a backup into the scope's user's data, & set their settings to the backup's
+        /// unless it's restored as a `copy`
+        ///
+        /// Rows keep their IDs, so nothing is saved if any of them is already saved, & every
+        /// conflicting row is reported instead. A backup given new IDs with
+        /// [`Backup::with_new_ids`] never conflicts, & is restored as a copy alongside what's
+        /// already saved, so it leaves the user's settings alone. A dry run only reports what would
+        /// be restored.
+        /// This should be run inside a database transaction, so the backup is restored all
+        /// together or not at all.
+        ///
+        /// ```
+        /// use chrono_tz::Tz;
+        /// use rust_decimal::Decimal;
+        ///
+        /// use hoops_app::backups::model::{db_read_backup, db_restore};
+        /// use hoops_app::hoops::model::{db_move_funds, Hoop};
+        /// use hoops_app::models::{doc_fixture, Create};
+        /// use hoops_app::users::model::db_read_one_by_username;
+        ///
+        /// # #[tokio::main]
+        /// # async fn main() -> Result<(), anyhow::Error> {
+        /// let (pool, scope, _) = doc_fixture().await?;
+        /// let hoop = Hoop::new("Groceries".into());
+        /// let hoop_id = hoop.id;
+        /// Hoop::create_one(&pool, scope, hoop).await?;
+        /// db_move_funds(&mut *pool.acquire().await?, scope, None, Some(hoop_id), Decimal::new(50, 0)).await?;
+        ///
+        /// let sydney: Tz = "Australia/Sydney".parse().unwrap();
+        /// let backup = db_read_backup(&pool, scope, sydney).await?;
+        /// assert_eq!((backup.accounts.len(), backup.hoops.len(), backup.allocations.len()), (1, 1, 1));
+        ///
+        /// // every row is already saved, so nothing is restored
+        /// let mut tx = pool.begin().await?;
+        /// let report = db_restore(&mut tx, scope, backup.clone(), false, false).await?;
+        /// assert!(!report.restored);
+        /// assert_eq!(report.time_zone, Some(sydney));
+        /// let conflicts: Vec<_> = report.conflicts.iter().map(|c| (c.kind.as_str(), c.name.as_str())).collect();
+        /// assert_eq!(conflicts, [("account", "Checking"), ("hoop", "Groceries"), ("allocation", "50 moved into Groceries")]);
+        ///
+        /// // a dry run reports what would be restored, without saving it
+        /// let copy = backup.with_new_ids();
+        /// let report = db_restore(&mut tx, scope, copy.clone(), true, true).await?;
+        /// assert!(report.conflicts.is_empty() && !report.restored);
+        /// assert_eq!((report.accounts, report.time_zone), (1, None));
+        ///
+        /// let report = db_restore(&mut tx, scope, copy, true, false).await?;
+        /// tx.commit().await?;
+        /// assert!(report.restored);
+        /// assert_eq!(db_read_backup(&pool, scope, sydney).await?.accounts.len(), 2);
+        /// // a copy leaves the user's time zone as it was
+        /// let user = db_read_one_by_username(&pool, "alice").await?.unwrap();
+        /// assert_eq!(user.time_zone, "UTC");
+        /// # Ok(())
+        /// # }
+        /// ```
+        pub async fn db_restore(
+            conn: &mut SqliteConnection,
+            scope: Scope,
+            backup: Backup,
+            copy: bool,
+            dry_run: bool,
+        ) -> Result<RestoreReport, anyhow::Error> {
+            use crate::users::model::db_update_time_zone;
+
+            let conflicts = db_find_conflicts(conn, &backup).await?;
+            let Backup {
+                settings,
+                accounts,
+                hoops,
+                allocations,
+                transactions,
+                ..
+            } = backup;
+            let mut report = RestoreReport {
+                accounts: accounts.len(),
+                hoops: hoops.len(),
+                allocations: allocations.len(),
+                transactions: transactions.len(),
+                time_zone: (!copy).then_some(settings.time_zone),
+                conflicts,
+                restored: false,
+            };
+            if dry_run || !report.conflicts.is_empty() {
+                return Ok(report);
+            }
+
+            // each row is saved after the rows it refers to
+            Account::create_many(&mut *conn, scope, accounts).await?;
+            Hoop::create_many(&mut *conn, scope, hoops).await?;
+            Allocation::create_many(&mut *conn, scope, allocations).await?;
+            Transaction::create_many(&mut *conn, scope, transactions).await?;
+            if let Some(time_zone) = report.time_zone {
+                db_update_time_zone(&mut *conn, scope.owner_id(), time_zone).await?;
+            }
+            report.restored = true;
+
+            Ok(report)
+        }
+
+        /// Every row in the backup with the same ID as one already saved, by any user
+        async fn db_find_conflicts(
+            conn: &mut SqliteConnection,
+            backup: &Backup,
+        ) -> Result<Vec<Conflict>, anyhow::Error> {
+            let hoop_names: HashMap<Uuid, &str> =
+                backup.hoops.iter().map(|hoop| (hoop.id, hoop.name.as_str())).collect();
+            let mut conflicts = Vec::new();
+
+            let accounts = backup.accounts.iter().map(|account| (account.id, account.name.clone()));
+            conflicts.extend(db_saved::<Account>(conn, "account", accounts).await?);
+            let hoops = backup.hoops.iter().map(|hoop| (hoop.id, hoop.name.clone()));
+            conflicts.extend(db_saved::<Hoop>(conn, "hoop", hoops).await?);
+            let allocations = backup.allocations.iter().map(|allocation| {
+                let hoop = hoop_names.get(&allocation.hoop_id).copied().unwrap_or_default();
+                let name = match allocation.amount.is_sign_negative() {
+                    true => format!("{} moved out of {hoop}", -allocation.amount),
+                    false => format!("{} moved into {hoop}", allocation.amount),
+                };
+                (allocation.id, name)
+            });
+            conflicts.extend(db_saved::<Allocation>(conn, "allocation", allocations).await?);
+            let transactions = backup
+                .transactions
+                .iter()
+                .map(|transaction| (transaction.id, transaction.payee.clone()));
+            conflicts.extend(db_saved::<Transaction>(conn, "transaction", transactions).await?);
+
+            Ok(conflicts)
+        }
+
+        /// The rows with each ID that's already saved in the type's table, as conflicts of the
+        /// given kind
+        async fn db_saved<T: Table>(
+            conn: &mut SqliteConnection,
+            kind: &str,
+            rows: impl Iterator<Item = (Uuid, String)>,
+        ) -> Result<Vec<Conflict>, anyhow::Error> {
+            let mut names: HashMap<Uuid, String> = rows.collect();
+            if names.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            // every ID is bound at once as a JSON array, instead of one at a time
+            let ids = serde_json::to_string(&names.keys().collect::<Vec<_>>())?;
+            let mut query = QueryBuilder::<Sqlite>::new("SELECT id FROM ");
+            query.push(T::TABLE);
+            query.push(" WHERE id IN (SELECT value FROM json_each(");
+            query.push_bind(ids);
+            query.push(")) ORDER BY id;");
+            let saved: Vec<String> = query.build_query_scalar().fetch_all(conn).await?;
+
+            saved
+                .into_iter()
+                .map(|id| {
+                    let id = Uuid::parse_str(&id)?;
+                    Ok(Conflict {
+                        kind: kind.to_string(),
+                        id,
+                        name: names.remove(&id).unwrap_or_default(),
+                    })
+                })
+                .collect()
+        }
+    }
+}
@@ -1,19 +1,40 @@
-use chrono::naive::NaiveDateTime;
+use chrono::{DateTime, Utc};
 use leptos::*;
 
 use crate::components::input::{Input, InputType};
+use crate::errors::ValidationErrors;
+use crate::time::format_input;
+use crate::users::use_time_zone;
 
 /// An input component for entering a date & time
+///
+/// The `value` is shown & entered in the user's time zone, so the server converts what's entered
+/// back to UTC with the same time zone. The input is only rendered once the time zone is known.
 #[component]
 pub fn DateTimePicker(
     name: String,
     label: String,
-    value: NaiveDateTime,
+    #[prop(into)] value: MaybeSignal<DateTime<Utc>>,
+    #[prop(optional, into)] errors: Option<Signal<ValidationErrors>>,
     #[prop(attrs)] attrs: Vec<(&'static str, Attribute)>,
 ) -> impl IntoView {
-    let value = value.format("%Y-%m-%dT%H:%M:%S").to_string();
+    let time_zone = use_time_zone();
+    let errors = errors.unwrap_or_default();
 
     view! {
-        <Input {..attrs} input_type=InputType::DateTime name label value attr:step=1 />
+        <Suspense fallback=move || view! {<p>Loading...</p>}.into_view()>
+            {
+                let name = name.clone();
+                let label = label.clone();
+                // passed on as a prop, the same way `InputAmount` does
+                let attrs = attrs.iter().cloned().chain([("step", 1.into_attribute())]).collect::<Vec<_>>();
+                move || {
+                    let value = format_input(value.get(), time_zone.get());
+                    view! {
+                        <Input attrs=attrs.clone() input_type=InputType::DateTime name=name.clone() label=label.clone() value errors />
+                    }
+                }
+            }
+        </Suspense>
     }
 }
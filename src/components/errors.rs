@@ -0,0 +1,91 @@
+use leptos::{leptos_server::Submission, *};
+
+use crate::errors::ValidationErrors;
+
+/// Renders the error from the last submission of an action, if there was one
+pub fn action_error<I: 'static, O: Clone + 'static>(
+    action: Action<I, Result<O, ServerFnError>>,
+) -> impl IntoView {
+    move || {
+        action
+            .value()
+            .get()
+            .and_then(Result::err)
+            .map(|err| view! { <p class="error">{error_message(&err)}</p> })
+    }
+}
+
+/// Renders the error from the last submission of an action, unless it was validation errors
+///
+/// For forms that show validation errors next to their fields, so they aren't shown twice.
+pub fn action_server_error<I: 'static, O: Clone + 'static>(
+    action: Action<I, Result<O, ServerFnError>>,
+) -> impl IntoView {
+    move || {
+        action
+            .value()
+            .get()
+            .and_then(Result::err)
+            .filter(|err| ValidationErrors::from_server_fn_error(err).is_none())
+            .map(|err| view! { <p class="error">{err.to_string()}</p> })
+    }
+}
+
+/// The validation errors from the last submission of an action, which are empty if there were
+/// none
+pub fn action_validation_errors<I: 'static, O: Clone + 'static>(
+    action: Action<I, Result<O, ServerFnError>>,
+) -> Signal<ValidationErrors> {
+    Signal::derive(move || validation_errors(action.value().get()))
+}
+
+/// The validation errors from the last submission of a multi-action, which are empty if there were
+/// none
+pub fn multi_action_validation_errors<I: 'static, O: Clone + 'static>(
+    action: MultiAction<I, Result<O, ServerFnError>>,
+) -> Signal<ValidationErrors> {
+    Signal::derive(move || {
+        action
+            .submissions()
+            .get()
+            .last()
+            .map(|submission| validation_errors(submission.value.get()))
+            .unwrap_or_default()
+    })
+}
+
+/// The validation errors from a single submission of a multi-action, which are empty if there
+/// were none
+pub fn submission_validation_errors<I: 'static, O: Clone + 'static>(
+    submission: Submission<I, Result<O, ServerFnError>>,
+) -> Signal<ValidationErrors> {
+    Signal::derive(move || validation_errors(submission.value.get()))
+}
+
+/// Renders every validation error for a single form field
+pub fn field_errors(errors: Signal<ValidationErrors>, field: String) -> impl IntoView {
+    move || {
+        errors.with(|errors| {
+            errors
+                .get(&field)
+                .iter()
+                .map(|message| view! { <p class="error">{message.clone()}</p> })
+                .collect_view()
+        })
+    }
+}
+
+/// The message to show a user for an error, without any of the encoding used for validation
+/// errors
+pub fn error_message(err: &ServerFnError) -> String {
+    ValidationErrors::from_server_fn_error(err)
+        .map(|errors| errors.to_string())
+        .unwrap_or_else(|| err.to_string())
+}
+
+fn validation_errors<O>(result: Option<Result<O, ServerFnError>>) -> ValidationErrors {
+    result
+        .and_then(Result::err)
+        .and_then(|err| ValidationErrors::from_server_fn_error(&err))
+        .unwrap_or_default()
+}
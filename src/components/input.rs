@@ -1,32 +1,101 @@
 use leptos::*;
 
+use crate::components::errors::field_errors;
+use crate::errors::ValidationErrors;
+
 /// Reusable amount input component
+///
+/// Amounts are signed, with money leaving an account entered as a negative amount.
 #[component]
 pub fn InputAmount(
     name: String,
     label: String,
     #[prop(optional)] value: String,
+    #[prop(optional, into)] errors: Option<Signal<ValidationErrors>>,
     #[prop(attrs)] attrs: Vec<(&'static str, Attribute)>,
 ) -> impl IntoView {
+    // passed on as a prop, since spreading attributes onto a component drops them when rendered
+    // on the server
+    let attrs = attrs
+        .into_iter()
+        .chain([("step", 0.01.into_attribute())])
+        .collect::<Vec<_>>();
+
     view! {
-        <Input {..attrs} name label value input_type=InputType::Number attr:step=0.01 attr:min=0.00 />
+        <Input attrs name label value errors=errors.unwrap_or_default() input_type=InputType::Number />
     }
 }
 
 /// Reusable text input component
+///
+/// When given the validation errors from a form's last submission, any errors for this input's
+/// field are shown after it & the input is marked as invalid. Only the error state changes, so
+/// whatever the user typed is kept for them to correct.
 #[component]
 pub fn Input(
     name: String,
     label: String,
     #[prop(default = InputType::Text)] input_type: InputType,
     #[prop(optional)] value: String,
+    #[prop(optional, into)] errors: Option<Signal<ValidationErrors>>,
     #[prop(attrs)] attrs: Vec<(&'static str, Attribute)>,
 ) -> impl IntoView {
     let input_type_str: String = input_type.into();
+    let errors = errors.unwrap_or_default();
+    let field = error_field(&name).to_string();
+    let invalid = Signal::derive(move || errors.with(|errors| !errors.get(&field).is_empty()));
 
     view! {
         <label for=&name>{&label}</label>
-        <input {..attrs} id=&name name=&name type=&input_type_str value=&value />
+        <input
+            {..attrs}
+            id=&name
+            name=&name
+            type=&input_type_str
+            value=&value
+            class:invalid=invalid
+            aria-invalid=move || invalid.get().to_string()
+        />
+        {field_errors(errors, error_field(&name).to_string())}
+    }
+}
+
+/// The field an input's validation errors are reported under, which for an input named as part of
+/// a nested form, like `form[payee]`, is its name within the form
+///
+/// ```
+/// use hoops_app::components::input::error_field;
+///
+/// assert_eq!(error_field("form[payee]"), "payee");
+/// assert_eq!(error_field("payee"), "payee");
+/// ```
+pub fn error_field(name: &str) -> &str {
+    name.rsplit('[')
+        .next()
+        .map_or(name, |field| field.trim_end_matches(']'))
+}
+
+/// Read the file chosen in a file input as text, handing it to `on_read`
+///
+/// Only read once the app is running in the browser.
+pub fn read_file(ev: ev::Event, on_read: impl Fn(String) + 'static) {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "hydrate")] {
+            use wasm_bindgen_futures::JsFuture;
+            use web_sys::HtmlInputElement;
+
+            let input = event_target::<HtmlInputElement>(&ev);
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            spawn_local(async move {
+                if let Some(text) = JsFuture::from(file.text()).await.ok().and_then(|text| text.as_string()) {
+                    on_read(text);
+                }
+            });
+        } else {
+            let _ = (ev, on_read);
+        }
     }
 }
 
@@ -35,17 +104,19 @@ pub enum InputType {
     Number,
     Password,
     Text,
+    Search,
     Date,
     DateTime,
 }
 
-impl Into<String> for InputType {
-    fn into(self) -> String {
-        match self {
+impl From<InputType> for String {
+    fn from(value: InputType) -> Self {
+        match value {
             InputType::Hidden => String::from("hidden"),
             InputType::Number => String::from("number"),
             InputType::Password => String::from("password"),
             InputType::Text => String::from("text"),
+            InputType::Search => String::from("search"),
             InputType::Date => String::from("date"),
             InputType::DateTime => String::from("datetime-local"),
         }
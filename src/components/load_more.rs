@@ -0,0 +1,67 @@
+use leptos::{html::Button, *};
+
+/// A button for loading the next page of a list, which presses itself whenever it scrolls into
+/// view, so the list keeps growing as the user scrolls to the end of it
+///
+/// It's disabled while `loading`, & once that page has loaded the one after it is loaded too if
+/// the button is still in view, until the list fills the screen. The list should stop rendering
+/// the button once there are no more pages.
+#[component]
+pub fn LoadMore(
+    #[prop(into)] on_load: Callback<()>,
+    #[prop(into)] loading: Signal<bool>,
+) -> impl IntoView {
+    let button = create_node_ref::<Button>();
+    let visible = create_rw_signal(false);
+    button.on_load(move |button| observe_visibility(button, visible));
+
+    // a page is never loaded while the last one still is, however the button was pressed
+    let load = move || {
+        if !loading.get_untracked() {
+            on_load(());
+        }
+    };
+    create_effect(move |_| {
+        // checked again once a page has loaded, in case the button's still in view
+        loading.track();
+        if visible.get() {
+            load();
+        }
+    });
+
+    view! {
+        <button type="button" class="load-more" node_ref=button disabled=loading on:click=move |_| load()>
+            {move || if loading.get() { "Loading..." } else { "Load more" }}
+        </button>
+    }
+}
+
+/// Keep `visible` set to whether the button is on the screen, for as long as it's rendered
+///
+/// Only watched once the app is running in the browser.
+fn observe_visibility(button: HtmlElement<Button>, visible: RwSignal<bool>) {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "hydrate")] {
+            use wasm_bindgen::{closure::Closure, JsCast};
+            use web_sys::{IntersectionObserver, IntersectionObserverEntry};
+
+            let callback = Closure::<dyn Fn(js_sys::Array)>::new(move |entries: js_sys::Array| {
+                if let Some(entry) = entries.iter().last() {
+                    let entry = entry.unchecked_into::<IntersectionObserverEntry>();
+                    visible.set(entry.is_intersecting());
+                }
+            });
+            let Ok(observer) = IntersectionObserver::new(callback.as_ref().unchecked_ref()) else {
+                return;
+            };
+            observer.observe(&button);
+
+            on_cleanup(move || {
+                observer.disconnect();
+                drop(callback);
+            });
+        } else {
+            let _ = (button, visible);
+        }
+    }
+}
@@ -1,3 +1,5 @@
 /// A collection of widely reusable components not coupled tightly to any one feature
 pub mod datepicker;
+pub mod errors;
 pub mod input;
+pub mod load_more;
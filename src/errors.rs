@@ -0,0 +1,99 @@
+//! Errors shared between server functions & the components calling them
+use std::collections::BTreeMap;
+use std::fmt;
+
+use leptos::ServerFnError;
+use serde::{Deserialize, Serialize};
+
+/// Prefix marking a `ServerFnError::ServerError` message as encoded [`ValidationErrors`]
+const VALIDATION_ERRORS_PREFIX: &str = "validation errors: ";
+
+/// Errors caused by the values given for a form's fields, keyed by field name, so each can be
+/// shown next to its field instead of for the form as a whole.
+///
+/// Server functions can only return a `ServerFnError`, so validation errors are encoded into its
+/// message & decoded again on the client.
+///
+/// ```
+/// use leptos::ServerFnError;
+///
+/// use hoops_app::errors::ValidationErrors;
+///
+/// let mut errors = ValidationErrors::default();
+/// assert_eq!(errors.clone().check(), Ok(()));
+///
+/// errors.add("amount", "Enter an amount other than zero");
+/// errors.add("payee", "Enter a payee");
+/// errors.add("amount", "Amounts can't have more than 2 decimal places");
+/// assert_eq!(errors.get("amount").len(), 2);
+/// assert!(errors.get("timestamp").is_empty());
+///
+/// let server_err = ServerFnError::from(errors.clone());
+/// assert_eq!(ValidationErrors::from_server_fn_error(&server_err), Some(errors));
+/// assert_eq!(
+///     ValidationErrors::from_server_fn_error(&ServerFnError::ServerError("oops".to_string())),
+///     None,
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ValidationErrors(BTreeMap<String, Vec<String>>);
+
+impl ValidationErrors {
+    /// Errors holding a single message for a single field
+    pub fn field(field: impl Into<String>, message: impl ToString) -> Self {
+        let mut errors = Self::default();
+        errors.add(field, message);
+        errors
+    }
+
+    /// Add a message to the errors for a field
+    pub fn add(&mut self, field: impl Into<String>, message: impl ToString) {
+        self.0
+            .entry(field.into())
+            .or_default()
+            .push(message.to_string());
+    }
+
+    /// Every message for a field, which is empty if the field is valid
+    pub fn get(&self, field: &str) -> &[String] {
+        self.0.get(field).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// `Ok` if there are no errors, otherwise the errors themselves
+    pub fn check(self) -> Result<(), Self> {
+        match self.is_empty() {
+            true => Ok(()),
+            false => Err(self),
+        }
+    }
+
+    /// Decode validation errors from the error returned by a server function, if it holds them
+    pub fn from_server_fn_error(err: &ServerFnError) -> Option<Self> {
+        match err {
+            ServerFnError::ServerError(message) => message
+                .strip_prefix(VALIDATION_ERRORS_PREFIX)
+                .and_then(|json| serde_json::from_str(json).ok()),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages: Vec<_> = self.0.values().flatten().map(String::as_str).collect();
+        f.write_str(&messages.join("; "))
+    }
+}
+
+impl From<ValidationErrors> for ServerFnError {
+    fn from(errors: ValidationErrors) -> Self {
+        match serde_json::to_string(&errors) {
+            Ok(json) => ServerFnError::ServerError(format!("{VALIDATION_ERRORS_PREFIX}{json}")),
+            Err(_) => ServerFnError::ServerError(errors.to_string()),
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! Live updates of the user's data, sent from the server whenever it's changed so every tab &
+//! device the user has open can show the change without reloading
+//!
+//! The server broadcasts an event after each change is saved, & streams the events for the logged
+//! in user's data to the client as Server-Sent Events from `/api/events`.
+use leptos::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Path of the endpoint streaming the logged in user's events
+pub const EVENTS_PATH: &str = "/api/events";
+
+/// The kinds of data an event can be about
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Entity {
+    Transaction,
+    Account,
+    Hoop,
+}
+
+/// What happened to the data
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Change {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// A change to one of the user's transactions, accounts, or hoops
+///
+/// Only says which one changed, so clients read it again if they're showing it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DataEvent {
+    pub entity: Entity,
+    pub change: Change,
+    pub id: Uuid,
+}
+
+impl DataEvent {
+    pub const fn new(entity: Entity, change: Change, id: Uuid) -> Self {
+        Self { entity, change, id }
+    }
+}
+
+/// The latest event for the logged in user's data, streamed for as long as the calling component
+/// is rendered
+///
+/// Only subscribes once the app is running in the browser, so it's always `None` on the server.
+pub fn use_data_events() -> ReadSignal<Option<DataEvent>> {
+    let (event, set_event) = create_signal(None);
+
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "hydrate")] {
+            use wasm_bindgen::{closure::Closure, JsCast};
+            use web_sys::{EventSource, MessageEvent};
+
+            // the browser reconnects by itself if the connection drops, but gives up if the user
+            // isn't logged in
+            if let Ok(source) = EventSource::new(EVENTS_PATH) {
+                let on_message = Closure::<dyn Fn(MessageEvent)>::new(move |message: MessageEvent| {
+                    let event = message
+                        .data()
+                        .as_string()
+                        .and_then(|data| serde_json::from_str(&data).ok());
+                    if let Some(event) = event {
+                        set_event.set(Some(event));
+                    }
+                });
+                source.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+                on_cleanup(move || {
+                    source.close();
+                    drop(on_message);
+                });
+            }
+        } else {
+            let _ = set_event;
+        }
+    }
+
+    event
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use futures::{stream, Stream};
+        use tokio::sync::broadcast::{self, error::RecvError};
+
+        use crate::models::Scope;
+
+        /// Most events kept for subscribers that haven't read them yet
+        const CAPACITY: usize = 256;
+
+        /// Channel the server broadcasts every user's events on
+        ///
+        /// Each subscriber only receives the events for its own scope's data.
+        ///
+        /// ```
+        /// use futures::StreamExt;
+        /// use uuid::Uuid;
+        ///
+        /// use hoops_app::events::{Change, DataEvent, Entity, Events};
+        /// use hoops_app::models::Scope;
+        ///
+        /// # #[tokio::main]
+        /// # async fn main() {
+        /// let events = Events::new();
+        /// let (alice, bob) = (Scope::new(Uuid::new_v4()), Scope::new(Uuid::new_v4()));
+        /// let mut received = Box::pin(events.subscribe(alice));
+        ///
+        /// let theirs = DataEvent::new(Entity::Transaction, Change::Created, Uuid::new_v4());
+        /// let ours = DataEvent::new(Entity::Hoop, Change::Updated, Uuid::new_v4());
+        /// events.publish(bob, theirs);
+        /// events.publish(alice, ours);
+        ///
+        /// assert_eq!(received.next().await, Some(ours));
+        /// # }
+        /// ```
+        #[derive(Clone, Debug)]
+        pub struct Events {
+            sender: broadcast::Sender<(Uuid, DataEvent)>,
+        }
+
+        impl Events {
+            pub fn new() -> Self {
+                let (sender, _) = broadcast::channel(CAPACITY);
+                Self { sender }
+            }
+
+            /// Send the event to every subscriber for the scope's data
+            ///
+            /// It's fine for nobody to be listening, in which case the event is dropped.
+            pub fn publish(&self, scope: Scope, event: DataEvent) {
+                let _ = self.sender.send((scope.owner_id(), event));
+            }
+
+            /// Receive the events for the scope's data, from now until the server shuts down
+            ///
+            /// A subscriber too slow to keep up misses the oldest events it hasn't read.
+            pub fn subscribe(&self, scope: Scope) -> impl Stream<Item = DataEvent> {
+                stream::unfold(self.sender.subscribe(), move |mut receiver| async move {
+                    loop {
+                        match receiver.recv().await {
+                            Ok((owner_id, event)) if owner_id == scope.owner_id() => {
+                                return Some((event, receiver));
+                            }
+                            Ok(_) => continue,
+                            Err(RecvError::Lagged(missed)) => {
+                                log::warn!("An events subscriber missed {missed} events");
+                            }
+                            Err(RecvError::Closed) => return None,
+                        }
+                    }
+                })
+            }
+        }
+
+        impl Default for Events {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        /// Let the logged in user's other tabs & devices know their data changed
+        ///
+        /// Uses the events channel provided to server functions as context, skipping the event if
+        /// there isn't one.
+        pub fn publish(scope: Scope, event: DataEvent) {
+            if let Some(events) = use_context::<Events>() {
+                events.publish(scope, event);
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use leptos::*;
+
+use crate::components::input::{Input, InputType};
+use crate::exports::model::{ExportFormat, DEFAULT_CURRENCY, EXPORT_PATH};
+
+/// Page for downloading the user's data, for taxes or plain text accounting tools
+///
+/// The form is sent straight to the export endpoint instead of a server function, so the browser
+/// saves the file as it's streamed.
+#[component]
+pub fn Export() -> impl IntoView {
+    view! {
+        <h1>Export</h1>
+        <p>
+            "Download your transactions, either all of them or those in a range of days. JSON
+            exports also hold your accounts & hoops, while ledger & beancount journals book
+            spending from each hoop to a sub-account of Expenses."
+        </p>
+        <form method="get" action=EXPORT_PATH>
+            <label for="format">Format</label>
+            <select id="format" name="format">
+                {ExportFormat::ALL
+                    .iter()
+                    .map(|format| view! { <option value=format.name()>{format.label()}</option> })
+                    .collect_view()}
+            </select>
+            <Input input_type=InputType::Date name="from".into() label="From".into() />
+            <Input input_type=InputType::Date name="to".into() label="To".into() />
+            <Input
+                name="currency".into()
+                label="Currency of journal amounts".into()
+                value=DEFAULT_CURRENCY.into()
+                attr:pattern="[A-Za-z][A-Za-z0-9]{1,23}"
+                attr:required=true
+            />
+            <button type="submit">Export</button>
+        </form>
+    }
+}
@@ -0,0 +1,272 @@
+//! Writing transactions as the plain text journals read by `ledger` & `beancount`
+//!
+//! Each transaction is an entry moving its amount between the account it was made from, under
+//! `Assets`, & what it was for. Spending from a hoop is booked to the hoop's sub-account of
+//! `Expenses`, so the journal totals spending by what the money was set aside for, while the rest
+//! is booked as uncategorized spending or income.
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::accounts::model::Account;
+use crate::hoops::model::Hoop;
+use crate::transactions::model::Transaction;
+
+const ASSETS: &str = "Assets";
+const EXPENSES: &str = "Expenses";
+const INCOME: &str = "Income";
+/// Sub-account of `Expenses` & `Income` for transactions that weren't spent from a hoop
+const UNCATEGORIZED: &str = "Uncategorized";
+
+/// The plain text accounting tools a journal can be written for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dialect {
+    Ledger,
+    Beancount,
+}
+
+impl Dialect {
+    /// Full name of the account with the given parts, each written so the tool reads it as a
+    /// single part of the name
+    ///
+    /// `ledger` allows most characters in a name, while `beancount` only allows letters, digits,
+    /// & dashes, with each part starting with a capital letter or digit.
+    ///
+    /// ```
+    /// use hoops_app::exports::journal::Dialect;
+    ///
+    /// let parts = ["Expenses", "Day-to-day: food & drink"];
+    /// assert_eq!(Dialect::Ledger.account(&parts), "Expenses:Day-to-day- food & drink");
+    /// assert_eq!(Dialect::Beancount.account(&parts), "Expenses:Day-To-Day-Food-Drink");
+    /// assert_eq!(Dialect::Beancount.account(&["Assets", "£"]), "Assets:Unnamed");
+    /// ```
+    pub fn account(self, parts: &[&str]) -> String {
+        parts
+            .iter()
+            .map(|part| {
+                let name = match self {
+                    Dialect::Ledger => part
+                        .replace(':', "-")
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    Dialect::Beancount => part
+                        .split(|c: char| !c.is_alphanumeric())
+                        .filter(|word| !word.is_empty())
+                        .map(capitalize)
+                        .collect::<Vec<_>>()
+                        .join("-"),
+                };
+                match name.is_empty() {
+                    true => "Unnamed".to_string(),
+                    false => name,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+}
+
+/// A journal of transactions being written one entry at a time, in date order
+///
+/// `beancount` needs every account opened before it's used, so each is opened by the first entry
+/// using it.
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use chrono_tz::Tz;
+/// use rust_decimal::Decimal;
+///
+/// use hoops_app::accounts::model::Account;
+/// use hoops_app::exports::journal::{Dialect, Journal};
+/// use hoops_app::hoops::model::Hoop;
+/// use hoops_app::transactions::model::Transaction;
+///
+/// let checking = Account::new("Checking".into(), None);
+/// let groceries = Hoop::new("Groceries".into());
+/// let timestamp = Utc.with_ymd_and_hms(2024, 1, 31, 23, 0, 0).unwrap();
+/// let shop = Transaction {
+///     pending: true,
+///     ..Transaction::new(Decimal::new(-450, 2), "Corner \"Store\"".into(), timestamp, Some("Milk".into()), checking.id, Some(groceries.id))
+/// };
+/// let paycheck = Transaction::new(Decimal::new(2500, 0), "Employer".into(), timestamp, None, checking.id, None);
+///
+/// let sydney = "Australia/Sydney".parse().unwrap();
+/// let mut ledger = Journal::new(Dialect::Ledger, "USD", sydney, &[checking.clone()], &[groceries.clone()]);
+/// assert_eq!(ledger.entry(&shop), format!("\
+/// 2024-02-01 ! Corner \"Store\"  ; Milk
+///     ; id: {}
+///     Assets:Checking  -4.50 USD
+///     Expenses:Groceries
+///
+/// ", shop.id));
+///
+/// let mut beancount = Journal::new(Dialect::Beancount, "USD", Tz::UTC, &[checking], &[groceries]);
+/// assert!(beancount.header().contains("option \"operating_currency\" \"USD\""));
+/// assert_eq!(beancount.entry(&shop), format!("\
+/// 2024-01-31 open Assets:Checking
+/// 2024-01-31 open Expenses:Groceries
+/// 2024-01-31 ! \"Corner \\\"Store\\\"\" \"Milk\"
+///   id: \"{}\"
+///   Assets:Checking  -4.50 USD
+///   Expenses:Groceries
+///
+/// ", shop.id));
+/// // accounts are only opened once
+/// assert_eq!(beancount.entry(&paycheck), format!("\
+/// 2024-01-31 open Income:Uncategorized
+/// 2024-01-31 * \"Employer\" \"\"
+///   id: \"{}\"
+///   Assets:Checking  2500 USD
+///   Income:Uncategorized
+///
+/// ", paycheck.id));
+/// ```
+pub struct Journal {
+    dialect: Dialect,
+    currency: String,
+    time_zone: Tz,
+    /// Journal name of each of the user's accounts
+    accounts: HashMap<Uuid, String>,
+    /// Journal name of each of the user's hoops' sub-accounts
+    hoops: HashMap<Uuid, String>,
+    /// Every account an entry has used so far
+    opened: HashSet<String>,
+}
+
+impl Journal {
+    /// Start a journal of transactions made from the given accounts & spent from the given hoops,
+    /// dated in the given time zone
+    pub fn new(
+        dialect: Dialect,
+        currency: &str,
+        time_zone: Tz,
+        accounts: &[Account],
+        hoops: &[Hoop],
+    ) -> Self {
+        Self {
+            dialect,
+            currency: currency.to_string(),
+            time_zone,
+            accounts: accounts
+                .iter()
+                .map(|account| (account.id, dialect.account(&[ASSETS, &account.name])))
+                .collect(),
+            hoops: hoops
+                .iter()
+                .map(|hoop| (hoop.id, dialect.account(&[EXPENSES, &hoop.name])))
+                .collect(),
+            opened: HashSet::new(),
+        }
+    }
+
+    /// Lines starting the journal, before any entry
+    pub fn header(&self) -> String {
+        match self.dialect {
+            Dialect::Ledger => format!("; Exported from Hoops, in {}\n\n", self.currency),
+            Dialect::Beancount => format!(
+                "option \"title\" \"Exported from Hoops\"\noption \"operating_currency\" \"{}\"\n\n",
+                self.currency,
+            ),
+        }
+    }
+
+    /// The transaction's entry, after opening any account it's the first to use
+    pub fn entry(&mut self, transaction: &Transaction) -> String {
+        let Transaction {
+            id,
+            amount,
+            description,
+            payee,
+            timestamp,
+            account_id,
+            spent_from,
+            pending,
+            ..
+        } = transaction;
+        let date = timestamp.with_timezone(&self.time_zone).date_naive();
+        let asset = self
+            .accounts
+            .get(account_id)
+            .cloned()
+            .unwrap_or_else(|| self.dialect.account(&[ASSETS, &account_id.to_string()]));
+        let category = self.category(*amount, *spent_from);
+        let flag = match pending {
+            true => '!',
+            false => '*',
+        };
+
+        let mut entry = String::new();
+        for account in [&asset, &category] {
+            entry.push_str(&self.open(date, account));
+        }
+        match self.dialect {
+            Dialect::Ledger => {
+                entry.push_str(&format!("{date} {flag} {}", payee.replace('\n', " ")));
+                if let Some(description) = description {
+                    entry.push_str(&format!("  ; {}", description.replace('\n', " ")));
+                }
+                entry.push_str(&format!("\n    ; id: {id}\n"));
+                entry.push_str(&format!("    {asset}  {amount} {}\n", self.currency));
+                entry.push_str(&format!("    {category}\n\n"));
+            }
+            Dialect::Beancount => {
+                let narration = description.as_deref().unwrap_or_default();
+                entry.push_str(&format!(
+                    "{date} {flag} {} {}\n",
+                    quote(payee),
+                    quote(narration),
+                ));
+                entry.push_str(&format!("  id: \"{id}\"\n"));
+                entry.push_str(&format!("  {asset}  {amount} {}\n", self.currency));
+                entry.push_str(&format!("  {category}\n\n"));
+            }
+        }
+
+        entry
+    }
+
+    /// The account the other side of a transaction is booked to
+    fn category(&self, amount: Decimal, spent_from: Option<Uuid>) -> String {
+        match spent_from {
+            Some(hoop_id) => self
+                .hoops
+                .get(&hoop_id)
+                .cloned()
+                .unwrap_or_else(|| self.dialect.account(&[EXPENSES, &hoop_id.to_string()])),
+            None if amount > Decimal::ZERO => self.dialect.account(&[INCOME, UNCATEGORIZED]),
+            None => self.dialect.account(&[EXPENSES, UNCATEGORIZED]),
+        }
+    }
+
+    /// Directive opening the account, if the dialect needs one & it hasn't been opened yet
+    fn open(&mut self, date: NaiveDate, account: &str) -> String {
+        if self.dialect != Dialect::Beancount || !self.opened.insert(account.to_string()) {
+            return String::new();
+        }
+
+        format!("{date} open {account}\n")
+    }
+}
+
+/// The word with its first letter in upper case
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Text as a `beancount` string, in double quotes with any in it escaped
+fn quote(text: &str) -> String {
+    format!(
+        "\"{}\"",
+        text.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', " ")
+    )
+}
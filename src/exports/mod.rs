@@ -0,0 +1,4 @@
+mod components;
+pub use components::*;
+pub mod journal;
+pub mod model;
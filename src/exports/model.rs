@@ -0,0 +1,514 @@
+use std::collections::HashMap;
+
+use cfg_if::cfg_if;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::accounts::model::Account;
+use crate::errors::ValidationErrors;
+use crate::hoops::model::Hoop;
+use crate::time::start_of_day;
+use crate::transactions::model::{Transaction, TransactionFilter};
+
+/// Path of the endpoint streaming the logged in user's data as a file
+pub const EXPORT_PATH: &str = "/api/export";
+
+/// Version of the JSON export's layout, bumped whenever it changes in a way that older readers
+/// can't handle
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Currency amounts are written in by the journal formats, unless another is chosen
+pub const DEFAULT_CURRENCY: &str = "USD";
+
+/// The file formats data can be exported as
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+    Ledger,
+    Beancount,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 4] = [
+        ExportFormat::Csv,
+        ExportFormat::Json,
+        ExportFormat::Ledger,
+        ExportFormat::Beancount,
+    ];
+
+    /// Name of the format, as used in the export's query params
+    pub const fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Ledger => "ledger",
+            ExportFormat::Beancount => "beancount",
+        }
+    }
+
+    /// Find the format with the given name, if there is one
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.name() == name)
+    }
+
+    /// Name of the format to show the user
+    pub const fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Ledger => "ledger journal",
+            ExportFormat::Beancount => "beancount journal",
+        }
+    }
+
+    /// Extension of the exported file's name
+    pub const fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Ledger => "ledger",
+            ExportFormat::Beancount => "beancount",
+        }
+    }
+
+    /// Media type the exported file is sent as
+    pub const fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Json => "application/json",
+            ExportFormat::Ledger | ExportFormat::Beancount => "text/plain; charset=utf-8",
+        }
+    }
+}
+
+/// What to export, as read from the export's query params
+///
+/// Dates are in the user's time zone, so they're only turned into a [`TransactionFilter`] once
+/// it's known.
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use chrono::{NaiveDate, TimeZone, Utc};
+///
+/// use hoops_app::exports::model::{ExportFormat, ExportQuery};
+///
+/// let params = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+///     pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+/// };
+///
+/// let query = ExportQuery::from_params(&params(&[
+///     ("format", "beancount"),
+///     ("from", "2024-01-01"),
+///     ("to", "2024-06-30"),
+///     ("currency", "aud"),
+/// ]))
+/// .unwrap();
+/// assert_eq!(query.format, ExportFormat::Beancount);
+/// assert_eq!(query.currency, "AUD");
+/// assert_eq!(query.file_name(), "hoops-2024-01-01-to-2024-06-30.beancount");
+///
+/// // the end date is included, up until the next day starts in the user's time zone
+/// let filter = query.filter("Australia/Sydney".parse().unwrap());
+/// assert_eq!(filter.until, Some(Utc.with_ymd_and_hms(2024, 6, 30, 14, 0, 0).unwrap()));
+///
+/// // empty params are left unset
+/// let query = ExportQuery::from_params(&params(&[("format", "csv"), ("from", ""), ("currency", "")])).unwrap();
+/// assert_eq!((query.from, query.to, query.currency.as_str()), (None, None, "USD"));
+/// assert_eq!(query.file_name(), "hoops.csv");
+///
+/// let errors = ExportQuery::from_params(&params(&[
+///     ("format", "xlsx"),
+///     ("from", "2024-02-01"),
+///     ("to", "2024-01-01"),
+///     ("currency", "$"),
+/// ]))
+/// .unwrap_err();
+/// assert_eq!(errors.get("format"), ["Choose CSV, JSON, ledger journal, or beancount journal"]);
+/// assert_eq!(errors.get("to"), ["Choose an end date after the start date"]);
+/// assert_eq!(errors.get("currency"), ["Enter a currency code, like USD"]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ExportQuery {
+    pub format: ExportFormat,
+    /// First day to export transactions from
+    pub from: Option<NaiveDate>,
+    /// Last day to export transactions from
+    pub to: Option<NaiveDate>,
+    /// Currency the journal formats write amounts in, since amounts are saved without one
+    pub currency: String,
+}
+
+impl Default for ExportQuery {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::default(),
+            from: None,
+            to: None,
+            currency: DEFAULT_CURRENCY.to_string(),
+        }
+    }
+}
+
+impl ExportQuery {
+    /// Read what to export from the query params, or why they can't be
+    pub fn from_params(params: &HashMap<String, String>) -> Result<Self, ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        let param = |name: &str| {
+            params
+                .get(name)
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+        };
+        let mut date = |name: &str| {
+            let value = param(name)?;
+            let date = value.parse().ok();
+            if date.is_none() {
+                errors.add(name, "Enter a date, like 2024-01-31");
+            }
+            date
+        };
+        let (from, to) = (date("from"), date("to"));
+
+        let format = match param("format") {
+            None => Some(ExportFormat::default()),
+            Some(name) => ExportFormat::from_name(name),
+        };
+        if format.is_none() {
+            let labels: Vec<_> = ExportFormat::ALL.iter().map(ExportFormat::label).collect();
+            let (last, rest) = labels.split_last().unwrap_or((&"", &[]));
+            errors.add("format", format!("Choose {}, or {last}", rest.join(", ")));
+        }
+        if from.zip(to).is_some_and(|(from, to)| to < from) {
+            errors.add("to", "Choose an end date after the start date");
+        }
+        let currency = param("currency").unwrap_or(DEFAULT_CURRENCY).to_uppercase();
+        if !is_currency(&currency) {
+            errors.add("currency", "Enter a currency code, like USD");
+        }
+
+        errors.check()?;
+        Ok(Self {
+            format: format.unwrap_or_default(),
+            from,
+            to,
+            currency,
+        })
+    }
+
+    /// The filter for reading the exported transactions, with days starting & ending in the given
+    /// time zone
+    pub fn filter(&self, time_zone: Tz) -> TransactionFilter {
+        TransactionFilter {
+            from: self.from.map(|from| start_of_day(from, time_zone)),
+            until: self
+                .to
+                .map(|to| start_of_day(to + Duration::days(1), time_zone)),
+            ..Default::default()
+        }
+    }
+
+    /// Name the exported file is saved as, with the days it covers
+    pub fn file_name(&self) -> String {
+        let days = match (self.from, self.to) {
+            (Some(from), Some(to)) => format!("-{from}-to-{to}"),
+            (Some(from), None) => format!("-from-{from}"),
+            (None, Some(to)) => format!("-to-{to}"),
+            (None, None) => String::new(),
+        };
+
+        format!("hoops{days}.{}", self.format.extension())
+    }
+}
+
+/// Whether the text can be used as a currency in every journal format, like `USD` or `BTC`
+fn is_currency(currency: &str) -> bool {
+    (2..=24).contains(&currency.len())
+        && currency.starts_with(|c: char| c.is_ascii_uppercase())
+        && currency
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+}
+
+/// Every one of a user's accounts & hoops, & their transactions in the days exported, as written
+/// by the JSON export
+///
+/// Readers should check the `version` before reading the rest, since the layout can change.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Export {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    /// The user's time zone, which the days exported are in
+    pub time_zone: Tz,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub accounts: Vec<Account>,
+    pub hoops: Vec<Hoop>,
+    /// Transactions in the days exported, oldest first
+    pub transactions: Vec<Transaction>,
+}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use futures::{stream, Stream};
+        use sqlx::SqlitePool;
+        use uuid::Uuid;
+
+        use crate::exports::journal::{Dialect, Journal};
+        use crate::models::Scope;
+        use crate::transactions::model::{db_read_page, Cursor, SortColumn, TransactionSort};
+
+        /// Number of transactions read from the database at a time while exporting
+        const EXPORT_BATCH: u32 = 500;
+
+        /// Format of the CSV export's dates, which is one the CSV import reads
+        const CSV_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+        /// Names of the CSV export's columns, which the CSV import recognizes
+        const CSV_HEADERS: [&str; 9] = [
+            "Date",
+            "Payee",
+            "Amount",
+            "Description",
+            "Account",
+            "Hoop",
+            "Pending",
+            "ID",
+            "Import ID",
+        ];
+
+        /// Writes an export a piece at a time, so it can be sent while the rest is still being read
+        ///
+        /// The export is the `head`, then each transaction's piece in date order, then the `tail`.
+        ///
+        /// ```
+        /// use chrono::{TimeZone, Utc};
+        /// use chrono_tz::Tz;
+        /// use rust_decimal::Decimal;
+        ///
+        /// use hoops_app::accounts::model::Account;
+        /// use hoops_app::exports::model::{Export, ExportFormat, ExportQuery, Exporter, EXPORT_VERSION};
+        /// use hoops_app::hoops::model::Hoop;
+        /// use hoops_app::transactions::model::Transaction;
+        ///
+        /// # fn main() -> Result<(), anyhow::Error> {
+        /// let checking = Account::new("Checking".into(), None);
+        /// let groceries = Hoop::new("Groceries".into());
+        /// let timestamp = Utc.with_ymd_and_hms(2024, 1, 31, 9, 30, 0).unwrap();
+        /// let transactions = [
+        ///     Transaction::new(Decimal::new(-450, 2), "Corner Store".into(), timestamp, Some("Milk, bread".into()), checking.id, Some(groceries.id)),
+        ///     Transaction::new(Decimal::new(2500, 0), "Employer".into(), timestamp, None, checking.id, None),
+        /// ];
+        /// let export = |format| -> Result<String, anyhow::Error> {
+        ///     let query = ExportQuery { format, ..Default::default() };
+        ///     let mut exporter = Exporter::new(&query, Tz::UTC, vec![checking.clone()], vec![groceries.clone()]);
+        ///     let mut file = exporter.head()?;
+        ///     for transaction in &transactions {
+        ///         file.push_str(&exporter.write(transaction)?);
+        ///     }
+        ///     Ok(file + &exporter.tail())
+        /// };
+        ///
+        /// let csv = export(ExportFormat::Csv)?;
+        /// let lines: Vec<_> = csv.lines().collect();
+        /// assert_eq!(lines[0], "Date,Payee,Amount,Description,Account,Hoop,Pending,ID,Import ID");
+        /// assert_eq!(
+        ///     lines[1],
+        ///     format!("2024-01-31 09:30:00,Corner Store,-4.50,\"Milk, bread\",Checking,Groceries,false,{},", transactions[0].id),
+        /// );
+        ///
+        /// // the JSON export reads back as a whole
+        /// let json: Export = serde_json::from_str(&export(ExportFormat::Json)?)?;
+        /// assert_eq!(json.version, EXPORT_VERSION);
+        /// assert_eq!(json.accounts[0].name, "Checking");
+        /// assert_eq!(json.hoops[0].name, "Groceries");
+        /// let payees: Vec<_> = json.transactions.iter().map(|t| t.payee.as_str()).collect();
+        /// assert_eq!(payees, ["Corner Store", "Employer"]);
+        ///
+        /// let ledger = export(ExportFormat::Ledger)?;
+        /// assert!(ledger.contains("2024-01-31 * Employer\n"));
+        /// assert!(ledger.contains("    Assets:Checking  -4.50 USD\n    Expenses:Groceries\n"));
+        /// # Ok(())
+        /// # }
+        /// ```
+        pub struct Exporter {
+            format: ExportFormat,
+            time_zone: Tz,
+            from: Option<NaiveDate>,
+            to: Option<NaiveDate>,
+            accounts: Vec<Account>,
+            hoops: Vec<Hoop>,
+            /// Names of the accounts & hoops, for the CSV export's columns
+            names: HashMap<Uuid, String>,
+            journal: Option<Journal>,
+            /// Number of transactions written so far
+            written: usize,
+        }
+
+        impl Exporter {
+            /// Start exporting what the query asks for from the given accounts & hoops, with
+            /// timestamps in the given time zone
+            pub fn new(query: &ExportQuery, time_zone: Tz, accounts: Vec<Account>, hoops: Vec<Hoop>) -> Self {
+                let dialect = match query.format {
+                    ExportFormat::Ledger => Some(Dialect::Ledger),
+                    ExportFormat::Beancount => Some(Dialect::Beancount),
+                    ExportFormat::Csv | ExportFormat::Json => None,
+                };
+                let names = accounts
+                    .iter()
+                    .map(|account| (account.id, account.name.clone()))
+                    .chain(hoops.iter().map(|hoop| (hoop.id, hoop.name.clone())))
+                    .collect();
+
+                Self {
+                    format: query.format,
+                    time_zone,
+                    from: query.from,
+                    to: query.to,
+                    journal: dialect
+                        .map(|dialect| Journal::new(dialect, &query.currency, time_zone, &accounts, &hoops)),
+                    accounts,
+                    hoops,
+                    names,
+                    written: 0,
+                }
+            }
+
+            /// The start of the export, before any transaction
+            pub fn head(&self) -> Result<String, anyhow::Error> {
+                if let Some(journal) = &self.journal {
+                    return Ok(journal.header());
+                }
+
+                match self.format {
+                    ExportFormat::Json => Ok(format!(
+                        "{{\"version\":{},\"exported_at\":{},\"time_zone\":{},\"from\":{},\"to\":{},\"accounts\":{},\"hoops\":{},\"transactions\":[",
+                        EXPORT_VERSION,
+                        serde_json::to_string(&Utc::now())?,
+                        serde_json::to_string(&self.time_zone)?,
+                        serde_json::to_string(&self.from)?,
+                        serde_json::to_string(&self.to)?,
+                        serde_json::to_string(&self.accounts)?,
+                        serde_json::to_string(&self.hoops)?,
+                    )),
+                    _ => csv_row(CSV_HEADERS),
+                }
+            }
+
+            /// The transaction's piece of the export
+            pub fn write(&mut self, transaction: &Transaction) -> Result<String, anyhow::Error> {
+                self.written += 1;
+                if let Some(journal) = &mut self.journal {
+                    return Ok(journal.entry(transaction));
+                }
+
+                match self.format {
+                    ExportFormat::Json => {
+                        let separator = if self.written > 1 { "," } else { "" };
+                        Ok(format!("{separator}{}", serde_json::to_string(transaction)?))
+                    }
+                    _ => {
+                        let name = |id: Option<Uuid>| {
+                            id.and_then(|id| self.names.get(&id))
+                                .cloned()
+                                .unwrap_or_default()
+                        };
+                        csv_row([
+                            transaction
+                                .timestamp
+                                .with_timezone(&self.time_zone)
+                                .format(CSV_DATE_FORMAT)
+                                .to_string(),
+                            transaction.payee.clone(),
+                            transaction.amount.to_string(),
+                            transaction.description.clone().unwrap_or_default(),
+                            name(Some(transaction.account_id)),
+                            name(transaction.spent_from),
+                            transaction.pending.to_string(),
+                            transaction.id.to_string(),
+                            transaction.import_id.clone().unwrap_or_default(),
+                        ])
+                    }
+                }
+            }
+
+            /// The end of the export, after every transaction
+            pub fn tail(&self) -> String {
+                match self.format {
+                    ExportFormat::Json => "]}\n".to_string(),
+                    _ => String::new(),
+                }
+            }
+        }
+
+        /// A single row of a CSV file, quoting the fields that need it
+        fn csv_row<I, T>(fields: I) -> Result<String, anyhow::Error>
+        where
+            I: IntoIterator<Item = T>,
+            T: AsRef<[u8]>,
+        {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            writer.write_record(fields)?;
+            Ok(String::from_utf8(writer.into_inner()?)?)
+        }
+
+        /// What's left to read of an export
+        enum Reading {
+            Start,
+            /// The transactions after the cursor with the token, or from the first one
+            Transactions(Box<Exporter>, Option<String>),
+            Done,
+        }
+
+        /// Stream the export the query asks for, reading the scope's user's transactions a batch
+        /// at a time, oldest first
+        pub fn db_export(
+            pool: SqlitePool,
+            scope: Scope,
+            time_zone: Tz,
+            query: ExportQuery,
+        ) -> impl Stream<Item = Result<String, anyhow::Error>> {
+            use crate::accounts::model::db_read_all as db_read_all_accounts;
+            use crate::hoops::model::db_read_all as db_read_all_hoops;
+
+            let filter = query.filter(time_zone);
+            let sort = TransactionSort {
+                column: SortColumn::Timestamp,
+                descending: false,
+            };
+
+            stream::try_unfold(Reading::Start, move |reading| {
+                let (pool, query, filter) = (pool.clone(), query.clone(), filter.clone());
+                async move {
+                    match reading {
+                        Reading::Start => {
+                            let accounts = db_read_all_accounts(&pool, scope).await?;
+                            let hoops = db_read_all_hoops(&pool, scope).await?;
+                            let exporter = Exporter::new(&query, time_zone, accounts, hoops);
+                            Ok(Some((exporter.head()?, Reading::Transactions(Box::new(exporter), None))))
+                        }
+                        Reading::Transactions(mut exporter, token) => {
+                            let cursor = token.as_deref().and_then(Cursor::from_token);
+                            let page = db_read_page(&pool, scope, &filter, sort, cursor, EXPORT_BATCH).await?;
+                            let mut chunk = String::new();
+                            for transaction in &page.transactions {
+                                chunk.push_str(&exporter.write(transaction)?);
+                            }
+                            match page.next {
+                                Some(next) => Ok(Some((chunk, Reading::Transactions(exporter, Some(next))))),
+                                None => {
+                                    chunk.push_str(&exporter.tail());
+                                    Ok(Some((chunk, Reading::Done)))
+                                }
+                            }
+                        }
+                        Reading::Done => Ok(None),
+                    }
+                }
+            })
+        }
+    }
+}
@@ -0,0 +1,358 @@
+use leptos::*;
+use leptos_router::*;
+use rust_decimal::prelude::*;
+use uuid::Uuid;
+
+use crate::components::{
+    errors::action_error,
+    input::{Input, InputAmount},
+};
+use crate::hoops::model::{Hoop, HoopSummary};
+use crate::safe_to_spend::refresh_after;
+
+#[cfg(feature = "ssr")]
+use crate::{
+    events::{publish, Change, DataEvent, Entity},
+    state::pool,
+    users::scope,
+};
+
+/// add Hoop server endpoint
+#[server(prefix = "/api", endpoint = "hoop/new")]
+pub async fn hoop_new(name: String) -> Result<(), ServerFnError> {
+    use crate::models::Create;
+
+    let scope = scope()?;
+    let pool = &pool()?;
+    let hoop = Hoop::new(name);
+    let id = hoop.id;
+
+    Hoop::create_one(pool, scope, hoop).await.map_err(|err| {
+        logging::log!("There was an error saving the hoop: {}", err);
+        ServerFnError::ServerError(err.to_string())
+    })?;
+
+    publish(scope, DataEvent::new(Entity::Hoop, Change::Created, id));
+    Ok(())
+}
+
+/// Server endpoint for reading every hoop (or every archived hoop) with its balance
+#[server(prefix = "/api", endpoint = "hoops/read/all")]
+pub async fn hoops_read_many(archived: bool) -> Result<Vec<HoopSummary>, ServerFnError> {
+    use crate::hoops::model::db_read_many_summaries;
+
+    let scope = scope()?;
+    let pool = &pool()?;
+
+    db_read_many_summaries(pool, scope, archived)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// Server endpoint for changing a hoop's name
+#[server(prefix = "/api", endpoint = "hoop/rename")]
+pub async fn hoop_rename(id: Uuid, name: String) -> Result<(), ServerFnError> {
+    use crate::hoops::model::HoopPatch;
+    use crate::models::Update;
+
+    let scope = scope()?;
+    let pool = &pool()?;
+    let patch = HoopPatch {
+        name: Some(name),
+        ..Default::default()
+    };
+
+    Hoop::patch_one_by_id(pool, scope, id, patch)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?
+        .ok_or_else(|| ServerFnError::ServerError(format!("No hoop found with id {id}")))?;
+
+    publish(scope, DataEvent::new(Entity::Hoop, Change::Updated, id));
+    Ok(())
+}
+
+/// Server endpoint for moving money between two hoops
+///
+/// An empty `from` or `to` stands for Safe-to-Spend, so money can also be set aside into a hoop or
+/// released from one.
+#[server(prefix = "/api", endpoint = "hoop/move")]
+pub async fn hoop_move_funds(
+    from: String,
+    to: String,
+    amount: Decimal,
+) -> Result<(), ServerFnError> {
+    use crate::hoops::model::db_move_funds;
+
+    // convert empty strings to None, otherwise parse as a hoop's ID
+    let parse = |id: String| match id.as_str() {
+        "" => Ok(None),
+        _ => Uuid::parse_str(&id)
+            .map(Some)
+            .map_err(|e| ServerFnError::Args(e.to_string())),
+    };
+    let from = parse(from)?;
+    let to = parse(to)?;
+    let scope = scope()?;
+    let pool = &pool()?;
+
+    // the balance check & the ledger entries all need to see the same balance
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    db_move_funds(&mut tx, scope, from, to, amount)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    tx.commit()
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+
+    // both hoops' balances changed
+    for id in [from, to].into_iter().flatten() {
+        publish(scope, DataEvent::new(Entity::Hoop, Change::Updated, id));
+    }
+    Ok(())
+}
+
+/// Server endpoint for archiving a hoop, sweeping its remaining funds back to Safe-to-Spend
+#[server(prefix = "/api", endpoint = "hoop/archive")]
+pub async fn hoop_archive(id: Uuid) -> Result<(), ServerFnError> {
+    use crate::hoops::model::db_archive;
+
+    let scope = scope()?;
+    let pool = &pool()?;
+
+    // the sweep is only saved along with the hoop being archived
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    db_archive(&mut tx, scope, id)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    tx.commit()
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+
+    publish(scope, DataEvent::new(Entity::Hoop, Change::Updated, id));
+    Ok(())
+}
+
+/// Server endpoint for unarchiving a hoop, moving the funds swept out of it back in
+#[server(prefix = "/api", endpoint = "hoop/unarchive")]
+pub async fn hoop_unarchive(id: Uuid) -> Result<(), ServerFnError> {
+    use crate::hoops::model::db_unarchive;
+
+    let scope = scope()?;
+    let pool = &pool()?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    db_unarchive(&mut tx, scope, id)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    tx.commit()
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+
+    publish(scope, DataEvent::new(Entity::Hoop, Change::Updated, id));
+    Ok(())
+}
+
+/// Lists every hoop with its balance, along with forms for adding hoops & moving money between
+/// them
+///
+/// Archived hoops are listed instead when the `archived` query param is `true`.
+#[component]
+pub fn All() -> impl IntoView {
+    let query = use_query_map();
+    let archived = move || query.with(|q| q.get("archived").map(|a| a == "true").unwrap_or(false));
+
+    let new = create_server_action::<HoopNew>();
+    let rename = create_server_action::<HoopRename>();
+    let move_funds = create_server_action::<HoopMoveFunds>();
+    let archive = create_server_action::<HoopArchive>();
+    let unarchive = create_server_action::<HoopUnarchive>();
+    refresh_after(move_funds.version());
+    refresh_after(archive.version());
+    refresh_after(unarchive.version());
+
+    // refetch the hoops every time one is changed
+    let hoops = create_resource(
+        move || {
+            (
+                archived(),
+                new.version().get(),
+                rename.version().get(),
+                move_funds.version().get(),
+                archive.version().get(),
+                unarchive.version().get(),
+            )
+        },
+        |(archived, ..)| hoops_read_many(archived),
+    );
+
+    view! {
+        <h1>{move || if archived() { "Archived hoops" } else { "Hoops" }}</h1>
+        <Suspense fallback=move || view! {<p>Loading...</p>}.into_view()>
+            {move || hoops.get().map(|hoops| match hoops {
+                Err(err) => {
+                    view! { <pre>Error fetching hoops: {err.to_string()}</pre>}.into_view()
+                },
+                Ok(hoops) if hoops.is_empty() => {
+                    view! {<p>No hoops yet...</p>}.into_view()
+                },
+                Ok(hoops) => {
+                    view! {
+                        <ul>
+                            {hoops
+                                .into_iter()
+                                .map(|summary| view! { <Item summary rename archive unarchive /> })
+                                .collect_view()}
+                        </ul>
+                    }.into_view()
+                },
+            })}
+        </Suspense>
+        {action_error(rename)}
+        {action_error(archive)}
+        {action_error(unarchive)}
+        {move || if archived() {
+            view! { <A href="/hoops">View hoops</A> }.into_view()
+        } else {
+            view! {
+                <A href="/hoops?archived=true">View archived hoops</A>
+                <h2>Move money</h2>
+                {action_error(move_funds)}
+                <ActionForm action=move_funds>
+                    <Select name="from".to_string() label="From:".to_string() none_label="Safe-to-Spend".to_string() />
+                    <Select name="to".to_string() label="To:".to_string() none_label="Safe-to-Spend".to_string() />
+                    <InputAmount name="amount".to_string() label="Amount:".to_string() attr:required=true attr:min=0.01 />
+                    <button type="submit">Move</button>
+                </ActionForm>
+                <h2>New hoop</h2>
+                {action_error(new)}
+                <ActionForm action=new>
+                    <Input name="name".to_string() label="Name:".to_string() attr:required=true />
+                    <button type="submit">Create</button>
+                </ActionForm>
+            }.into_view()
+        }}
+    }
+}
+
+/// Component for rendering a single item in a list of hoops, with forms for renaming & archiving
+/// it, or for unarchiving it if it's archived
+#[component]
+fn Item(
+    summary: HoopSummary,
+    rename: Action<HoopRename, Result<(), ServerFnError>>,
+    archive: Action<HoopArchive, Result<(), ServerFnError>>,
+    unarchive: Action<HoopUnarchive, Result<(), ServerFnError>>,
+) -> impl IntoView {
+    let HoopSummary {
+        hoop: Hoop { id, name, archived },
+        balance,
+    } = summary;
+
+    let value = name.clone();
+    let forms = if archived {
+        view! {
+            <ActionForm action=unarchive>
+                <input type="hidden" name="id" value=id.to_string() />
+                <button type="submit">Unarchive</button>
+            </ActionForm>
+        }
+        .into_view()
+    } else {
+        view! {
+            <ActionForm action=rename>
+                <input type="hidden" name="id" value=id.to_string() />
+                <Input name="name".to_string() label="Name:".to_string() value=value attr:required=true />
+                <button type="submit">Rename</button>
+            </ActionForm>
+            <ActionForm action=archive>
+                <input type="hidden" name="id" value=id.to_string() />
+                // archiving can't be undone without the user noticing, so ask them first
+                <button
+                    type="submit"
+                    onclick="return confirm('Transactions can no longer be spent from an archived hoop, & its remaining funds will be moved to Safe-to-Spend. Are you sure?')"
+                >
+                    Archive
+                </button>
+            </ActionForm>
+        }
+        .into_view()
+    };
+
+    view! {
+        <li>
+            <ul>
+                <li>{name}</li>
+                <li>{balance.to_string()}</li>
+                <li>{forms}</li>
+            </ul>
+        </li>
+    }
+}
+
+/// A `<select>` input for choosing one of the hoops that haven't been archived
+///
+/// When given a `none_label`, an extra option with an empty value is listed first, letting the
+/// user choose no hoop at all. The hoop selected by default is always listed, even once it's been
+/// archived, so a form showing it doesn't lose it when sent.
+#[component]
+pub fn Select(
+    name: String,
+    label: String,
+    /// ID of the hoop selected by default, if any
+    #[prop(optional_no_strip)]
+    value: Option<Uuid>,
+    /// Label for the option standing in for no hoop, if one can be chosen
+    #[prop(optional, into)]
+    none_label: Option<String>,
+) -> impl IntoView {
+    let hoops = create_resource(
+        move || value,
+        |value| async move {
+            let mut hoops = hoops_read_many(false).await?;
+            if let Some(id) = value.filter(|id| !hoops.iter().any(|summary| summary.hoop.id == *id))
+            {
+                let archived = hoops_read_many(true).await?;
+                hoops.extend(archived.into_iter().filter(|summary| summary.hoop.id == id));
+            }
+            Ok::<_, ServerFnError>(hoops)
+        },
+    );
+    let required = none_label.is_none();
+
+    view! {
+        <label for=&name>{&label}</label>
+        <select id=&name name=&name required=required>
+            {none_label.map(|none_label| view! {
+                <option value="" selected=value.is_none()>{none_label}</option>
+            })}
+            <Suspense fallback=move || view! {<option disabled>Loading...</option>}.into_view()>
+                {move || hoops.get().map(|hoops| {
+                    hoops
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|HoopSummary { hoop, .. }| {
+                            let name = match hoop.archived {
+                                true => format!("{} (archived)", hoop.name),
+                                false => hoop.name,
+                            };
+                            view! {
+                                <option value=hoop.id.to_string() selected=value == Some(hoop.id)>
+                                    {name}
+                                </option>
+                            }
+                        })
+                        .collect_view()
+                })}
+            </Suspense>
+        </select>
+    }
+}
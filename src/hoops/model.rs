@@ -0,0 +1,374 @@
+use cfg_if::cfg_if;
+use chrono::{DateTime, Utc};
+use hoops_macros::Model;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Data type for modeling a hoop, a named pot of money set aside for a purpose
+#[derive(Clone, Deserialize, Serialize, Model)]
+#[model(table = "hoops")]
+pub struct Hoop {
+    pub id: Uuid,
+    pub name: String,
+    /// Archived hoops are hidden from the list of hoops & can't be spent from
+    pub archived: bool,
+}
+
+impl Hoop {
+    pub fn new(name: String) -> Self {
+        Hoop {
+            id: Uuid::new_v4(),
+            name,
+            archived: false,
+        }
+    }
+}
+
+/// A partial set of a hoop's information, used for changing only some of a saved hoop's fields.
+/// Any field left as `None` is not changed.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct HoopPatch {
+    pub name: Option<String>,
+    pub archived: Option<bool>,
+}
+
+/// What caused money to be moved into or out of a hoop
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "ssr", derive(sqlx::Type))]
+#[cfg_attr(feature = "ssr", sqlx(rename_all = "lowercase"))]
+pub enum AllocationKind {
+    /// Money moved by hand, to or from Safe-to-Spend or another hoop
+    Move,
+    /// The hoop's remaining balance, swept back to Safe-to-Spend when it was archived
+    Archive,
+    /// An archive sweep being undone when the hoop was unarchived
+    Unarchive,
+}
+
+/// A single entry in the ledger of money moved into or out of a hoop
+///
+/// Allocations are append-only: a hoop's balance is only ever changed by adding new entries.
+#[derive(Clone, Deserialize, Serialize, Model)]
+#[model(table = "hoop_allocations")]
+pub struct Allocation {
+    pub id: Uuid,
+    pub hoop_id: Uuid,
+    /// Positive amounts move money into the hoop, negative ones move it out
+    pub amount: Decimal,
+    /// The hoop the money came from or went to, or `None` for Safe-to-Spend
+    pub counterpart_hoop_id: Option<Uuid>,
+    pub timestamp: DateTime<Utc>,
+    pub kind: AllocationKind,
+}
+
+impl Allocation {
+    pub fn new(
+        hoop_id: Uuid,
+        amount: Decimal,
+        counterpart_hoop_id: Option<Uuid>,
+        timestamp: DateTime<Utc>,
+        kind: AllocationKind,
+    ) -> Self {
+        Allocation {
+            id: Uuid::new_v4(),
+            hoop_id,
+            amount,
+            counterpart_hoop_id,
+            timestamp,
+            kind,
+        }
+    }
+}
+
+/// A hoop along with its current balance
+#[derive(Clone, Deserialize, Serialize)]
+pub struct HoopSummary {
+    pub hoop: Hoop,
+    /// Sum of every allocation to the hoop, plus every transaction spent from it
+    pub balance: Decimal,
+}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use std::collections::HashMap;
+        use std::convert::TryFrom;
+        use anyhow::{anyhow, bail};
+        use sqlx::{Executor, Sqlite, SqliteConnection};
+
+        use crate::models::{sum_by_id, Create, Ident, OrderBy, Read, Scope, Update};
+
+        impl Create for Hoop {
+            type SqlType = HoopSql;
+        }
+
+        impl Read for Hoop {
+            type SqlType = HoopSql;
+
+            const ORDER_BY: &'static [OrderBy] = &[OrderBy::asc(Ident::new("name"))];
+        }
+
+        impl Update for Hoop {
+            type Patch = HoopPatch;
+
+            fn apply_patch(&mut self, patch: HoopPatch) {
+                let HoopPatch { name, archived } = patch;
+
+                if let Some(name) = name {
+                    self.name = name;
+                }
+                if let Some(archived) = archived {
+                    self.archived = archived;
+                }
+            }
+        }
+
+        // allocations can't be updated or deleted, which the table's triggers also enforce
+        impl Create for Allocation {
+            type SqlType = AllocationSql;
+        }
+
+        impl Read for Allocation {
+            type SqlType = AllocationSql;
+
+            const ORDER_BY: &'static [OrderBy] = &[OrderBy::desc(Ident::new("timestamp"))];
+        }
+
+        /// Read every hoop owned by the scope's user, whether archived or not
+        pub async fn db_read_all<'c, E>(executor: E, scope: Scope) -> Result<Vec<Hoop>, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite>,
+        {
+            sqlx::query_as::<_, HoopSql>("SELECT * FROM hoops WHERE owner_id = ? ORDER BY name ASC;")
+                .bind(scope.owner_id().to_string())
+                .fetch_all(executor)
+                .await?
+                .into_iter()
+                .map(Hoop::try_from)
+                .collect()
+        }
+
+        /// Read every entry in the ledger of money moved into or out of the scope's user's hoops,
+        /// oldest first
+        pub async fn db_read_all_allocations<'c, E>(
+            executor: E,
+            scope: Scope,
+        ) -> Result<Vec<Allocation>, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite>,
+        {
+            sqlx::query_as::<_, AllocationSql>(
+                "SELECT * FROM hoop_allocations WHERE owner_id = ? ORDER BY timestamp ASC, id ASC;"
+            )
+                .bind(scope.owner_id().to_string())
+                .fetch_all(executor)
+                .await?
+                .into_iter()
+                .map(Allocation::try_from)
+                .collect()
+        }
+
+        /// Read every hoop owned by the scope's user that is either archived or not, along with its
+        /// balance
+        pub async fn db_read_many_summaries<'c, E>(
+            executor: E,
+            scope: Scope,
+            archived: bool,
+        ) -> Result<Vec<HoopSummary>, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite> + Copy,
+        {
+            let hoops = sqlx::query_as::<_, HoopSql>(
+                r#"
+                SELECT * FROM hoops
+                WHERE owner_id = ? AND archived = ?
+                ORDER BY name ASC;
+                "#
+            )
+                .bind(scope.owner_id().to_string())
+                .bind(archived)
+                .fetch_all(executor)
+                .await?;
+            let mut balances = db_balances(executor, scope).await?;
+
+            hoops
+                .into_iter()
+                .map(|row| {
+                    let hoop = Hoop::try_from(row)?;
+                    let balance = balances.remove(&hoop.id).unwrap_or_default();
+
+                    Ok(HoopSummary { hoop, balance })
+                })
+                .collect()
+        }
+
+        /// Sum the allocations & the transactions spent from every hoop owned by the scope's user
+        pub async fn db_balances<'c, E>(
+            executor: E,
+            scope: Scope,
+        ) -> Result<HashMap<Uuid, Decimal>, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite>,
+        {
+            let rows = sqlx::query_as::<_, (String, String)>(
+                r#"
+                SELECT hoop_id, amount FROM hoop_allocations WHERE owner_id = ?1
+                UNION ALL
+                SELECT spent_from, amount FROM transactions
+                WHERE owner_id = ?1 AND spent_from IS NOT NULL;
+                "#
+            )
+                .bind(scope.owner_id().to_string())
+                .fetch_all(executor)
+                .await?;
+
+            sum_by_id(rows)
+        }
+
+        /// Sum the allocations & the transactions spent from a single hoop owned by the scope's
+        /// user
+        pub async fn db_balance<'c, E>(executor: E, scope: Scope, id: Uuid) -> Result<Decimal, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite>,
+        {
+            let rows = sqlx::query_as::<_, (String, String)>(
+                r#"
+                SELECT hoop_id, amount FROM hoop_allocations WHERE owner_id = ?1 AND hoop_id = ?2
+                UNION ALL
+                SELECT spent_from, amount FROM transactions WHERE owner_id = ?1 AND spent_from = ?2;
+                "#
+            )
+                .bind(scope.owner_id().to_string())
+                .bind(id.to_string())
+                .fetch_all(executor)
+                .await?;
+
+            Ok(sum_by_id(rows)?.remove(&id).unwrap_or_default())
+        }
+
+        /// Move the given amount of money between two hoops, where `None` stands for Safe-to-Spend
+        ///
+        /// Adds an entry to the ledger for each hoop involved. Money can't be moved out of a hoop
+        /// that doesn't hold enough of it, so this should be run inside a database transaction to
+        /// keep the balance from changing between checking & moving it.
+        pub async fn db_move_funds(
+            conn: &mut SqliteConnection,
+            scope: Scope,
+            from: Option<Uuid>,
+            to: Option<Uuid>,
+            amount: Decimal,
+        ) -> Result<(), anyhow::Error> {
+            if amount <= Decimal::ZERO {
+                bail!("The amount to move must be greater than zero");
+            }
+            if from == to {
+                bail!("Money must be moved to a different hoop");
+            }
+
+            let timestamp = Utc::now();
+
+            if let Some(from) = from {
+                let hoop = read_open_hoop(conn, scope, from).await?;
+                let balance = db_balance(&mut *conn, scope, from).await?;
+                if balance < amount {
+                    bail!("{} only has {balance} available", hoop.name);
+                }
+
+                let allocation = Allocation::new(from, -amount, to, timestamp, AllocationKind::Move);
+                Allocation::create_one(&mut *conn, scope, allocation).await?;
+            }
+
+            if let Some(to) = to {
+                read_open_hoop(conn, scope, to).await?;
+
+                let allocation = Allocation::new(to, amount, from, timestamp, AllocationKind::Move);
+                Allocation::create_one(&mut *conn, scope, allocation).await?;
+            }
+
+            Ok(())
+        }
+
+        /// Archive a hoop, sweeping whatever is left in it back to Safe-to-Spend
+        ///
+        /// This should be run inside a database transaction, so the sweep is never saved without
+        /// the hoop being archived.
+        pub async fn db_archive(conn: &mut SqliteConnection, scope: Scope, id: Uuid) -> Result<Hoop, anyhow::Error> {
+            let hoop = read_open_hoop(conn, scope, id).await?;
+
+            let balance = db_balance(&mut *conn, scope, id).await?;
+            if !balance.is_zero() {
+                let sweep = Allocation::new(id, -balance, None, Utc::now(), AllocationKind::Archive);
+                Allocation::create_one(&mut *conn, scope, sweep).await?;
+            }
+
+            set_archived(conn, scope, hoop.id, true).await
+        }
+
+        /// Unarchive a hoop, moving the funds swept out of it when it was archived back into it
+        ///
+        /// A sweep is only undone if it hasn't been already, so a hoop that had nothing left in it
+        /// when it was last archived doesn't get the funds from an earlier archival back twice.
+        /// This should be run inside a database transaction.
+        pub async fn db_unarchive(conn: &mut SqliteConnection, scope: Scope, id: Uuid) -> Result<Hoop, anyhow::Error> {
+            let hoop = Hoop::read_one_by_id(&mut *conn, scope, id)
+                .await?
+                .ok_or_else(|| anyhow!("No hoop found with id {id}"))?;
+            if !hoop.archived {
+                bail!("{} isn't archived", hoop.name);
+            }
+
+            let last = sqlx::query_as::<_, AllocationSql>(
+                r#"
+                SELECT * FROM hoop_allocations
+                WHERE owner_id = ? AND hoop_id = ? AND kind IN ('archive', 'unarchive')
+                ORDER BY timestamp DESC
+                LIMIT 1;
+                "#
+            )
+                .bind(scope.owner_id().to_string())
+                .bind(id.to_string())
+                .fetch_optional(&mut *conn)
+                .await?
+                .map(Allocation::try_from)
+                .transpose()?;
+            if let Some(sweep) = last.filter(|last| last.kind == AllocationKind::Archive) {
+                let restore = Allocation::new(id, -sweep.amount, None, Utc::now(), AllocationKind::Unarchive);
+                Allocation::create_one(&mut *conn, scope, restore).await?;
+            }
+
+            set_archived(conn, scope, hoop.id, false).await
+        }
+
+        /// Read a hoop that hasn't been archived, treating a missing or archived hoop as an error
+        async fn read_open_hoop(
+            conn: &mut SqliteConnection,
+            scope: Scope,
+            id: Uuid,
+        ) -> Result<Hoop, anyhow::Error> {
+            let hoop = Hoop::read_one_by_id(conn, scope, id)
+                .await?
+                .ok_or_else(|| anyhow!("No hoop found with id {id}"))?;
+            if hoop.archived {
+                bail!("{} has been archived", hoop.name);
+            }
+
+            Ok(hoop)
+        }
+
+        async fn set_archived(
+            conn: &mut SqliteConnection,
+            scope: Scope,
+            id: Uuid,
+            archived: bool,
+        ) -> Result<Hoop, anyhow::Error> {
+            let patch = HoopPatch {
+                archived: Some(archived),
+                ..Default::default()
+            };
+
+            Hoop::patch_one_by_id(conn, scope, id, patch)
+                .await?
+                .ok_or_else(|| anyhow!("No hoop found with id {id}"))
+        }
+    }
+}
@@ -0,0 +1,627 @@
+use leptos::*;
+use leptos_router::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::accounts::account_read_one;
+use crate::components::errors::{action_error, error_message};
+use crate::components::input::read_file;
+use crate::imports::csv::{CsvColumns, CsvMapping, DATE_FORMATS};
+use crate::imports::model::{Format, ImportedTransaction, RowError};
+use crate::time::format_local;
+use crate::transactions::model::Transaction;
+use crate::users::use_time_zone;
+
+#[cfg(feature = "ssr")]
+use crate::{
+    events::{publish, Change, DataEvent, Entity},
+    imports::model::Parsed,
+    models::Scope,
+    state::pool,
+    users::{current_user, scope},
+};
+
+/// What importing a file would do, shown before it's imported
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ImportPreview {
+    pub format: Format,
+    /// A CSV file's columns & how they're mapped
+    pub columns: Option<CsvColumns>,
+    /// The first few transactions that would be imported
+    pub transactions: Vec<ImportedTransaction>,
+    /// Number of transactions that would be imported
+    pub valid: usize,
+    /// Number of transactions that will be skipped, since they've already been imported
+    pub already_imported: usize,
+    /// Every transaction that looks like one already saved
+    pub duplicates: Vec<LikelyDuplicate>,
+    /// Every row that can't be imported
+    pub errors: Vec<RowError>,
+}
+
+/// A transaction in a file that's likely a duplicate of one already saved in the account
+#[derive(Clone, Deserialize, Serialize)]
+pub struct LikelyDuplicate {
+    pub imported: ImportedTransaction,
+    pub saved: Transaction,
+}
+
+/// Server endpoint for previewing a file's import into one of the user's accounts
+///
+/// The file's format is told from its contents. A CSV file is read with the given mapping, or one
+/// guessed from the file, & dates without a time zone are read in the user's.
+#[server(prefix = "/api", endpoint = "import/preview")]
+pub async fn preview_import(
+    account_id: Uuid,
+    contents: String,
+    mapping: Option<CsvMapping>,
+) -> Result<ImportPreview, ServerFnError> {
+    use crate::imports::model::PREVIEW_ROWS;
+    use crate::transactions::model::db_read_import_ids;
+
+    let (format, columns, mut parsed) = parse_file(&contents, mapping)?;
+    let scope = scope()?;
+    let pool = &pool()?;
+    let imported = db_read_import_ids(pool, scope, account_id)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    let already_imported = parsed.skip_imported(&imported);
+
+    let Parsed {
+        mut transactions,
+        errors,
+    } = parsed;
+    let saved = find_saved_duplicates(pool, scope, account_id, &transactions).await?;
+    let duplicates = transactions
+        .iter()
+        .zip(saved)
+        .filter_map(|(imported, saved)| {
+            saved.map(|saved| LikelyDuplicate {
+                imported: imported.clone(),
+                saved,
+            })
+        })
+        .collect();
+    let valid = transactions.len();
+    transactions.truncate(PREVIEW_ROWS);
+
+    Ok(ImportPreview {
+        format,
+        columns,
+        transactions,
+        valid,
+        already_imported,
+        duplicates,
+        errors,
+    })
+}
+
+/// Server endpoint for importing every readable transaction in a file into one of the user's open
+/// accounts, skipping those shown with errors in its preview & those already imported
+///
+/// When merging duplicates, transactions that look like ones already saved aren't imported, &
+/// the saved ones are marked as imported instead. The transactions are all saved together, or not
+/// at all. Takes the user to the account's transactions once they're saved.
+#[server(prefix = "/api", endpoint = "import")]
+pub async fn import_file(
+    account_id: Uuid,
+    contents: String,
+    mapping: Option<CsvMapping>,
+    // only sent when the merge checkbox is checked
+    merge_duplicates: Option<bool>,
+) -> Result<(), ServerFnError> {
+    let (_, _, parsed) = parse_file(&contents, mapping)?;
+
+    save_imported(account_id, parsed, merge_duplicates.unwrap_or_default()).await
+}
+
+/// Read every transaction in a file, in whichever format it's in, along with a CSV file's columns
+///
+/// Transactions the file doesn't give an ID are given a fingerprint of their details instead.
+#[cfg(feature = "ssr")]
+fn parse_file(
+    contents: &str,
+    mapping: Option<CsvMapping>,
+) -> Result<(Format, Option<CsvColumns>, Parsed), ServerFnError> {
+    use crate::imports::{csv, ofx, qif};
+
+    let time_zone = current_user()?.time_zone;
+    let format = Format::detect(contents);
+    let (columns, mut parsed) = match format {
+        Format::Csv => csv::parse_with_columns(contents, mapping, time_zone)
+            .map(|(columns, parsed)| (Some(columns), parsed)),
+        Format::Ofx => ofx::parse(contents, time_zone).map(|parsed| (None, parsed)),
+        Format::Qif => qif::parse(contents, time_zone).map(|parsed| (None, parsed)),
+    }
+    .map_err(file_error)?;
+    parsed.fingerprint();
+
+    Ok((format, columns, parsed))
+}
+
+/// Pair each imported transaction with the saved transaction in the account it's likely a
+/// duplicate of, if any
+#[cfg(feature = "ssr")]
+async fn find_saved_duplicates<'c, E>(
+    executor: E,
+    scope: Scope,
+    account_id: Uuid,
+    imported: &[ImportedTransaction],
+) -> Result<Vec<Option<Transaction>>, ServerFnError>
+where
+    E: sqlx::Executor<'c, Database = sqlx::Sqlite>,
+{
+    use crate::transactions::duplicates::{db_read_nearby, find_duplicates};
+
+    let timestamps = imported.iter().map(|transaction| transaction.timestamp);
+    let (Some(earliest), Some(latest)) = (timestamps.clone().min(), timestamps.max()) else {
+        return Ok(Vec::new());
+    };
+    let saved = db_read_nearby(executor, scope, account_id, earliest, latest)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    let transactions: Vec<_> = imported
+        .iter()
+        .map(|imported| imported.clone().into_transaction(account_id))
+        .collect();
+
+    Ok(find_duplicates(&transactions, &saved)
+        .into_iter()
+        .map(Option::<&Transaction>::cloned)
+        .collect())
+}
+
+/// An error for a file that can't be imported at all, shown to the user as the reason why
+#[cfg(feature = "ssr")]
+fn file_error(message: impl ToString) -> ServerFnError {
+    crate::errors::ValidationErrors::field("file", message).into()
+}
+
+/// Save a file's transactions to the account in a single database transaction, skipping those
+/// already imported, then take the user to the account's transactions
+///
+/// When merging duplicates, the saved transactions the file's duplicate are given their import
+/// IDs instead, so importing the file again skips them.
+#[cfg(feature = "ssr")]
+async fn save_imported(
+    account_id: Uuid,
+    mut parsed: Parsed,
+    merge_duplicates: bool,
+) -> Result<(), ServerFnError> {
+    use crate::transactions::model::{
+        db_create_many_checked, db_read_import_ids, db_set_import_id,
+    };
+
+    if parsed.transactions.is_empty() {
+        return Err(file_error("There are no transactions to import"));
+    }
+    let scope = scope()?;
+    let pool = &pool()?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    // read inside the transaction, so the same file imported twice at once can't be saved twice
+    let imported = db_read_import_ids(&mut *tx, scope, account_id)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    parsed.skip_imported(&imported);
+    if parsed.transactions.is_empty() {
+        return Err(file_error(
+            "Every transaction in the file has already been imported",
+        ));
+    }
+
+    let saved = match merge_duplicates {
+        true => find_saved_duplicates(&mut *tx, scope, account_id, &parsed.transactions).await?,
+        false => Vec::new(),
+    };
+    let mut saved = saved.into_iter();
+    let mut transactions = Vec::new();
+    for imported in parsed.transactions {
+        match (saved.next().flatten(), &imported.import_id) {
+            (Some(saved), Some(import_id)) => {
+                db_set_import_id(&mut *tx, scope, saved.id, import_id)
+                    .await
+                    .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+            }
+            _ => transactions.push(imported.into_transaction(account_id)),
+        }
+    }
+    // a single event is enough for the user's other tabs to read their lists again
+    let last_id = transactions.last().map(|transaction| transaction.id);
+
+    if !transactions.is_empty() {
+        db_create_many_checked(&mut tx, scope, transactions)
+            .await
+            .map_err(|err| {
+                logging::log!("There was an error importing transactions: {}", err);
+                ServerFnError::ServerError(err.to_string())
+            })?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+
+    if let Some(id) = last_id {
+        publish(
+            scope,
+            DataEvent::new(Entity::Transaction, Change::Created, id),
+        );
+    }
+    leptos_axum::redirect(&format!("/transactions?account={account_id}"));
+    Ok(())
+}
+
+/// Page for importing transactions from a bank's CSV, OFX, or QIF file into the account with the
+/// ID from the current route's `id` param
+///
+/// The file is previewed as soon as it's chosen or pasted in, with a guess at which columns of a
+/// CSV file hold what. Changing the mapping previews it again, & the rows that can't be read are
+/// listed so the user can see what will be skipped before importing the rest. Transactions that
+/// look like ones already saved are listed too, & merged into them unless the user chooses to
+/// import them anyway.
+#[component]
+pub fn Import() -> impl IntoView {
+    let params = use_params_map();
+    let account_id = move || params.with(|p| p.get("id").and_then(|id| Uuid::parse_str(id).ok()));
+    let account = create_resource(account_id, |id| async move {
+        match id {
+            Some(id) => account_read_one(id)
+                .await
+                .map(|summary| summary.account.name),
+            None => Err(ServerFnError::Args("No account chosen".into())),
+        }
+    });
+
+    let contents = create_rw_signal(String::new());
+    // the user's changes to the guessed mapping, which start over with each new file
+    let mapping = create_rw_signal(None::<CsvMapping>);
+    let preview = create_resource(
+        move || (account_id(), contents.get(), mapping.get()),
+        |(account_id, contents, mapping)| async move {
+            match account_id {
+                Some(account_id) if !contents.trim().is_empty() => {
+                    preview_import(account_id, contents, mapping)
+                        .await
+                        .map(Some)
+                }
+                _ => Ok(None),
+            }
+        },
+    );
+    let import = create_server_action::<ImportFile>();
+
+    let set_contents = move |text: String| {
+        mapping.set(None);
+        contents.set(text);
+    };
+
+    view! {
+        <Suspense fallback=move || view! {<p>Loading...</p>}.into_view()>
+            {move || account.get().map(|name| match name {
+                Ok(name) => view! { <h1>Import transactions into {name}</h1> }.into_view(),
+                Err(err) => view! { <pre>Error fetching account: {err.to_string()}</pre> }.into_view(),
+            })}
+        </Suspense>
+        <label for="file">"CSV, OFX, QFX, or QIF file:"</label>
+        <input
+            type="file" id="file" accept=".csv,.ofx,.qfx,.qif,text/csv,application/x-ofx,application/qif"
+            on:change=move |ev| read_file(ev, set_contents)
+        />
+        <label for="contents">"Or paste its contents:"</label>
+        <textarea id="contents" rows=6 prop:value=contents on:change=move |ev| set_contents(event_target_value(&ev)) />
+        <Transition fallback=move || view! {<p>Loading...</p>}.into_view()>
+            {move || preview.get().map(|preview| match preview {
+                Err(err) => view! { <p class="error">{error_message(&err)}</p> }.into_view(),
+                Ok(None) => ().into_view(),
+                Ok(Some(preview)) => {
+                    let ImportPreview { format, columns, transactions, valid, already_imported, duplicates, errors } = preview;
+                    let likely = duplicates.len();
+                    let merge = create_rw_signal(true);
+                    let submit = move || match merge.get() {
+                        true => format!("Import {} transactions", valid - likely),
+                        false => format!("Import {valid} transactions"),
+                    };
+                    view! {
+                        <p>"Reading the file as " {format.name()}</p>
+                        {columns.clone().map(|columns| view! { <MappingFields columns mapping /> })}
+                        <Preview transactions errors already_imported />
+                        <Duplicates duplicates />
+                        {action_error(import)}
+                        <ActionForm action=import>
+                            <input type="hidden" name="account_id" value=account_id().map(|id| id.to_string()) />
+                            <input type="hidden" name="contents" prop:value=contents />
+                            {columns.map(|columns| view! { <MappingInputs mapping=columns.mapping /> })}
+                            {(likely > 0).then(|| view! {
+                                <label>
+                                    <input
+                                        type="checkbox" name="merge_duplicates" value="true" checked=true
+                                        on:change=move |ev| merge.set(event_target_checked(&ev))
+                                    />
+                                    "Merge them into the transactions already saved, instead of importing them"
+                                </label>
+                            })}
+                            <button type="submit" disabled=valid == 0>{submit}</button>
+                        </ActionForm>
+                    }.into_view()
+                },
+            })}
+        </Transition>
+        {move || account_id().map(|id| view! { <A href=format!("/accounts/{id}")>Back to account</A> })}
+    }
+}
+
+/// Fields for choosing which of a CSV file's columns hold each part of a transaction, filled in
+/// with the preview's mapping
+///
+/// Every change is written to `mapping`, which previews the file again.
+#[component]
+fn MappingFields(columns: CsvColumns, mapping: RwSignal<Option<CsvMapping>>) -> impl IntoView {
+    let CsvColumns {
+        headers,
+        mapping: current,
+    } = columns;
+    let change = {
+        let current = current.clone();
+        move |update: &dyn Fn(&mut CsvMapping)| {
+            let mut changed = current.clone();
+            update(&mut changed);
+            mapping.set(Some(changed));
+        }
+    };
+    let column = |value: &str| value.parse::<usize>().ok();
+
+    // offer the file's own format when it isn't one of the usual ones
+    let mut formats: Vec<(String, String)> = DATE_FORMATS
+        .iter()
+        .map(|(example, format)| (example.to_string(), format.to_string()))
+        .collect();
+    if !formats
+        .iter()
+        .any(|(_, format)| *format == current.date_format)
+    {
+        formats.push((current.date_format.clone(), current.date_format.clone()));
+    }
+
+    view! {
+        <fieldset class="mapping">
+            <legend>Columns</legend>
+            <label>
+                <input
+                    type="checkbox"
+                    checked=current.has_headers
+                    on:change={
+                        let change = change.clone();
+                        move |ev| {
+                            let checked = event_target_checked(&ev);
+                            change(&|mapping| mapping.has_headers = checked)
+                        }
+                    }
+                />
+                "The first row names the columns"
+            </label>
+            <ColumnSelect
+                id="mapping_date" label="Date:" headers=headers.clone() value=Some(current.date)
+                on_change={
+                    let change = change.clone();
+                    move |value: String| if let Some(i) = column(&value) { change(&|mapping| mapping.date = i) }
+                }
+            />
+            <label for="mapping_date_format">Date format:</label>
+            <select
+                id="mapping_date_format"
+                on:change={
+                    let change = change.clone();
+                    move |ev| {
+                        let format = event_target_value(&ev);
+                        change(&|mapping| mapping.date_format = format.clone())
+                    }
+                }
+            >
+                {formats
+                    .into_iter()
+                    .map(|(example, format)| {
+                        let selected = format == current.date_format;
+                        view! { <option value=format selected=selected>{example}</option> }
+                    })
+                    .collect_view()}
+            </select>
+            <ColumnSelect
+                id="mapping_payee" label="Payee:" headers=headers.clone() value=Some(current.payee)
+                on_change={
+                    let change = change.clone();
+                    move |value: String| if let Some(i) = column(&value) { change(&|mapping| mapping.payee = i) }
+                }
+            />
+            <ColumnSelect
+                id="mapping_amount" label="Amount:" headers=headers.clone() value=current.amount
+                none_label="Separate money out & in columns"
+                on_change={
+                    let change = change.clone();
+                    move |value: String| change(&|mapping| mapping.amount = column(&value))
+                }
+            />
+            <ColumnSelect
+                id="mapping_debit" label="Money out:" headers=headers.clone() value=current.debit
+                none_label="None"
+                on_change={
+                    let change = change.clone();
+                    move |value: String| change(&|mapping| mapping.debit = column(&value))
+                }
+            />
+            <ColumnSelect
+                id="mapping_credit" label="Money in:" headers=headers.clone() value=current.credit
+                none_label="None"
+                on_change={
+                    let change = change.clone();
+                    move |value: String| change(&|mapping| mapping.credit = column(&value))
+                }
+            />
+            <ColumnSelect
+                id="mapping_description" label="Description:" headers value=current.description
+                none_label="None"
+                on_change=move |value: String| change(&|mapping| mapping.description = column(&value))
+            />
+        </fieldset>
+    }
+}
+
+/// A `<select>` for choosing one of the file's columns, by its name
+///
+/// When given a `none_label`, an extra option with an empty value is listed first, for choosing
+/// no column at all.
+#[component]
+fn ColumnSelect<F>(
+    id: &'static str,
+    label: &'static str,
+    headers: Vec<String>,
+    value: Option<usize>,
+    #[prop(optional)] none_label: Option<&'static str>,
+    on_change: F,
+) -> impl IntoView
+where
+    F: Fn(String) + 'static,
+{
+    view! {
+        <label for=id>{label}</label>
+        <select id=id on:change=move |ev| on_change(event_target_value(&ev))>
+            {none_label.map(|none_label| view! {
+                <option value="" selected=value.is_none()>{none_label}</option>
+            })}
+            {headers
+                .into_iter()
+                .enumerate()
+                .map(|(i, header)| view! {
+                    <option value=i.to_string() selected=value == Some(i)>{header}</option>
+                })
+                .collect_view()}
+        </select>
+    }
+}
+
+/// Hidden inputs sending the mapping along with the import form
+#[component]
+fn MappingInputs(mapping: CsvMapping) -> impl IntoView {
+    let CsvMapping {
+        has_headers,
+        date,
+        date_format,
+        payee,
+        amount,
+        debit,
+        credit,
+        description,
+    } = mapping;
+    // columns that aren't chosen are left out
+    let fields = [
+        ("has_headers", Some(has_headers.to_string())),
+        ("date", Some(date.to_string())),
+        ("date_format", Some(date_format)),
+        ("payee", Some(payee.to_string())),
+        ("amount", amount.map(|i| i.to_string())),
+        ("debit", debit.map(|i| i.to_string())),
+        ("credit", credit.map(|i| i.to_string())),
+        ("description", description.map(|i| i.to_string())),
+    ];
+
+    fields
+        .into_iter()
+        .filter_map(|(name, value)| {
+            value.map(|value| {
+                view! { <input type="hidden" name=format!("mapping[{name}]") value=value /> }
+            })
+        })
+        .collect_view()
+}
+
+/// The transactions in a file that look like ones already saved, next to the ones they look like
+#[component]
+fn Duplicates(duplicates: Vec<LikelyDuplicate>) -> impl IntoView {
+    let time_zone = use_time_zone();
+    let count = duplicates.len();
+    let describe = move |timestamp, payee: &str, amount: Decimal| {
+        format!(
+            "{}: {payee}, {amount}",
+            format_local(timestamp, time_zone.get_untracked())
+        )
+    };
+
+    (count > 0).then(|| view! {
+        <p>{format!("{count} transactions look like ones already saved:")}</p>
+        <table class="duplicates">
+            <thead>
+                <tr><th>In the file</th><th>Already saved</th></tr>
+            </thead>
+            <tbody>
+                {duplicates
+                    .into_iter()
+                    .map(|LikelyDuplicate { imported, saved }| view! {
+                        <tr>
+                            <td>{describe(imported.timestamp, &imported.payee, imported.amount)}</td>
+                            <td>
+                                <A href=format!("/transactions/{}", saved.id)>
+                                    {describe(saved.timestamp, &saved.payee, saved.amount)}
+                                </A>
+                            </td>
+                        </tr>
+                    })
+                    .collect_view()}
+            </tbody>
+        </table>
+    })
+}
+
+/// The first few transactions read from a file, how many were already imported, & every row
+/// that couldn't be read
+#[component]
+fn Preview(
+    transactions: Vec<ImportedTransaction>,
+    errors: Vec<RowError>,
+    already_imported: usize,
+) -> impl IntoView {
+    let time_zone = use_time_zone();
+    let duplicates = match already_imported {
+        0 => None,
+        n => Some(format!(
+            "{n} transactions have already been imported, & will be skipped"
+        )),
+    };
+    let skipped = match errors.len() {
+        0 => None,
+        n => Some(format!("{n} rows can't be read, & will be skipped:")),
+    };
+
+    view! {
+        <table class="preview">
+            <thead>
+                <tr><th>Date</th><th>Payee</th><th>Amount</th><th>Description</th></tr>
+            </thead>
+            <tbody>
+                {transactions
+                    .into_iter()
+                    .map(|ImportedTransaction { timestamp, payee, amount, description, .. }| view! {
+                        <tr>
+                            <td>{move || format_local(timestamp, time_zone.get())}</td>
+                            <td>{payee}</td>
+                            <td>{amount.to_string()}</td>
+                            <td>{description}</td>
+                        </tr>
+                    })
+                    .collect_view()}
+            </tbody>
+        </table>
+        {duplicates.map(|duplicates| view! { <p>{duplicates}</p> })}
+        {skipped.map(|skipped| view! {
+            <p>{skipped}</p>
+            <ul class="errors">
+                {errors
+                    .into_iter()
+                    .map(|RowError { line, message }| view! { <li>"Line " {line} ": " {message}</li> })
+                    .collect_view()}
+            </ul>
+        })}
+    }
+}
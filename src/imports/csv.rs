@@ -0,0 +1,307 @@
+//! Reading transactions from the CSV files banks export, which all lay out their columns
+//! differently, so the user maps each column to the part of a transaction it holds
+use cfg_if::cfg_if;
+use serde::{Deserialize, Serialize};
+
+/// Date formats offered when mapping a file's date column, as an example date & its `chrono`
+/// format
+pub const DATE_FORMATS: [(&str, &str); 7] = [
+    ("2024-01-31", "%Y-%m-%d"),
+    ("31/01/2024", "%d/%m/%Y"),
+    ("01/31/2024", "%m/%d/%Y"),
+    ("31.01.2024", "%d.%m.%Y"),
+    ("31 Jan 2024", "%d %b %Y"),
+    ("2024-01-31 13:45", "%Y-%m-%d %H:%M"),
+    ("2024-01-31 13:45:00", "%Y-%m-%d %H:%M:%S"),
+];
+
+/// Which of a CSV file's columns hold each part of a transaction, numbered from 0
+///
+/// The amount is read from either a single column of signed amounts, or a pair of columns for the
+/// money going out & coming in.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CsvMapping {
+    /// Whether the first row names the columns, instead of holding a transaction
+    pub has_headers: bool,
+    pub date: usize,
+    /// `chrono` format the dates are written in, with or without a time
+    pub date_format: String,
+    pub payee: usize,
+    /// Column of signed amounts, which is used over the debit & credit columns if given
+    pub amount: Option<usize>,
+    /// Column of the money going out of the account
+    pub debit: Option<usize>,
+    /// Column of the money coming into the account
+    pub credit: Option<usize>,
+    pub description: Option<usize>,
+}
+
+impl Default for CsvMapping {
+    fn default() -> Self {
+        Self {
+            has_headers: true,
+            date: 0,
+            date_format: DATE_FORMATS[0].1.to_string(),
+            payee: 1,
+            amount: Some(2),
+            debit: None,
+            credit: None,
+            description: None,
+        }
+    }
+}
+
+/// A file's columns & how they're mapped, shown so the user can change the mapping
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct CsvColumns {
+    /// Names of the file's columns, or their numbers if it doesn't name them
+    pub headers: Vec<String>,
+    pub mapping: CsvMapping,
+}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use ::csv::{ReaderBuilder, StringRecord, Trim};
+        use chrono_tz::Tz;
+        use rust_decimal::Decimal;
+
+        use crate::imports::model::{parse_amount, parse_date, ImportedTransaction, Parsed, RowError};
+
+        /// Most rows looked at when guessing how a file is laid out
+        const SAMPLE_ROWS: usize = 20;
+
+        /// Read every row of a CSV file as a transaction, with the given mapping
+        ///
+        /// Fails if the file isn't valid CSV or the mapping has no amount columns, while rows
+        /// that can't be read are listed with why, so the rest can still be imported.
+        ///
+        /// ```
+        /// use chrono::{TimeZone, Utc};
+        /// use chrono_tz::Tz;
+        /// use rust_decimal::Decimal;
+        ///
+        /// use hoops_app::imports::csv::{parse, CsvMapping};
+        ///
+        /// let csv = "\
+        /// Date,Details,Money out,Money in,Notes
+        /// 02/01/2024,Corner Store,9.50,,Milk
+        /// 03/01/2024,Paycheck,,\"2,500.00\",
+        /// 04/01/2024,,12.00,,
+        /// 5th Jan,Bakery,4.00,,
+        /// ";
+        /// let mapping = CsvMapping {
+        ///     date_format: "%d/%m/%Y".into(),
+        ///     amount: None,
+        ///     debit: Some(2),
+        ///     credit: Some(3),
+        ///     description: Some(4),
+        ///     ..Default::default()
+        /// };
+        ///
+        /// let parsed = parse(csv, &mapping, Tz::UTC).unwrap();
+        /// let store = &parsed.transactions[0];
+        /// assert_eq!(store.timestamp, Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap());
+        /// assert_eq!((store.payee.as_str(), store.amount), ("Corner Store", Decimal::new(-950, 2)));
+        /// assert_eq!(store.description.as_deref(), Some("Milk"));
+        /// assert_eq!(parsed.transactions[1].amount, Decimal::new(250000, 2));
+        /// assert_eq!(parsed.transactions[1].description, None);
+        ///
+        /// // rows that can't be read are reported by line, without stopping the rest
+        /// let lines: Vec<_> = parsed.errors.iter().map(|error| error.line).collect();
+        /// assert_eq!(lines, [4, 5]);
+        /// assert_eq!(parsed.errors[0].message, "Enter a payee");
+        /// ```
+        pub fn parse(csv: &str, mapping: &CsvMapping, time_zone: Tz) -> Result<Parsed, String> {
+            if mapping.amount.is_none() && mapping.debit.is_none() && mapping.credit.is_none() {
+                return Err("Choose the column amounts are in".into());
+            }
+
+            let mut parsed = Parsed::default();
+            for record in reader(csv, mapping.has_headers).records() {
+                let record = record.map_err(|e| e.to_string())?;
+                let line = record.position().map(|position| position.line()).unwrap_or_default();
+                match parse_record(&record, mapping, time_zone) {
+                    Ok(transaction) => parsed.transactions.push(transaction),
+                    Err(message) => parsed.errors.push(RowError { line, message }),
+                }
+            }
+
+            Ok(parsed)
+        }
+
+        /// Read a file with the given mapping, or one guessed from its contents, along with the
+        /// columns it was read with
+        pub fn parse_with_columns(
+            csv: &str,
+            mapping: Option<CsvMapping>,
+            time_zone: Tz,
+        ) -> Result<(CsvColumns, Parsed), String> {
+            let mapping = mapping.unwrap_or_else(|| guess_mapping(csv));
+            let parsed = parse(csv, &mapping, time_zone)?;
+            let headers = headers(csv, mapping.has_headers)?;
+
+            Ok((CsvColumns { headers, mapping }, parsed))
+        }
+
+        /// Guess which columns hold what from a file's column names & the format of its dates,
+        /// falling back to the default mapping for anything that can't be told
+        ///
+        /// ```
+        /// use hoops_app::imports::csv::{guess_mapping, CsvMapping};
+        ///
+        /// let csv = "Transaction Date,Description,Debit,Credit,Memo\n31/01/2024,Bakery,4.00,,\n";
+        /// assert_eq!(guess_mapping(csv), CsvMapping {
+        ///     has_headers: true,
+        ///     date: 0,
+        ///     date_format: "%d/%m/%Y".into(),
+        ///     payee: 1,
+        ///     amount: None,
+        ///     debit: Some(2),
+        ///     credit: Some(3),
+        ///     description: Some(4),
+        /// });
+        ///
+        /// // without column names, the amount is the column that always holds a number
+        /// let csv = "2024-01-31,Bakery,-4.00\n2024-02-01,Paycheck,2500\n";
+        /// let mapping = guess_mapping(csv);
+        /// assert!(!mapping.has_headers);
+        /// assert_eq!((mapping.date, mapping.payee, mapping.amount), (0, 1, Some(2)));
+        /// ```
+        pub fn guess_mapping(csv: &str) -> CsvMapping {
+            let mut mapping = CsvMapping::default();
+            let Some(Ok(first)) = reader(csv, false).records().next() else {
+                return mapping;
+            };
+
+            // a file names its columns if none of its first row's fields is an amount or a date
+            let is_value = |field: &str| parse_amount(field).is_some() || date_format(&[field]).is_some();
+            mapping.has_headers = !first.iter().any(is_value);
+            let rows: Vec<StringRecord> = reader(csv, mapping.has_headers)
+                .records()
+                .take(SAMPLE_ROWS)
+                .filter_map(Result::ok)
+                .collect();
+            let column = |i: usize| -> Vec<&str> { rows.iter().filter_map(|row| row.get(i)).collect() };
+
+            if mapping.has_headers {
+                let names: Vec<Vec<String>> = first
+                    .iter()
+                    .map(|name| {
+                        name.to_lowercase()
+                            .split(|c: char| !c.is_alphanumeric())
+                            .map(String::from)
+                            .collect()
+                    })
+                    .collect();
+                // the first column with one of the words in its name, other than those taken
+                let find = |words: &[&str], taken: &[usize]| {
+                    (0..names.len()).find(|i| {
+                        !taken.contains(i) && names[*i].iter().any(|name| words.contains(&name.as_str()))
+                    })
+                };
+
+                mapping.date = find(&["date"], &[]).unwrap_or(mapping.date);
+                let payee_words = ["payee", "merchant", "name", "description", "details"];
+                mapping.payee = find(&payee_words, &[mapping.date]).unwrap_or(mapping.payee);
+                let taken = [mapping.date, mapping.payee];
+                mapping.debit = find(&["debit", "debits", "withdrawal", "withdrawals", "out"], &taken);
+                mapping.credit = find(&["credit", "credits", "deposit", "deposits", "in"], &taken);
+                mapping.amount = match (mapping.debit, mapping.credit) {
+                    (None, None) => find(&["amount"], &taken).or(mapping.amount),
+                    _ => None,
+                };
+                let description_words = ["memo", "notes", "note", "reference", "description", "details"];
+                mapping.description = find(&description_words, &taken);
+            } else {
+                let is_amount = |i: usize| {
+                    let values = column(i);
+                    !values.is_empty() && values.iter().all(|v| parse_amount(v).is_some())
+                };
+                let is_date = |i: usize| date_format(&column(i)).is_some();
+                let columns = first.len();
+
+                mapping.date = (0..columns).find(|i| is_date(*i)).unwrap_or(mapping.date);
+                mapping.amount = (0..columns).rev().find(|i| *i != mapping.date && is_amount(*i)).or(mapping.amount);
+                mapping.payee = (0..columns)
+                    .find(|i| *i != mapping.date && Some(*i) != mapping.amount)
+                    .unwrap_or(mapping.payee);
+            }
+
+            if let Some(format) = date_format(&column(mapping.date)) {
+                mapping.date_format = format.to_string();
+            }
+            mapping
+        }
+
+        /// The first of the offered date formats every one of the values is written in, if any
+        fn date_format(values: &[&str]) -> Option<&'static str> {
+            if values.is_empty() {
+                return None;
+            }
+
+            DATE_FORMATS
+                .iter()
+                .map(|(_, format)| *format)
+                .find(|format| values.iter().all(|value| parse_date(value, format, Tz::UTC).is_some()))
+        }
+
+        /// Names of the file's columns, or `Column 1`, `Column 2`, & so on if it doesn't name them
+        fn headers(csv: &str, has_headers: bool) -> Result<Vec<String>, String> {
+            let Some(first) = reader(csv, false).records().next() else {
+                return Ok(Vec::new());
+            };
+            let first = first.map_err(|e| e.to_string())?;
+
+            Ok(match has_headers {
+                true => first.iter().map(String::from).collect(),
+                false => (1..=first.len()).map(|i| format!("Column {i}")).collect(),
+            })
+        }
+
+        /// A reader for the file's rows, which may have different numbers of columns
+        fn reader(csv: &str, has_headers: bool) -> ::csv::Reader<&[u8]> {
+            ReaderBuilder::new()
+                .has_headers(has_headers)
+                .flexible(true)
+                .trim(Trim::All)
+                .from_reader(csv.as_bytes())
+        }
+
+        /// Read a single row as a transaction, or describe why it can't be
+        fn parse_record(record: &StringRecord, mapping: &CsvMapping, time_zone: Tz) -> Result<ImportedTransaction, String> {
+            let field = |column: usize| record.get(column).unwrap_or_default();
+            let date = field(mapping.date);
+            let timestamp = parse_date(date, &mapping.date_format, time_zone)
+                .ok_or_else(|| format!("Couldn't read \"{date}\" as a date"))?;
+
+            let read_amount = |column: usize| {
+                let value = field(column);
+                parse_amount(value).ok_or_else(|| format!("Couldn't read \"{value}\" as an amount"))
+            };
+            // money out & in are read as amounts without their sign, & left blank when zero
+            let read_money = |column: Option<usize>| match column.filter(|c| !field(*c).is_empty()) {
+                Some(column) => read_amount(column).map(|amount| amount.abs()),
+                None => Ok(Decimal::ZERO),
+            };
+            let amount = match mapping.amount {
+                Some(column) => read_amount(column)?,
+                None => read_money(mapping.credit)? - read_money(mapping.debit)?,
+            };
+
+            let description = mapping
+                .description
+                .map(field)
+                .filter(|description| !description.is_empty())
+                .map(String::from);
+
+            ImportedTransaction {
+                timestamp,
+                payee: field(mapping.payee).to_string(),
+                amount,
+                description,
+                import_id: None,
+            }
+            .check()
+        }
+    }
+}
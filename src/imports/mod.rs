@@ -0,0 +1,6 @@
+mod components;
+pub use components::*;
+pub mod csv;
+pub mod model;
+pub mod ofx;
+pub mod qif;
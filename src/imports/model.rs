@@ -0,0 +1,318 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::time::start_of_day;
+use crate::transactions::model::{validate, Transaction};
+
+/// Most transactions of a file shown in its preview
+pub const PREVIEW_ROWS: usize = 10;
+
+/// How far into a file its format is looked for, which is enough for any OFX file's header
+const DETECT_BYTES: usize = 512;
+
+/// The kinds of files transactions can be imported from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Format {
+    Csv,
+    /// OFX statements, & the QFX files Quicken names them
+    Ofx,
+    Qif,
+}
+
+impl Format {
+    /// Tell which format a file is in from its contents, since banks don't name their files
+    /// consistently
+    ///
+    /// Only the start of the file is looked at, so a CSV that mentions OFX further down is still
+    /// read as a CSV.
+    ///
+    /// ```
+    /// use hoops_app::imports::model::Format;
+    ///
+    /// assert_eq!(Format::detect("OFXHEADER:100\nDATA:OFXSGML\n\n<OFX>"), Format::Ofx);
+    /// assert_eq!(Format::detect("<?xml version=\"1.0\"?>\n<?OFX OFXHEADER=\"200\"?>\n<ofx>"), Format::Ofx);
+    /// assert_eq!(Format::detect("!Type:Bank\nD1/31'24\n"), Format::Qif);
+    /// assert_eq!(Format::detect("Date,Payee,Amount\n"), Format::Csv);
+    /// assert_eq!(Format::detect("Date,Payee,Amount\n1/31/24,<OFX> Ltd,-5\n"), Format::Csv);
+    /// ```
+    pub fn detect(contents: &str) -> Self {
+        let start = contents.trim_start();
+        let head = &start.as_bytes()[..start.len().min(DETECT_BYTES)];
+        let starts_with = |prefix: &[u8]| {
+            head.len() >= prefix.len() && head[..prefix.len()].eq_ignore_ascii_case(prefix)
+        };
+        let mentions_ofx = || {
+            head.windows(4)
+                .any(|window| window.eq_ignore_ascii_case(b"<OFX"))
+        };
+        if starts_with(b"OFXHEADER")
+            || starts_with(b"<OFX")
+            || (starts_with(b"<?xml") && mentions_ofx())
+        {
+            Format::Ofx
+        } else if start.starts_with('!') {
+            Format::Qif
+        } else {
+            Format::Csv
+        }
+    }
+
+    /// The format's name, as it's shown to the user
+    pub const fn name(self) -> &'static str {
+        match self {
+            Format::Csv => "CSV",
+            Format::Ofx => "OFX",
+            Format::Qif => "QIF",
+        }
+    }
+}
+
+/// A transaction read from an imported file, before it's saved to an account
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ImportedTransaction {
+    pub timestamp: DateTime<Utc>,
+    pub payee: String,
+    pub amount: Decimal,
+    pub description: Option<String>,
+    /// The ID the bank gave the transaction in the file, if it gave one
+    pub import_id: Option<String>,
+}
+
+impl ImportedTransaction {
+    /// Check the transaction can be saved, the same way as one entered by hand, describing what's
+    /// wrong with it if not
+    pub fn check(self) -> Result<Self, String> {
+        validate(&self.payee, self.amount)
+            .check()
+            .map(|_| self)
+            .map_err(|errors| errors.to_string())
+    }
+
+    /// A new transaction in the given account, with the imported details
+    pub fn into_transaction(self, account_id: Uuid) -> Transaction {
+        Transaction {
+            import_id: self.import_id,
+            ..Transaction::new(
+                self.amount,
+                self.payee,
+                self.timestamp,
+                self.description,
+                account_id,
+                None,
+            )
+        }
+    }
+}
+
+/// A line of an imported file that couldn't be read as a transaction, & why
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RowError {
+    /// Line of the file the row starts on, counting from 1
+    pub line: u64,
+    pub message: String,
+}
+
+/// Everything read from an imported file
+///
+/// Rows that couldn't be read are left out of the transactions, so the rest can still be imported.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct Parsed {
+    pub transactions: Vec<ImportedTransaction>,
+    pub errors: Vec<RowError>,
+}
+
+impl Parsed {
+    /// Leave out the transactions with an import ID that's already been imported, or that
+    /// appeared earlier in the file, returning how many were left out
+    ///
+    /// ```
+    /// use std::collections::HashSet;
+    ///
+    /// use chrono::Utc;
+    /// use rust_decimal::Decimal;
+    ///
+    /// use hoops_app::imports::model::{ImportedTransaction, Parsed};
+    ///
+    /// let imported = |import_id: Option<&str>| ImportedTransaction {
+    ///     timestamp: Utc::now(),
+    ///     payee: "Bakery".into(),
+    ///     amount: Decimal::new(-4, 0),
+    ///     description: None,
+    ///     import_id: import_id.map(String::from),
+    /// };
+    /// let mut parsed = Parsed {
+    ///     transactions: vec![imported(Some("1")), imported(Some("2")), imported(Some("2")), imported(None)],
+    ///     errors: Vec::new(),
+    /// };
+    ///
+    /// assert_eq!(parsed.skip_imported(&HashSet::from(["1".to_string()])), 2);
+    /// let left: Vec<_> = parsed.transactions.iter().map(|t| t.import_id.as_deref()).collect();
+    /// assert_eq!(left, [Some("2"), None]);
+    /// ```
+    pub fn skip_imported(&mut self, imported: &HashSet<String>) -> usize {
+        let before = self.transactions.len();
+        let mut seen = HashSet::new();
+        self.transactions
+            .retain(|transaction| match &transaction.import_id {
+                Some(id) => !imported.contains(id) && seen.insert(id.clone()),
+                None => true,
+            });
+
+        before - self.transactions.len()
+    }
+}
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use std::collections::HashMap;
+
+        use sha2::{Digest, Sha256};
+
+        impl Parsed {
+            /// Give each transaction the bank didn't give an ID a fingerprint of its details
+            /// instead, so importing it again is skipped the same way
+            ///
+            /// Identical transactions in a file, like two coffees bought the same day, are told
+            /// apart by how many came before them.
+            ///
+            /// ```
+            /// use chrono::Utc;
+            /// use rust_decimal::Decimal;
+            ///
+            /// use hoops_app::imports::model::{ImportedTransaction, Parsed};
+            ///
+            /// let now = Utc::now();
+            /// let coffee = |import_id: Option<&str>| ImportedTransaction {
+            ///     timestamp: now,
+            ///     payee: "Cafe".into(),
+            ///     amount: Decimal::new(-4, 0),
+            ///     description: None,
+            ///     import_id: import_id.map(String::from),
+            /// };
+            /// let fingerprinted = || {
+            ///     let mut parsed = Parsed {
+            ///         transactions: vec![coffee(None), coffee(None), coffee(Some("FITID-1"))],
+            ///         errors: Vec::new(),
+            ///     };
+            ///     parsed.fingerprint();
+            ///     parsed.transactions.into_iter().map(|t| t.import_id.unwrap()).collect::<Vec<_>>()
+            /// };
+            ///
+            /// let ids = fingerprinted();
+            /// assert_ne!(ids[0], ids[1]);
+            /// assert_eq!(ids[2], "FITID-1");
+            /// // the same file gets the same fingerprints
+            /// assert_eq!(fingerprinted(), ids);
+            /// ```
+            pub fn fingerprint(&mut self) {
+                let mut counts = HashMap::new();
+                for transaction in &mut self.transactions {
+                    if transaction.import_id.is_some() {
+                        continue;
+                    }
+
+                    let details = format!(
+                        "{}\n{}\n{}\n{}",
+                        transaction.timestamp.to_rfc3339(),
+                        transaction.amount.normalize(),
+                        transaction.payee,
+                        transaction.description.as_deref().unwrap_or_default(),
+                    );
+                    let count = counts.entry(details.clone()).or_insert(0);
+                    *count += 1;
+                    let fingerprint = Sha256::digest(format!("{details}\n{count}").as_bytes());
+                    transaction.import_id = Some(hex::encode(fingerprint));
+                }
+            }
+        }
+    }
+}
+
+/// Read an amount the way banks tend to write them, or `None` if it isn't one
+///
+/// Currency symbols, spaces, & commas between thousands are ignored, while an amount wrapped in
+/// parentheses is negative. Any other comma, like a decimal comma, isn't read as an amount, since
+/// it can't be told apart from one between thousands.
+///
+/// ```
+/// use rust_decimal::Decimal;
+///
+/// use hoops_app::imports::model::parse_amount;
+///
+/// assert_eq!(parse_amount("-12.50"), Some(Decimal::new(-1250, 2)));
+/// assert_eq!(parse_amount(" $1,234.56 "), Some(Decimal::new(123456, 2)));
+/// assert_eq!(parse_amount("-$5"), Some(Decimal::new(-5, 0)));
+/// assert_eq!(parse_amount("(40.00)"), Some(Decimal::new(-4000, 2)));
+/// assert_eq!(parse_amount("1,234.50"), Some(Decimal::new(123450, 2)));
+/// assert_eq!(parse_amount("1,50"), None);
+/// assert_eq!(parse_amount(""), None);
+/// assert_eq!(parse_amount("n/a"), None);
+/// ```
+pub fn parse_amount(value: &str) -> Option<Decimal> {
+    let value = value.trim();
+    let (negative, value) = match value.strip_prefix('(').and_then(|v| v.strip_suffix(')')) {
+        Some(inner) => (true, inner),
+        None => (false, value),
+    };
+    let chars: Vec<char> = value.chars().collect();
+    let mut digits = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        match c {
+            // a comma between thousands is always followed by exactly three digits
+            ',' => {
+                let group = chars[i + 1..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit())
+                    .count();
+                if group != 3 {
+                    return None;
+                }
+            }
+            '0'..='9' | '.' | '-' | '+' => digits.push(*c),
+            _ => {}
+        }
+    }
+    let amount = Decimal::from_str(&digits).ok()?;
+
+    Some(if negative { -amount } else { amount })
+}
+
+/// Read a date, or a date & time, written in the given `chrono` format, in the given time zone
+///
+/// A date without a time is read as the start of that day.
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+///
+/// use hoops_app::imports::model::parse_date;
+///
+/// let sydney = "Australia/Sydney".parse().unwrap();
+/// assert_eq!(
+///     parse_date("27/01/2024", "%d/%m/%Y", sydney),
+///     Some(Utc.with_ymd_and_hms(2024, 1, 26, 13, 0, 0).unwrap()),
+/// );
+/// assert_eq!(
+///     parse_date("2024-01-27 09:30", "%Y-%m-%d %H:%M", sydney),
+///     Some(Utc.with_ymd_and_hms(2024, 1, 26, 22, 30, 0).unwrap()),
+/// );
+/// assert_eq!(parse_date("01/27/2024", "%d/%m/%Y", sydney), None);
+/// ```
+pub fn parse_date(value: &str, format: &str, time_zone: Tz) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+        return time_zone
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|timestamp| timestamp.with_timezone(&Utc));
+    }
+
+    NaiveDate::parse_from_str(value, format)
+        .ok()
+        .map(|date| start_of_day(date, time_zone))
+}
@@ -0,0 +1,252 @@
+//! Reading transactions from OFX statements, & the QFX files Quicken names them
+//!
+//! OFX 1.x files are SGML, where tags holding a value usually aren't closed, while OFX 2.x files
+//! are XML. Both are read the same way, as a list of tags each followed by the text up to the next
+//! tag, so closing tags are only needed around each transaction.
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use rust_decimal::prelude::*;
+
+use crate::imports::model::{parse_amount, parse_date, ImportedTransaction, Parsed, RowError};
+
+/// Read every transaction in an OFX file's statements
+///
+/// Fails if the file isn't OFX at all, while transactions that can't be read are listed with why,
+/// so the rest can still be imported. Dates without a time zone are read in the given one.
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use chrono_tz::Tz;
+/// use rust_decimal::Decimal;
+///
+/// use hoops_app::imports::ofx::parse;
+///
+/// let sgml = "\
+/// OFXHEADER:100
+/// DATA:OFXSGML
+///
+/// <OFX><BANKMSGSRSV1><STMTTRNRS><STMTRS><BANKTRANLIST>
+/// <STMTTRN>
+/// <TRNTYPE>DEBIT
+/// <DTPOSTED>20240131120000.000[-5:EST]
+/// <TRNAMT>-4.50
+/// <FITID>20240131001
+/// <NAME>Corner Store
+/// <MEMO>Milk &amp; bread
+/// </STMTTRN>
+/// <STMTTRN>
+/// <TRNTYPE>CREDIT
+/// <DTPOSTED>20240201
+/// <TRNAMT>2500.00
+/// <FITID>20240201001
+/// <PAYEE><NAME>Paycheck<ADDR1>1 Main St</PAYEE>
+/// </STMTTRN>
+/// <STMTTRN>
+/// <DTPOSTED>sometime
+/// <TRNAMT>-1
+/// <NAME>Bakery
+/// </STMTTRN>
+/// </BANKTRANLIST></STMTRS></STMTTRNRS></BANKMSGSRSV1></OFX>
+/// ";
+///
+/// let parsed = parse(sgml, Tz::UTC).unwrap();
+/// let store = &parsed.transactions[0];
+/// assert_eq!(store.timestamp, Utc.with_ymd_and_hms(2024, 1, 31, 17, 0, 0).unwrap());
+/// assert_eq!((store.payee.as_str(), store.amount), ("Corner Store", Decimal::new(-450, 2)));
+/// assert_eq!(store.description.as_deref(), Some("Milk & bread"));
+/// assert_eq!(store.import_id.as_deref(), Some("20240131001"));
+/// assert_eq!(parsed.transactions[1].payee, "Paycheck");
+/// assert_eq!(parsed.transactions[1].timestamp, Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap());
+///
+/// assert_eq!(parsed.errors.len(), 1);
+/// assert_eq!(parsed.errors[0].line, 20);
+/// assert_eq!(parsed.errors[0].message, "Couldn't read \"sometime\" as a date");
+///
+/// let xml = r#"<?xml version="1.0"?>
+/// <?OFX OFXHEADER="200" VERSION="220"?>
+/// <OFX><CREDITCARDMSGSRSV1><CCSTMTTRNRS><CCSTMTRS><BANKTRANLIST>
+///   <STMTTRN>
+///     <DTPOSTED>20240203093000</DTPOSTED>
+///     <TRNAMT>-12.00</TRNAMT>
+///     <FITID>A1</FITID>
+///     <NAME>Bookshop</NAME>
+///   </STMTTRN>
+/// </BANKTRANLIST></CCSTMTRS></CCSTMTTRNRS></CREDITCARDMSGSRSV1></OFX>"#;
+///
+/// let parsed = parse(xml, Tz::UTC).unwrap();
+/// assert_eq!(parsed.transactions[0].payee, "Bookshop");
+/// assert_eq!(parsed.transactions[0].timestamp, Utc.with_ymd_and_hms(2024, 2, 3, 9, 30, 0).unwrap());
+///
+/// assert!(parse("Date,Payee,Amount", Tz::UTC).is_err());
+/// ```
+pub fn parse(ofx: &str, time_zone: Tz) -> Result<Parsed, String> {
+    let mut elements = elements(ofx);
+    if !elements.any(|element| element.tag == "OFX" && !element.closing) {
+        return Err("This isn't an OFX file".into());
+    }
+
+    let mut parsed = Parsed::default();
+    let mut transaction: Option<(u64, HashMap<String, String>)> = None;
+    for element in elements {
+        if element.tag == "STMTTRN" {
+            if !element.closing {
+                transaction = Some((element.line, HashMap::new()));
+            } else if let Some((line, fields)) = transaction.take() {
+                match read_transaction(&fields, time_zone) {
+                    Ok(transaction) => parsed.transactions.push(transaction),
+                    Err(message) => parsed.errors.push(RowError { line, message }),
+                }
+            }
+        } else if let Some((_, fields)) = &mut transaction {
+            // the transaction's own name comes before any inside its payee's address
+            if !element.closing && !element.text.is_empty() {
+                fields.entry(element.tag).or_insert(element.text);
+            }
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Read an OFX date, like `20240131`, `20240131120000`, or `20240131120000.000[-5:EST]`
+///
+/// The time zone is given as hours from UTC, while dates without one are read in the given time
+/// zone.
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use chrono_tz::Tz;
+///
+/// use hoops_app::imports::ofx::parse_ofx_date;
+///
+/// let sydney: Tz = "Australia/Sydney".parse().unwrap();
+/// assert_eq!(
+///     parse_ofx_date("20240127", sydney),
+///     Some(Utc.with_ymd_and_hms(2024, 1, 26, 13, 0, 0).unwrap()),
+/// );
+/// assert_eq!(
+///     parse_ofx_date("20240127093000.000[+5.5:IST]", sydney),
+///     Some(Utc.with_ymd_and_hms(2024, 1, 27, 4, 0, 0).unwrap()),
+/// );
+/// assert_eq!(
+///     parse_ofx_date("202401270930[0:GMT]", sydney),
+///     Some(Utc.with_ymd_and_hms(2024, 1, 27, 9, 30, 0).unwrap()),
+/// );
+/// assert_eq!(parse_ofx_date("2024-01-27", sydney), None);
+/// ```
+pub fn parse_ofx_date(value: &str, time_zone: Tz) -> Option<DateTime<Utc>> {
+    let (datetime, zone) = match value.trim().split_once('[') {
+        Some((datetime, zone)) => (datetime, Some(zone.trim_end_matches(']'))),
+        None => (value.trim(), None),
+    };
+    // milliseconds are dropped
+    let datetime = datetime.split('.').next().unwrap_or_default();
+    let format = match datetime.len() {
+        8 => "%Y%m%d",
+        12 => "%Y%m%d%H%M",
+        14 => "%Y%m%d%H%M%S",
+        _ => return None,
+    };
+
+    let Some(zone) = zone else {
+        return parse_date(datetime, format, time_zone);
+    };
+    let hours = zone.split(':').next().unwrap_or_default().trim();
+    let seconds = Decimal::from_str(hours.trim_start_matches('+')).ok()? * Decimal::from(3600);
+    let offset = FixedOffset::east_opt(seconds.round().to_i32()?)?;
+    let naive = match format {
+        "%Y%m%d" => NaiveDateTime::parse_from_str(&format!("{datetime}000000"), "%Y%m%d%H%M%S"),
+        format => NaiveDateTime::parse_from_str(datetime, format),
+    };
+
+    offset
+        .from_local_datetime(&naive.ok()?)
+        .single()
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+}
+
+/// A tag in a file, & the text up to the next tag
+struct Element {
+    /// Line of the file the tag is on, counting from 1
+    line: u64,
+    /// The tag's name in upper case
+    tag: String,
+    closing: bool,
+    text: String,
+}
+
+/// Every tag in a file, in order, skipping the XML declaration & processing instructions
+fn elements(ofx: &str) -> impl Iterator<Item = Element> + '_ {
+    let mut segments = ofx.split('<');
+    let mut line = 1 + segments.next().map_or(0, count_lines);
+
+    segments.filter_map(move |segment| {
+        let tag_line = line;
+        line += count_lines(segment);
+
+        let (tag, text) = segment.split_once('>')?;
+        let (closing, tag) = match tag.strip_prefix('/') {
+            Some(tag) => (true, tag),
+            None => (false, tag),
+        };
+        if tag.starts_with(['?', '!']) {
+            return None;
+        }
+
+        Some(Element {
+            line: tag_line,
+            tag: tag.split_whitespace().next()?.to_uppercase(),
+            closing,
+            text: unescape(text.trim()),
+        })
+    })
+}
+
+fn count_lines(text: &str) -> u64 {
+    text.matches('\n').count() as u64
+}
+
+/// Replace the entities SGML & XML escape special characters with
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+/// Read a transaction from the values of the tags inside its `<STMTTRN>`, or describe why it can't
+/// be
+fn read_transaction(
+    fields: &HashMap<String, String>,
+    time_zone: Tz,
+) -> Result<ImportedTransaction, String> {
+    let field = |tag: &str| fields.get(tag).map(String::as_str);
+
+    let date = field("DTPOSTED").ok_or("The transaction has no date")?;
+    let timestamp = parse_ofx_date(date, time_zone)
+        .ok_or_else(|| format!("Couldn't read \"{date}\" as a date"))?;
+    let amount = field("TRNAMT").ok_or("The transaction has no amount")?;
+    let amount =
+        parse_amount(amount).ok_or_else(|| format!("Couldn't read \"{amount}\" as an amount"))?;
+
+    // some banks leave out the name & only describe the transaction in its memo
+    let (payee, description) = match (field("NAME"), field("MEMO")) {
+        (Some(name), memo) => (name, memo),
+        (None, Some(memo)) => (memo, None),
+        (None, None) => ("", None),
+    };
+
+    ImportedTransaction {
+        timestamp,
+        payee: payee.to_string(),
+        amount,
+        description: description.map(String::from),
+        import_id: field("FITID").map(String::from),
+    }
+    .check()
+}
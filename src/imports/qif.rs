@@ -0,0 +1,342 @@
+//! Reading transactions from the QIF files older tools like Quicken & GnuCash export
+//!
+//! A QIF file is split into sections by header lines like `!Type:Bank`, each holding records made
+//! of one line per field & ending with a `^` line. The first character of a field's line says
+//! which field it is, like `D` for the date or `T` for the amount. Only the bank & credit card
+//! sections' transactions are read, with each split of a transaction read as one of its own.
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+
+use crate::imports::model::{parse_amount, parse_date, ImportedTransaction, Parsed, RowError};
+
+/// Sections listing things other than transactions, which are skipped
+const LIST_SECTIONS: [&str; 6] = [
+    "account",
+    "type:cat",
+    "type:class",
+    "type:memorized",
+    "type:prices",
+    "type:security",
+];
+
+/// Read every transaction in a QIF file's bank & credit card sections
+///
+/// Fails if the file isn't QIF at all, while records that can't be read are listed with why, so
+/// the rest can still be imported. Whether dates are written with the day or month first is told
+/// from the file's dates, & they're read in the given time zone.
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use chrono_tz::Tz;
+/// use rust_decimal::Decimal;
+///
+/// use hoops_app::imports::qif::parse;
+///
+/// let qif = "\
+/// !Type:Bank
+/// D1/31'24
+/// T-1,250.00
+/// PLandlord
+/// MJanuary rent
+/// LHousing:Rent
+/// ^
+/// D2/ 1'24
+/// T-54.30
+/// PGrocery store
+/// SFood:Groceries
+/// EWeekly shop
+/// $-40.00
+/// SHousehold
+/// $-14.30
+/// ^
+/// D2/2'24
+/// T-10.00
+/// PBakery
+/// SFood
+/// $-9.00
+/// ^
+/// !Type:Invst
+/// D2/3'24
+/// NBuy
+/// YExample Corp
+/// T-100.00
+/// ^
+/// ";
+///
+/// let parsed = parse(qif, Tz::UTC).unwrap();
+/// let rent = &parsed.transactions[0];
+/// assert_eq!(rent.timestamp, Utc.with_ymd_and_hms(2024, 1, 31, 0, 0, 0).unwrap());
+/// assert_eq!((rent.payee.as_str(), rent.amount), ("Landlord", Decimal::new(-125000, 2)));
+/// assert_eq!(rent.description.as_deref(), Some("Housing:Rent - January rent"));
+///
+/// // each split is a transaction of its own
+/// let splits: Vec<_> = parsed.transactions[1..]
+///     .iter()
+///     .map(|t| (t.payee.as_str(), t.amount, t.description.as_deref()))
+///     .collect();
+/// assert_eq!(splits, [
+///     ("Grocery store", Decimal::new(-4000, 2), Some("Food:Groceries - Weekly shop")),
+///     ("Grocery store", Decimal::new(-1430, 2), Some("Household")),
+/// ]);
+///
+/// let errors: Vec<_> = parsed.errors.iter().map(|e| (e.line, e.message.as_str())).collect();
+/// assert_eq!(errors, [
+///     (17, "The splits add up to -9.00, not the transaction's amount of -10.00"),
+///     (24, "Only bank & credit card transactions can be imported"),
+/// ]);
+///
+/// // dates that can't be month first are read day first
+/// let parsed = parse("!Type:CCard\nD31/01/2024\nT-5\nPCafe\n^\n", Tz::UTC).unwrap();
+/// assert_eq!(parsed.transactions[0].timestamp, Utc.with_ymd_and_hms(2024, 1, 31, 0, 0, 0).unwrap());
+///
+/// assert!(parse("Date,Payee,Amount", Tz::UTC).is_err());
+/// ```
+pub fn parse(qif: &str, time_zone: Tz) -> Result<Parsed, String> {
+    if !qif.trim_start().starts_with('!') {
+        return Err("This isn't a QIF file".into());
+    }
+
+    let records = records(qif);
+    // Quicken writes the month first, so the day's only first if every date has to be
+    let dates: Vec<&str> = records
+        .iter()
+        .filter(|record| record.is_transaction())
+        .filter_map(|record| record.field('D'))
+        .collect();
+    let reads = |day_first| {
+        dates
+            .iter()
+            .all(|date| parse_qif_date(date, day_first, time_zone).is_some())
+    };
+    let day_first = !reads(false) && reads(true);
+
+    let mut parsed = Parsed::default();
+    for record in records {
+        if record.is_list() {
+            continue;
+        }
+        match record.read(day_first, time_zone) {
+            Ok(transactions) => parsed.transactions.extend(transactions),
+            Err(message) => parsed.errors.push(RowError {
+                line: record.line,
+                message,
+            }),
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Read a QIF date, like `1/31/2024`, `01/31/24`, `1/31'24`, or `2024-01-31`, as the start of
+/// that day in the given time zone
+///
+/// Two digit years are in the 2000s up to 68, & the 1900s from 69.
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use chrono_tz::Tz;
+///
+/// use hoops_app::imports::qif::parse_qif_date;
+///
+/// let date = |y, m, d| Some(Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap());
+/// assert_eq!(parse_qif_date("1/31/2024", false, Tz::UTC), date(2024, 1, 31));
+/// assert_eq!(parse_qif_date(" 1/ 5'04", false, Tz::UTC), date(2004, 1, 5));
+/// assert_eq!(parse_qif_date("12/25/98", false, Tz::UTC), date(1998, 12, 25));
+/// assert_eq!(parse_qif_date("25.12.2023", true, Tz::UTC), date(2023, 12, 25));
+/// assert_eq!(parse_qif_date("2024-01-31", true, Tz::UTC), date(2024, 1, 31));
+/// assert_eq!(parse_qif_date("31/01/2024", false, Tz::UTC), None);
+/// ```
+pub fn parse_qif_date(value: &str, day_first: bool, time_zone: Tz) -> Option<DateTime<Utc>> {
+    let date: String = value
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '\'' | '-' | '.' => '/',
+            c => c,
+        })
+        .collect();
+    let parts: Vec<&str> = date.split('/').collect();
+    let format = match (parts.as_slice(), day_first) {
+        ([year, _, _], _) if year.len() == 4 => "%Y/%m/%d",
+        ([_, _, year], true) if year.len() <= 2 => "%d/%m/%y",
+        ([_, _, year], false) if year.len() <= 2 => "%m/%d/%y",
+        ([_, _, _], true) => "%d/%m/%Y",
+        ([_, _, _], false) => "%m/%d/%Y",
+        _ => return None,
+    };
+
+    parse_date(&date, format, time_zone)
+}
+
+/// The lines of a record, up to the `^` ending it
+struct Record<'a> {
+    /// Line of the file the record starts on, counting from 1
+    line: u64,
+    /// The header of the section the record is in, in lower case & without its `!`
+    section: String,
+    /// Each field's code & value, in the order they're written
+    fields: Vec<(char, &'a str)>,
+}
+
+/// A share of a transaction, with its own category, memo, & amount
+#[derive(Default)]
+struct Split<'a> {
+    category: Option<&'a str>,
+    memo: Option<&'a str>,
+    amount: Option<&'a str>,
+}
+
+impl<'a> Record<'a> {
+    fn is_transaction(&self) -> bool {
+        matches!(self.section.as_str(), "type:bank" | "type:ccard")
+    }
+
+    fn is_list(&self) -> bool {
+        LIST_SECTIONS.contains(&self.section.as_str())
+    }
+
+    /// The value of the first field with the code, unless it's blank
+    fn field(&self, code: char) -> Option<&'a str> {
+        self.fields
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, value)| *value)
+            .filter(|value| !value.is_empty())
+    }
+
+    /// The record's splits, in order
+    ///
+    /// Each split starts with its category, but one missing its category is started by the
+    /// memo or amount it would otherwise repeat.
+    fn splits(&self) -> Vec<Split<'a>> {
+        let mut splits: Vec<Split> = Vec::new();
+        for &(code, value) in &self.fields {
+            match (code, splits.last_mut()) {
+                ('S', _) => splits.push(Split {
+                    category: Some(value).filter(|v| !v.is_empty()),
+                    ..Default::default()
+                }),
+                ('E', Some(split)) if split.memo.is_none() && split.amount.is_none() => {
+                    split.memo = Some(value);
+                }
+                ('E', _) => splits.push(Split {
+                    memo: Some(value),
+                    ..Default::default()
+                }),
+                ('$', Some(split)) if split.amount.is_none() => split.amount = Some(value),
+                ('$', _) => splits.push(Split {
+                    amount: Some(value),
+                    ..Default::default()
+                }),
+                _ => {}
+            }
+        }
+
+        splits
+    }
+
+    /// Read the record as a transaction, or as one for each of its splits, or describe why it
+    /// can't be
+    fn read(&self, day_first: bool, time_zone: Tz) -> Result<Vec<ImportedTransaction>, String> {
+        if !self.is_transaction() {
+            return Err("Only bank & credit card transactions can be imported".into());
+        }
+
+        let date = self.field('D').ok_or("The transaction has no date")?;
+        let timestamp = parse_qif_date(date, day_first, time_zone)
+            .ok_or_else(|| format!("Couldn't read \"{date}\" as a date"))?;
+        let read_amount = |value: &str| {
+            parse_amount(value).ok_or_else(|| format!("Couldn't read \"{value}\" as an amount"))
+        };
+        let amount = self
+            .field('T')
+            .or(self.field('U'))
+            .map(read_amount)
+            .transpose()?;
+
+        // some tools leave out the payee & only describe the transaction in its memo
+        let (payee, memo) = match (self.field('P'), self.field('M')) {
+            (Some(payee), memo) => (payee, memo),
+            (None, Some(memo)) => (memo, None),
+            (None, None) => ("", None),
+        };
+        let transaction = |amount, category, memo| {
+            ImportedTransaction {
+                timestamp,
+                payee: payee.to_string(),
+                amount,
+                description: describe(category, memo),
+                import_id: None,
+            }
+            .check()
+        };
+
+        let splits = self.splits();
+        if splits.is_empty() {
+            let amount = amount.ok_or("The transaction has no amount")?;
+            return transaction(amount, self.field('L'), memo).map(|t| vec![t]);
+        }
+
+        let amounts = splits
+            .iter()
+            .map(|split| {
+                split
+                    .amount
+                    .ok_or("A split has no amount".to_string())
+                    .and_then(read_amount)
+            })
+            .collect::<Result<Vec<Decimal>, String>>()?;
+        let total: Decimal = amounts.iter().sum();
+        if let Some(amount) = amount.filter(|amount| *amount != total) {
+            return Err(format!(
+                "The splits add up to {total}, not the transaction's amount of {amount}"
+            ));
+        }
+
+        splits
+            .into_iter()
+            .zip(amounts)
+            .map(|(split, amount)| transaction(amount, split.category, split.memo.or(memo)))
+            .collect()
+    }
+}
+
+/// Every record in a file, with the section it's in
+fn records(qif: &str) -> Vec<Record> {
+    let mut records = Vec::new();
+    let mut section = String::new();
+    let mut record: Option<Record> = None;
+
+    for (i, line) in qif.lines().enumerate() {
+        let line = line.trim_end();
+        if let Some(header) = line.strip_prefix('!') {
+            let header = header.trim().to_lowercase();
+            // options switch how Quicken reads the file, without starting a section
+            if !header.starts_with("option:") && !header.starts_with("clear:") {
+                records.extend(record.take());
+                section = header;
+            }
+        } else if line.trim() == "^" {
+            records.extend(record.take());
+        } else if let Some(code) = line.chars().next() {
+            let record = record.get_or_insert_with(|| Record {
+                line: i as u64 + 1,
+                section: section.clone(),
+                fields: Vec::new(),
+            });
+            record.fields.push((code, line[code.len_utf8()..].trim()));
+        }
+    }
+    records.extend(record);
+
+    records
+}
+
+/// A transaction's description, from its category & memo
+fn describe(category: Option<&str>, memo: Option<&str>) -> Option<String> {
+    match (category, memo) {
+        (Some(category), Some(memo)) => Some(format!("{category} - {memo}")),
+        (category, memo) => category.or(memo).map(String::from),
+    }
+}
@@ -1,14 +1,26 @@
 #![feature(associated_type_bounds)]
 
+// lets code generated by `hoops_macros` refer to this crate by name, even from inside it
+extern crate self as hoops_app;
+
 use cfg_if::cfg_if;
+pub mod accounts;
 pub mod app;
+pub mod backups;
+pub mod components;
 pub mod error_template;
+pub mod errors;
+pub mod events;
+pub mod exports;
 pub mod fileserv;
+pub mod hoops;
+pub mod imports;
 pub mod models;
+pub mod safe_to_spend;
 pub mod state;
-
-mod components;
-mod transactions;
+pub mod time;
+pub mod transactions;
+pub mod users;
 
 cfg_if! { if #[cfg(feature = "hydrate")] {
     use leptos::*;
@@ -1,21 +1,35 @@
 #[cfg(feature = "ssr")]
 #[tokio::main]
 async fn main() {
+    use std::collections::HashMap;
+    use std::convert::Infallible;
+
     use axum::{
-        body::Body as AxumBody,
-        extract::{Path, State},
-        http::Request,
-        response::{IntoResponse, Response},
-        routing::{get, post},
-        Router,
+        body::{Body as AxumBody, StreamBody},
+        extract::{Path, Query, State},
+        http::{header, Request, StatusCode},
+        response::{
+            sse::{Event, KeepAlive, Sse},
+            IntoResponse, Response,
+        },
+        routing::get,
+        Json, Router,
     };
+    use futures::{Stream, StreamExt};
     use leptos::*;
     use leptos_axum::{generate_route_list, LeptosRoutes};
     use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
 
     use hoops_app::app::*;
+    use hoops_app::backups::model::{db_read_backup, BACKUP_PATH};
+    use hoops_app::events::{Events, EVENTS_PATH};
+    use hoops_app::exports::model::{db_export, ExportQuery, EXPORT_PATH};
     use hoops_app::fileserv::file_and_error_handler;
+    use hoops_app::models::Scope;
     use hoops_app::state::AppState;
+    use hoops_app::users::model::{
+        db_claim_unowned, db_delete_expired_sessions, db_read_one_by_username, CurrentUser,
+    };
 
     simple_logger::init_with_level(log::Level::Debug).expect("couldn't initialize logging");
 
@@ -29,6 +43,40 @@ async fn main() {
         .await
         .expect("could not run SQLx migrations");
 
+    // data saved before users were added is only given to a user when asked for, with
+    // `hoops-app claim-unowned <username>`, instead of to whoever registers first
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let [command, username] = args.as_slice() {
+        if command == "claim-unowned" {
+            let user = db_read_one_by_username(&pool, username)
+                .await
+                .expect("could not read user")
+                .unwrap_or_else(|| panic!("there's no user named {username}"));
+            let mut tx = pool.begin().await.expect("could not start transaction");
+            let claimed = db_claim_unowned(&mut tx, user.id)
+                .await
+                .expect("could not claim unowned data");
+            tx.commit().await.expect("could not commit transaction");
+            log::info!("Gave {} {} unowned rows", user.username, claimed);
+            return;
+        }
+    }
+
+    // expired sessions can't be used, but are only deleted when their user logs out, so they're
+    // cleared out every hour instead of piling up
+    tokio::spawn({
+        let pool = pool.clone();
+        async move {
+            let mut hourly = tokio::time::interval(std::time::Duration::from_secs(60 * 60));
+            loop {
+                hourly.tick().await;
+                if let Err(err) = db_delete_expired_sessions(&pool).await {
+                    log::error!("There was an error deleting expired sessions: {}", err);
+                }
+            }
+        }
+    });
+
     // Setting get_configuration(None) means we'll be using cargo-leptos's env values
     // For deployment these variables are:
     // <https://github.com/leptos-rs/start-axum#executing-a-server-on-a-remote-machine-without-the-toolchain>
@@ -43,11 +91,13 @@ async fn main() {
         leptos_options,
         pool: pool.clone(),
         routes: routes.clone(),
+        events: Events::new(),
     };
 
-    /// Provide db pool to server functions as global context
+    /// Provide db pool & logged in user to server functions as global context
     async fn server_fn_handler(
         State(app_state): State<AppState>,
+        user: Option<CurrentUser>,
         path: Path<String>,
         headers: http::HeaderMap,
         raw_query: axum::extract::RawQuery,
@@ -61,24 +111,36 @@ async fn main() {
             raw_query,
             move || {
                 provide_context(app_state.pool.clone());
+                provide_context(app_state.events.clone());
+                if let Some(user) = user.clone() {
+                    provide_context(user);
+                }
             },
             request,
         )
         .await
     }
 
-    /// Provide db pool to routes as global context
-    async fn routes_handler(State(app_state): State<AppState>, req: Request<AxumBody>) -> Response {
+    /// Provide db pool & logged in user to routes as global context
+    async fn routes_handler(
+        State(app_state): State<AppState>,
+        user: Option<CurrentUser>,
+        req: Request<AxumBody>,
+    ) -> Response {
         let AppState {
             leptos_options,
             pool,
             routes,
+            ..
         } = app_state;
         let handler = leptos_axum::render_route_with_context(
             leptos_options.clone(),
             routes.clone(),
             move || {
                 provide_context(pool.clone());
+                if let Some(user) = user.clone() {
+                    provide_context(user);
+                }
             },
             App,
         );
@@ -86,8 +148,81 @@ async fn main() {
         handler(req).await.into_response()
     }
 
+    /// Stream the logged in user's data events, for as long as they stay connected
+    async fn events_handler(
+        State(events): State<Events>,
+        user: CurrentUser,
+    ) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+        let stream = events
+            .subscribe(Scope::new(user.id))
+            .filter_map(|event| async move { Event::default().json_data(event).ok().map(Ok) });
+
+        Sse::new(stream).keep_alive(KeepAlive::default())
+    }
+
+    /// Stream the logged in user's data as a file in the format they asked for
+    async fn export_handler(
+        State(pool): State<SqlitePool>,
+        user: CurrentUser,
+        Query(params): Query<HashMap<String, String>>,
+    ) -> Response {
+        let query = match ExportQuery::from_params(&params) {
+            Ok(query) => query,
+            Err(errors) => return (StatusCode::BAD_REQUEST, errors.to_string()).into_response(),
+        };
+        let headers = [
+            (
+                header::CONTENT_TYPE,
+                query.format.content_type().to_string(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", query.file_name()),
+            ),
+        ];
+        // the response has already started by the time an error can happen, so it's cut short
+        let stream = db_export(pool, Scope::new(user.id), user.time_zone, query).map(|chunk| {
+            chunk.map_err(|err| {
+                log::error!("There was an error exporting: {}", err);
+                std::io::Error::new(std::io::ErrorKind::Other, err.to_string())
+            })
+        });
+
+        (headers, StreamBody::new(stream)).into_response()
+    }
+
+    /// Send everything the logged in user's saved as a backup file
+    async fn backup_handler(State(pool): State<SqlitePool>, user: CurrentUser) -> Response {
+        let backup = match db_read_backup(&pool, Scope::new(user.id), user.time_zone).await {
+            Ok(backup) => backup,
+            Err(err) => {
+                log::error!("There was an error backing up: {}", err);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        };
+        let file_name = format!(
+            "hoops-backup-{}.json",
+            backup
+                .created_at
+                .with_timezone(&user.time_zone)
+                .date_naive()
+        );
+        let headers = [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{file_name}\""),
+            ),
+        ];
+
+        (headers, Json(backup)).into_response()
+    }
+
     // build our application with a route
     let app = Router::new()
+        .route(EVENTS_PATH, get(events_handler))
+        .route(EXPORT_PATH, get(export_handler))
+        .route(BACKUP_PATH, get(backup_handler))
         .route(
             "/api/*fn_name",
             get(server_fn_handler).post(server_fn_handler),
@@ -1,37 +1,371 @@
 #![cfg(feature = "ssr")]
 
-use sqlx::{sqlite::SqliteRow, FromRow, SqlitePool};
+pub mod repository;
+
+use std::fmt;
+
+use sqlx::{
+    query_builder::Separated, sqlite::SqliteRow, Acquire, Executor, FromRow, QueryBuilder, Sqlite,
+    SqliteConnection,
+};
+use uuid::Uuid;
+
+use repository::Table as Repository;
+
+/// A table or column name that is safe to write directly into a SQL query.
+///
+/// Identifiers can only be made from `&'static str` values & must be made up of ASCII letters,
+/// digits, & underscores, so user input can never be used as one. Building an invalid identifier
+/// in a `const` fails to compile.
+///
+/// ```
+/// use hoops_app::models::Ident;
+///
+/// const AMOUNT: Ident = Ident::new("amount");
+///
+/// assert_eq!(AMOUNT.as_str(), "amount");
+/// // identifiers are quoted when written into a query
+/// assert_eq!(AMOUNT.to_string(), "\"amount\"");
+/// ```
+///
+/// ```should_panic
+/// use hoops_app::models::Ident;
+///
+/// Ident::new("amount; DROP TABLE transactions");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ident(&'static str);
+
+impl Ident {
+    pub const fn new(name: &'static str) -> Self {
+        let bytes = name.as_bytes();
+        assert!(!bytes.is_empty(), "identifiers can't be empty");
+
+        let mut i = 0;
+        while i < bytes.len() {
+            let byte = bytes[i];
+            assert!(
+                byte == b'_' || byte.is_ascii_alphabetic() || (i > 0 && byte.is_ascii_digit()),
+                "identifiers may only contain ASCII letters, digits, & underscores"
+            );
+            i += 1;
+        }
+
+        Self(name)
+    }
+
+    pub const fn as_str(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl fmt::Display for Ident {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\"", self.0)
+    }
+}
+
+/// Direction to sort a column in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Asc,
+    Desc,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Direction::Asc => write!(f, " ASC"),
+            Direction::Desc => write!(f, " DESC"),
+        }
+    }
+}
+
+/// A single column to sort query results by
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OrderBy {
+    pub column: Ident,
+    pub direction: Direction,
+}
+
+impl OrderBy {
+    pub const fn asc(column: Ident) -> Self {
+        Self {
+            column,
+            direction: Direction::Asc,
+        }
+    }
+
+    pub const fn desc(column: Ident) -> Self {
+        Self {
+            column,
+            direction: Direction::Desc,
+        }
+    }
+
+    /// The same column, sorted the opposite way
+    pub const fn reversed(&self) -> Self {
+        let direction = match self.direction {
+            Direction::Asc => Direction::Desc,
+            Direction::Desc => Direction::Asc,
+        };
+
+        Self {
+            column: self.column,
+            direction,
+        }
+    }
+}
+
+/// The user whose data a query is limited to
+///
+/// Every method on `Create`, `Read`, `Update`, & `Delete` takes a scope, & only ever sees or
+/// changes the rows owned by its user, so one user's data can't be read or changed by another.
+///
+/// ```
+/// use uuid::Uuid;
+///
+/// use hoops_app::models::Scope;
+///
+/// let owner_id = Uuid::new_v4();
+/// assert_eq!(Scope::new(owner_id).owner_id(), owner_id);
+/// ```
+///
+/// Rows owned by another user are treated as if they don't exist, so they can't be used, moved, or
+/// deleted either:
+///
+/// ```
+/// use chrono::Utc;
+/// use rust_decimal::Decimal;
+///
+/// use hoops_app::accounts::model::Account;
+/// use hoops_app::hoops::model::{db_move_funds, Hoop};
+/// use hoops_app::models::{doc_fixture_two_users, Create, Delete, Read};
+/// use hoops_app::transactions::model::{db_create_one_checked, Transaction};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), anyhow::Error> {
+/// let (pool, alice, bob, account_id) = doc_fixture_two_users().await?;
+/// let mut conn = pool.acquire().await?;
+///
+/// let income = Transaction::new(Decimal::new(500, 0), "Work".into(), Utc::now(), None, account_id, None);
+/// let income_id = income.id;
+/// db_create_one_checked(&mut conn, alice, income).await?;
+/// let hoop = Hoop::new("Rent".into());
+/// let hoop_id = hoop.id;
+/// Hoop::create_one(&pool, alice, hoop).await?;
+/// db_move_funds(&mut conn, alice, None, Some(hoop_id), Decimal::new(50, 0)).await?;
+///
+/// assert!(Account::read_one_by_id(&pool, bob, account_id).await?.is_none());
+/// assert!(Transaction::read_one_by_id(&pool, bob, income_id).await?.is_none());
+/// // bob can't spend from alice's account or hoop, move her money, or delete her data
+/// let spend = Transaction::new(Decimal::new(-10, 0), "Shop".into(), Utc::now(), None, account_id, None);
+/// assert!(db_create_one_checked(&mut conn, bob, spend).await.is_err());
+/// assert!(db_move_funds(&mut conn, bob, Some(hoop_id), None, Decimal::new(50, 0)).await.is_err());
+/// assert!(!Transaction::delete_one_by_id(&pool, bob, income_id).await?);
+/// assert!(Transaction::read_one_by_id(&pool, alice, income_id).await?.is_some());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Scope {
+    owner_id: Uuid,
+}
+
+impl Scope {
+    pub const fn new(owner_id: Uuid) -> Self {
+        Self { owner_id }
+    }
+
+    /// ID of the user the scope is limited to
+    pub const fn owner_id(&self) -> Uuid {
+        self.owner_id
+    }
+}
 
 /// Declare a struct to have a specific table name
 ///
 /// ```
-/// use hoops_app::models::Table;
+/// use hoops_app::models::{ Ident, Table };
 ///
 /// struct MyStruct();
 ///
 /// impl Table for MyStruct {
-///   const TABLE: &'static str = "my_table_name";
+///   const TABLE: Ident = Ident::new("my_table_name");
 /// }
 ///
-/// assert_eq!(MyStruct::TABLE, "my_table_name");
+/// assert_eq!(MyStruct::TABLE.as_str(), "my_table_name");
 /// ```
 pub trait Table {
-    const TABLE: &'static str;
+    const TABLE: Ident;
+}
+
+/// Describes how a type is laid out as a row in the database.
+///
+/// Implemented on the `*Sql` row types generated by `#[derive(Model)]`, giving the column names
+/// in the same order their values are pushed by `push_values`.
+pub trait SqlRow: for<'r> FromRow<'r, SqliteRow> + Send + Unpin {
+    /// Names of every column in the row
+    const COLUMNS: &'static [Ident];
+
+    /// Bind each of the row's values, in the same order as `COLUMNS`
+    fn push_values(self, values: &mut Separated<'_, '_, Sqlite, &'static str>);
+}
+
+/// Conditions narrowing down the rows read by `Read::read_many_filtered`, on top of the scope
+///
+/// Each condition is pushed onto a query that already has a `WHERE` clause, so it must start with
+/// ` AND`. Columns should only be written as an [`Ident`], & values only ever bound.
+///
+/// ```
+/// use sqlx::{QueryBuilder, Sqlite};
+///
+/// use hoops_app::models::{Filter, Ident};
+///
+/// struct TxtIs(String);
+///
+/// impl Filter for TxtIs {
+///   fn push_conditions(&self, query: &mut QueryBuilder<'_, Sqlite>) {
+///     query.push(" AND ").push(Ident::new("txt")).push(" = ");
+///     query.push_bind(self.0.clone());
+///   }
+/// }
+///
+/// let mut query = QueryBuilder::<Sqlite>::new("SELECT * FROM my_table WHERE 1");
+/// TxtIs(String::from("b")).push_conditions(&mut query);
+/// ().push_conditions(&mut query);
+/// assert_eq!(query.sql(), "SELECT * FROM my_table WHERE 1 AND \"txt\" = ?");
+/// ```
+pub trait Filter {
+    fn push_conditions(&self, query: &mut QueryBuilder<'_, Sqlite>);
+}
+
+/// No conditions at all, reading every row in the scope
+impl Filter for () {
+    fn push_conditions(&self, _query: &mut QueryBuilder<'_, Sqlite>) {}
+}
+
+/// The filter's conditions if there is one, or none at all
+impl<F: Filter> Filter for Option<F> {
+    fn push_conditions(&self, query: &mut QueryBuilder<'_, Sqlite>) {
+        if let Some(filter) = self {
+            filter.push_conditions(query);
+        }
+    }
+}
+
+/// Both filters' conditions, so rows have to match both
+impl<A: Filter, B: Filter> Filter for (A, B) {
+    fn push_conditions(&self, query: &mut QueryBuilder<'_, Sqlite>) {
+        self.0.push_conditions(query);
+        self.1.push_conditions(query);
+    }
 }
 
 /// Methods for saving a type to a database.
 ///
 /// Requires a second type that the first one can be translated into/from for the actual data types
 /// that will be stored in the database. Also requires that the type knows it's table name, via
+/// the `Table` trait. All of this can be generated with `#[derive(Model)]`.
+///
+/// ```
+/// use uuid::Uuid;
+/// use sqlx::SqlitePool;
+///
+/// use hoops_app::models::{Create, Scope};
+/// use hoops_macros::Model;
+///
+/// // generate the table name, `MyTypeSql` row type, & conversions between the two
+/// #[derive(Model)]
+/// #[model(table = "my_table")]
+/// struct MyType {
+///   id: Uuid,
+///   txt: Option<String>,
+/// }
+///
+/// // implement default create methods for MyType
+/// impl Create for MyType {
+///   type SqlType = MyTypeSql;
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), anyhow::Error> {
+/// let pool = SqlitePool::connect("sqlite::memory:").await?;
+/// # sqlx::query("CREATE TABLE my_table (id TEXT PRIMARY KEY NOT NULL, txt TEXT, owner_id TEXT) STRICT;")
+/// #     .execute(&pool)
+/// #     .await?;
+/// // create a new MyType value using the newly implemented default methods
+/// let scope = Scope::new(Uuid::new_v4());
+/// let id = Uuid::new_v4();
+/// MyType::create_one(&pool, scope, MyType {
+///   id,
+///   txt: Some(String::from("Some text")),
+/// }).await?;
+///
+/// // the new row is owned by the scope's user
+/// let (txt, owner_id): (String, String) =
+///     sqlx::query_as("SELECT txt, owner_id FROM my_table WHERE id = ?;")
+///         .bind(id.to_string())
+///         .fetch_one(&pool)
+///         .await?;
+/// assert_eq!(txt, "Some text");
+/// assert_eq!(owner_id, scope.owner_id().to_string());
+/// # Ok(())
+/// # }
+/// ```
+pub trait Create: Sized + Table + Send {
+    type SqlType: From<Self> + SqlRow;
+
+    /// Insert the given item into the database, owned by the scope's user
+    fn create_one<'c, E>(
+        executor: E,
+        scope: Scope,
+        value: Self,
+    ) -> impl std::future::Future<Output = Result<(), anyhow::Error>> + Send
+    where
+        E: Executor<'c, Database = Sqlite>,
+    {
+        async move {
+            Repository::<Self, Self::SqlType>::new(Self::TABLE)
+                .create_one(executor, scope, value)
+                .await
+        }
+    }
+
+    /// Insert every one of the given items into the database, owned by the scope's user
+    ///
+    /// The items are inserted a batch at a time, so this should be run inside a database
+    /// transaction for them to be saved all together or not at all.
+    fn create_many(
+        conn: &mut SqliteConnection,
+        scope: Scope,
+        values: Vec<Self>,
+    ) -> impl std::future::Future<Output = Result<(), anyhow::Error>> + Send {
+        async move {
+            Repository::<Self, Self::SqlType>::new(Self::TABLE)
+                .create_many(conn, scope, values)
+                .await
+        }
+    }
+}
+
+/// Methods for reading a type from a database.
+///
+/// Requires a second type that the first one can be translated into/from for the actual data types
+/// that will be stored in the database. Also requires that the type knows it's table name, via
 /// the `Table` trait.
 ///
+/// Default implementations are provided for every method, so implementing the trait only requires
+/// naming the `SqlType`. The ordering used by `read_many` can be changed by overriding `ORDER_BY`.
+///
 /// ```
 /// use uuid::Uuid;
 /// use std::convert::TryFrom;
 /// use sqlx::{FromRow, SqlitePool, sqlite::SqliteRow};
 ///
-/// use hoops_app::models::{ Create, Table };
+/// use hoops_app::models::{ Ident, OrderBy, Read, Scope, Table };
 ///
+/// #[derive(Debug, PartialEq)]
 /// struct MyType {
 ///   id: Uuid,
 ///   txt: String,
@@ -66,130 +400,393 @@ pub trait Table {
 ///   }
 /// }
 ///
-/// // save the associated table name as a const on MyType
 /// impl Table for MyType {
-///   const TABLE: &'static str = "my_table";
+///   const TABLE: Ident = Ident::new("my_table");
 /// }
 ///
-/// // implement default create methods for MyType
-/// impl Create<'_> for MyType {
+/// // implement default read methods for MyType, ordering results by `txt`
+/// impl Read for MyType {
 ///   type SqlType = MyTypeSql;
+///
+///   const ORDER_BY: &'static [OrderBy] = &[OrderBy::asc(Ident::new("txt"))];
 /// }
 ///
-/// // create a new MyType value using the newly implemented default methods
-/// MyType::create_one(MyType {
-///   id: Uuid::new_v4(),
-///   txt: String::from("Some text"),
-/// });
-///
-/// // TODO: assert it exists? probably just use sqlx directly for that...
-/// ```
-pub trait Create<'r>: Sized + Table {
-    type SqlType: From<Self> + FromRow<'r, SqliteRow>;
-
-    // TODO: Write derive proc macro to write a fn like below automatically for the given struct
-    // fn create_one(pool: &SqlitePool, value: Self) -> Result<(), anyhow::Error> {
-    //   ...get cols & vals out as arrays
-    //   let cols: [&str] = ...;
-    //   let vals: (...) = ...;
-    //
-    //   then call from cols and vals:
-    //   Self::create_one_from_cols_and_vals(pool, cols, vals)
-    // }
-
-    // /// Insert the given item into the database
-    // fn create_one_from_cols_and_vals(
-    //     pool: &SqlitePool,
-    //     value: Self,
-    //     columns: &[&str],
-    // ) -> impl std::future::Future<Output = Result<(), anyhow::Error>> + Send;
-
-    // instead, for now just use this one
-    /// Insert the given item into the database
-    fn create_one(
-        pool: &SqlitePool,
-        value: Self,
-    ) -> impl std::future::Future<Output = Result<(), anyhow::Error>> + Send {
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), anyhow::Error> {
+/// let pool = SqlitePool::connect("sqlite::memory:").await?;
+/// # sqlx::query("CREATE TABLE my_table (id TEXT PRIMARY KEY NOT NULL, txt TEXT, owner_id TEXT) STRICT;")
+/// #     .execute(&pool)
+/// #     .await?;
+/// let scope = Scope::new(Uuid::new_v4());
+/// let other = Scope::new(Uuid::new_v4());
+/// let id = Uuid::new_v4();
+/// let other_id = Uuid::new_v4();
+/// sqlx::query("INSERT INTO my_table (id, txt, owner_id) VALUES (?1, 'b', ?2), (?3, 'a', ?2), (?4, 'c', ?5);")
+///     .bind(id.to_string())
+///     .bind(scope.owner_id().to_string())
+///     .bind(Uuid::new_v4().to_string())
+///     .bind(other_id.to_string())
+///     .bind(other.owner_id().to_string())
+///     .execute(&pool)
+///     .await?;
+///
+/// // read a single item by its id
+/// let one = MyType::read_one_by_id(&pool, scope, id).await?;
+/// assert_eq!(one, Some(MyType { id, txt: String::from("b") }));
+///
+/// // or read many, ordered by `ORDER_BY`
+/// let many = MyType::read_many(&pool, scope, None, None).await?;
+/// assert_eq!(many.iter().map(|t| t.txt.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+///
+/// // using limit & offset to paginate the results
+/// let page = MyType::read_many(&pool, scope, Some(1), Some(1)).await?;
+/// assert_eq!(page, vec![MyType { id, txt: String::from("b") }]);
+///
+/// // items owned by another user are never read
+/// assert_eq!(MyType::read_one_by_id(&pool, scope, other_id).await?, None);
+/// let theirs = MyType::read_many(&pool, other, None, None).await?;
+/// assert_eq!(theirs, vec![MyType { id: other_id, txt: String::from("c") }]);
+/// # Ok(())
+/// # }
+/// ```
+pub trait Read: TryFrom<Self::SqlType, Error = anyhow::Error> + Table + Sized + Send {
+    type SqlType: From<Self> + for<'r> FromRow<'r, SqliteRow> + Send + Unpin;
+
+    /// Columns used to sort the results of `read_many`
+    const ORDER_BY: &'static [OrderBy] = &[OrderBy::asc(repository::ID)];
+
+    /// Read one item with given ID from the database, if it exists & is owned by the scope's user
+    fn read_one_by_id<'c, E>(
+        executor: E,
+        scope: Scope,
+        id: Uuid,
+    ) -> impl std::future::Future<Output = Result<Option<Self>, anyhow::Error>> + Send
+    where
+        E: Executor<'c, Database = Sqlite>,
+    {
+        async move {
+            Repository::<Self, Self::SqlType>::new(Self::TABLE)
+                .read_one_by_id(executor, scope, id)
+                .await
+        }
+    }
+
+    /// Read many items owned by the scope's user from the database
+    ///
+    /// Uses the given limit & offset for pagination, or defaults to 100 rows starting with the
+    /// first result.
+    fn read_many<'c, E>(
+        executor: E,
+        scope: Scope,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> impl std::future::Future<Output = Result<Vec<Self>, anyhow::Error>> + Send
+    where
+        E: Executor<'c, Database = Sqlite>,
+    {
         async move {
-            let _ = async { 1 }.await;
-            unimplemented!()
+            Repository::<Self, Self::SqlType>::new(Self::TABLE)
+                .read_many(executor, scope, Self::ORDER_BY, limit, offset)
+                .await
+        }
+    }
+
+    /// Read many items owned by the scope's user that match the given filter, sorted by the given
+    /// columns instead of `ORDER_BY`
+    ///
+    /// Uses the given limit & offset for pagination, or defaults to 100 rows starting with the
+    /// first result.
+    fn read_many_filtered<'c, 'a, E, F>(
+        executor: E,
+        scope: Scope,
+        filter: &'a F,
+        order_by: &'a [OrderBy],
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> impl std::future::Future<Output = Result<Vec<Self>, anyhow::Error>> + Send + 'a
+    where
+        E: Executor<'c, Database = Sqlite> + 'a,
+        F: Filter + Sync + ?Sized,
+    {
+        async move {
+            Repository::<Self, Self::SqlType>::new(Self::TABLE)
+                .read_many_filtered(executor, scope, filter, order_by, limit, offset)
+                .await
         }
     }
 }
 
-/// Methods for saving a type to a database.
+/// Methods for changing a type that is already saved in a database.
 ///
-/// Requires a second type that the first one can be translated into/from for the actual data types
-/// that will be stored in the database. Also requires that the type knows it's table name, via
-/// the `Table` trait.
+/// Builds on `Read`, reusing its `SqlType` to return the item as it was saved after the change.
+/// A full replace is provided by `update_one_by_id` for any type whose `SqlType` is a `SqlRow`.
+/// Since which fields can be patched depends on the type, implementors must supply `apply_patch`,
+/// which `patch_one_by_id` uses to change only the fields set on the type's `Patch`.
 ///
 /// ```
 /// use uuid::Uuid;
-/// use std::convert::TryFrom;
-/// use sqlx::{FromRow, SqlitePool, sqlite::SqliteRow};
+/// use sqlx::SqlitePool;
 ///
-/// use hoops_app::models::{ Read, Table };
+/// use hoops_app::models::{ Read, Scope, Update };
+/// use hoops_macros::Model;
 ///
+/// #[derive(Debug, PartialEq, Model)]
+/// #[model(table = "my_table")]
 /// struct MyType {
 ///   id: Uuid,
 ///   txt: String,
 /// }
 ///
-/// #[derive(FromRow, Clone)]
-/// struct MyTypeSql {
-///   id: String,
-///   txt: String,
+/// /// Fields left as `None` are not changed when patching
+/// #[derive(Default)]
+/// struct MyTypePatch {
+///   txt: Option<String>,
 /// }
 ///
-/// impl From<MyType> for MyTypeSql {
-///   fn from(value: MyType) -> Self {
-///     let MyType { id, txt } = value;
-///     let id = id
-///         .hyphenated()
-///         .encode_lower(&mut Uuid::encode_buffer())
-///         .to_string();
+/// impl Read for MyType {
+///   type SqlType = MyTypeSql;
+/// }
 ///
-///     Self { id, txt }
+/// impl Update for MyType {
+///   type Patch = MyTypePatch;
+///
+///   fn apply_patch(&mut self, patch: MyTypePatch) {
+///     if let Some(txt) = patch.txt {
+///       self.txt = txt;
+///     }
 ///   }
 /// }
 ///
-/// impl TryFrom<MyTypeSql> for MyType {
-///   type Error = anyhow::Error;
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), anyhow::Error> {
+/// let pool = SqlitePool::connect("sqlite::memory:").await?;
+/// # sqlx::query("CREATE TABLE my_table (id TEXT PRIMARY KEY NOT NULL, txt TEXT, owner_id TEXT) STRICT;")
+/// #     .execute(&pool)
+/// #     .await?;
+/// let scope = Scope::new(Uuid::new_v4());
+/// let id = Uuid::new_v4();
+/// sqlx::query("INSERT INTO my_table (id, txt, owner_id) VALUES (?, 'old', ?);")
+///     .bind(id.to_string())
+///     .bind(scope.owner_id().to_string())
+///     .execute(&pool)
+///     .await?;
 ///
-///   fn try_from(value: MyTypeSql) -> Result<Self, Self::Error> {
-///     let MyTypeSql { id, txt } = value;
-///     let id = Uuid::parse_str(&id)?;
+/// // an empty patch leaves the row as it was
+/// let patched = MyType::patch_one_by_id(&pool, scope, id, MyTypePatch::default()).await?;
+/// assert_eq!(patched, Some(MyType { id, txt: String::from("old") }));
 ///
-///     Ok(MyType{ id, txt })
-///   }
-/// }
+/// // while a full update replaces every column
+/// let updated = MyType::update_one_by_id(&pool, scope, id, MyType { id, txt: String::from("new") }).await?;
+/// assert_eq!(updated, Some(MyType { id, txt: String::from("new") }));
+///
+/// // updating an item that doesn't exist returns nothing
+/// let missing = MyType::patch_one_by_id(&pool, scope, Uuid::new_v4(), MyTypePatch::default()).await?;
+/// assert_eq!(missing, None);
+///
+/// // & so does updating an item owned by another user, which is left unchanged
+/// let other = Scope::new(Uuid::new_v4());
+/// let patch = MyTypePatch { txt: Some(String::from("theirs")) };
+/// assert_eq!(MyType::patch_one_by_id(&pool, other, id, patch).await?, None);
+/// let theirs = MyType { id, txt: String::from("theirs") };
+/// assert_eq!(MyType::update_one_by_id(&pool, other, id, theirs).await?, None);
+/// let unchanged = MyType::read_one_by_id(&pool, scope, id).await?;
+/// assert_eq!(unchanged, Some(MyType { id, txt: String::from("new") }));
+/// # Ok(())
+/// # }
+/// ```
+pub trait Update: Read<SqlType: SqlRow> {
+    /// A partial version of the type, where every field that is set will be written
+    type Patch: Send;
+
+    /// Change only the fields set on the given patch
+    fn apply_patch(&mut self, patch: Self::Patch);
+
+    /// Replace every column of the item with given ID, returning the updated item if it exists &
+    /// is owned by the scope's user
+    ///
+    /// The ID on the given value is ignored in favor of the `id` argument.
+    fn update_one_by_id<'c, E>(
+        executor: E,
+        scope: Scope,
+        id: Uuid,
+        value: Self,
+    ) -> impl std::future::Future<Output = Result<Option<Self>, anyhow::Error>> + Send
+    where
+        E: Executor<'c, Database = Sqlite>,
+    {
+        async move {
+            Repository::<Self, Self::SqlType>::new(Self::TABLE)
+                .update_one_by_id(executor, scope, id, value)
+                .await
+        }
+    }
+
+    /// Change only the fields set on the given patch for the item with given ID, returning the
+    /// updated item if it exists & is owned by the scope's user
+    ///
+    /// The item is read & written back inside a database transaction, so no other change to it can
+    /// be lost in between.
+    fn patch_one_by_id<'c, A>(
+        acquire: A,
+        scope: Scope,
+        id: Uuid,
+        patch: Self::Patch,
+    ) -> impl std::future::Future<Output = Result<Option<Self>, anyhow::Error>> + Send
+    where
+        A: Acquire<'c, Database = Sqlite> + Send,
+    {
+        async move {
+            let repository = Repository::<Self, Self::SqlType>::new(Self::TABLE);
+
+            let mut tx = acquire.begin().await?;
+            let conn: &mut SqliteConnection = &mut tx;
+            let Some(mut value) = repository.read_one_by_id(&mut *conn, scope, id).await? else {
+                return Ok(None);
+            };
+            value.apply_patch(patch);
+            let updated = repository.update_one_by_id(conn, scope, id, value).await?;
+            tx.commit().await?;
+
+            Ok(updated)
+        }
+    }
+}
+
+/// Methods for removing a type from a database.
+///
+/// Only requires that the type knows it's table name, via the `Table` trait.
+///
+/// ```
+/// use uuid::Uuid;
+/// use sqlx::SqlitePool;
+///
+/// use hoops_app::models::{ Delete, Ident, Scope, Table };
+///
+/// struct MyType;
 ///
 /// impl Table for MyType {
-///   const TABLE: &'static str = "my_table";
+///   const TABLE: Ident = Ident::new("my_table");
 /// }
 ///
-/// impl Read<'_> for MyType {
-///   type SqlType = MyTypeSql;
+/// // implement default delete methods for MyType
+/// impl Delete for MyType {}
 ///
-///   async fn read_many(self, pool: &SqlitePool) -> Result<Vec<MyType>, anyhow::Error> {
-///     // write actual sqlx query here?
-///     // ideally I can do a default impl for this one since it'll be so common...
-///     todo!()
-///   }
-/// }
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), anyhow::Error> {
+/// let pool = SqlitePool::connect("sqlite::memory:").await?;
+/// # sqlx::query("CREATE TABLE my_table (id TEXT PRIMARY KEY NOT NULL, txt TEXT, owner_id TEXT) STRICT;")
+/// #     .execute(&pool)
+/// #     .await?;
+/// let scope = Scope::new(Uuid::new_v4());
+/// let id = Uuid::new_v4();
+/// sqlx::query("INSERT INTO my_table (id, txt, owner_id) VALUES (?, 'Some text', ?);")
+///     .bind(id.to_string())
+///     .bind(scope.owner_id().to_string())
+///     .execute(&pool)
+///     .await?;
+///
+/// // another user can't remove the row...
+/// assert!(!MyType::delete_one_by_id(&pool, Scope::new(Uuid::new_v4()), id).await?);
+/// // ...but its owner can the first time...
+/// assert!(MyType::delete_one_by_id(&pool, scope, id).await?);
+/// // ...so there's nothing left to remove the second time
+/// assert!(!MyType::delete_one_by_id(&pool, scope, id).await?);
+/// # Ok(())
+/// # }
 /// ```
-pub trait Read<'r>: TryFrom<Self::SqlType> + Table + Sized {
-    type SqlType: From<Self> + FromRow<'r, SqliteRow>;
-
-    /// Read one item with given ID from the database, if it exists
-    //fn read_one_by_id<Id>(
-    //    self,
-    //    pool: SqlitePool,
-    //    id: Id,
-    //) -> impl std::future::Future<Output = Result<Option<Self>, anyhow::Error>> + Send;
-    /// Read many items from the database
-    fn read_many(
-        self,
-        pool: &SqlitePool,
-    ) -> impl std::future::Future<Output = Result<Vec<Self>, anyhow::Error>> + Send;
+pub trait Delete: Table + Sized {
+    /// Delete the item with given ID from the database if it's owned by the scope's user,
+    /// returning whether a row was removed
+    fn delete_one_by_id<'c, E>(
+        executor: E,
+        scope: Scope,
+        id: Uuid,
+    ) -> impl std::future::Future<Output = Result<bool, anyhow::Error>> + Send
+    where
+        E: Executor<'c, Database = Sqlite>,
+    {
+        async move {
+            Repository::<Self, ()>::new(Self::TABLE)
+                .delete_one_by_id(executor, scope, id)
+                .await
+        }
+    }
+}
+
+/// Add up `(id, amount)` rows into a total for each ID.
+///
+/// Amounts are stored as text to keep their precision, so they're summed here as `Decimal` values
+/// instead of in SQL, where they would be converted to floats.
+///
+/// ```
+/// use rust_decimal::Decimal;
+/// use uuid::Uuid;
+///
+/// use hoops_app::models::sum_by_id;
+///
+/// let id = Uuid::new_v4();
+/// let rows = vec![
+///     (id.to_string(), String::from("0.10")),
+///     (id.to_string(), String::from("0.20")),
+/// ];
+///
+/// let totals = sum_by_id(rows).unwrap();
+/// assert_eq!(totals[&id], Decimal::new(30, 2));
+/// ```
+pub fn sum_by_id(
+    rows: Vec<(String, String)>,
+) -> Result<std::collections::HashMap<Uuid, rust_decimal::Decimal>, anyhow::Error> {
+    let mut totals = std::collections::HashMap::new();
+
+    for (id, amount) in rows {
+        let id = Uuid::parse_str(&id)?;
+        let amount = rust_decimal::Decimal::from_str_exact(&amount)?;
+        *totals.entry(id).or_default() += amount;
+    }
+
+    Ok(totals)
+}
+
+/// Database for doctests, in memory, with a user named "alice" & a "Checking" account of theirs
+///
+/// Returns the pool, alice's scope, & the account's ID. Only built with the `doc-fixtures`
+/// feature, which the crate turns on for its own tests.
+#[cfg(feature = "doc-fixtures")]
+pub async fn doc_fixture() -> Result<(sqlx::SqlitePool, Scope, Uuid), anyhow::Error> {
+    use crate::accounts::model::Account;
+
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await?;
+    sqlx::migrate!().run(&pool).await?;
+    let scope = doc_fixture_user(&pool, "alice").await?;
+    let account = Account::new("Checking".into(), None);
+    let account_id = account.id;
+    Account::create_one(&pool, scope, account).await?;
+
+    Ok((pool, scope, account_id))
+}
+
+/// The same database as [`doc_fixture`], with a second user named "bob" who has nothing saved
+///
+/// Returns the pool, alice's & bob's scopes, & the ID of alice's account.
+#[cfg(feature = "doc-fixtures")]
+pub async fn doc_fixture_two_users() -> Result<(sqlx::SqlitePool, Scope, Scope, Uuid), anyhow::Error>
+{
+    let (pool, alice, account_id) = doc_fixture().await?;
+    let bob = doc_fixture_user(&pool, "bob").await?;
+
+    Ok((pool, alice, bob, account_id))
+}
+
+/// Save a user with the given name, returning their scope
+#[cfg(feature = "doc-fixtures")]
+async fn doc_fixture_user(pool: &sqlx::SqlitePool, username: &str) -> Result<Scope, anyhow::Error> {
+    use chrono_tz::Tz;
+
+    use crate::users::model::{db_create_user, User};
+
+    let user = User::new(username.into(), String::new(), Tz::UTC);
+    let scope = Scope::new(user.id);
+    db_create_user(&mut *pool.acquire().await?, user).await?;
+
+    Ok(scope)
 }
@@ -0,0 +1,343 @@
+//! A generic repository implementing the most common CRUD queries once for every model.
+//!
+//! Queries are built dynamically with `sqlx::QueryBuilder`. Table & column names are only ever
+//! written into a query as an [`Ident`], so no runtime string can end up as part of the SQL.
+//!
+//! Every query is limited to the rows owned by the user of the given [`Scope`], by filtering on
+//! (or, when inserting, setting) the table's `owner_id` column.
+use std::marker::PhantomData;
+
+use sqlx::{sqlite::SqliteRow, Executor, FromRow, QueryBuilder, Sqlite, SqliteConnection};
+use uuid::Uuid;
+
+use super::{Filter, Ident, OrderBy, Scope, SqlRow};
+
+/// Name of the primary key column shared by every table
+pub const ID: Ident = Ident::new("id");
+
+/// Name of the column holding the ID of the user who owns a row, shared by every table
+pub const OWNER_ID: Ident = Ident::new("owner_id");
+
+/// Most rows inserted by a single statement, keeping it well under SQLite's limit on the number of
+/// values bound to one statement
+const ROWS_PER_INSERT: usize = 500;
+
+/// A repository for reading & writing `Model` values to a single table, storing them as
+/// `ModelSql` rows.
+///
+/// Every method takes any sqlx `Executor`, so the same queries can be run directly on a pool or
+/// inside a database transaction.
+///
+/// ```
+/// use uuid::Uuid;
+/// use sqlx::SqlitePool;
+///
+/// use hoops_app::models::{repository::Table, Ident, OrderBy, Scope};
+/// use hoops_macros::Model;
+///
+/// #[derive(Debug, PartialEq, Model)]
+/// #[model(table = "my_table")]
+/// struct MyType {
+///   id: Uuid,
+///   txt: Option<String>,
+/// }
+///
+/// const MY_TABLE: Table<MyType, MyTypeSql> = Table::new(Ident::new("my_table"));
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), anyhow::Error> {
+/// let pool = SqlitePool::connect("sqlite::memory:").await?;
+/// # sqlx::query("CREATE TABLE my_table (id TEXT PRIMARY KEY NOT NULL, txt TEXT, owner_id TEXT) STRICT;")
+/// #     .execute(&pool)
+/// #     .await?;
+/// let scope = Scope::new(Uuid::new_v4());
+/// let id = Uuid::new_v4();
+/// MY_TABLE.create_one(&pool, scope, MyType { id, txt: None }).await?;
+///
+/// // changes can be grouped in a transaction by passing it as the executor
+/// let mut tx = pool.begin().await?;
+/// let txt = Some(String::from("Some text"));
+/// let updated = MY_TABLE
+///     .update_one_by_id(&mut *tx, scope, id, MyType { id, txt: txt.clone() })
+///     .await?;
+/// tx.commit().await?;
+/// assert_eq!(updated, Some(MyType { id, txt }));
+///
+/// let all = MY_TABLE
+///     .read_many(&pool, scope, &[OrderBy::asc(Ident::new("txt"))], None, None)
+///     .await?;
+/// assert_eq!(all.len(), 1);
+///
+/// // many items can be inserted at once, in a transaction so they're saved all together
+/// let mut tx = pool.begin().await?;
+/// let more = (0..3).map(|_| MyType { id: Uuid::new_v4(), txt: None }).collect();
+/// MY_TABLE.create_many(&mut tx, scope, more).await?;
+/// tx.commit().await?;
+/// assert_eq!(MY_TABLE.read_many(&pool, scope, &[], None, None).await?.len(), 4);
+///
+/// // rows owned by one user can't be seen by another
+/// let other = Scope::new(Uuid::new_v4());
+/// assert_eq!(MY_TABLE.read_one_by_id(&pool, other, id).await?, None);
+/// assert!(!MY_TABLE.delete_one_by_id(&pool, other, id).await?);
+///
+/// assert!(MY_TABLE.delete_one_by_id(&pool, scope, id).await?);
+/// assert_eq!(MY_TABLE.read_one_by_id(&pool, scope, id).await?, None);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Table<Model, ModelSql> {
+    name: Ident,
+    // `fn() -> ...` keeps the repository `Send + Sync` no matter what the model types are
+    types: PhantomData<fn() -> (Model, ModelSql)>,
+}
+
+impl<Model, ModelSql> Table<Model, ModelSql> {
+    pub const fn new(name: Ident) -> Self {
+        Self {
+            name,
+            types: PhantomData,
+        }
+    }
+
+    /// Name of the table used in every query
+    pub const fn name(&self) -> Ident {
+        self.name
+    }
+
+    /// Delete the item with given ID, returning whether a row was removed
+    pub async fn delete_one_by_id<'c, E>(
+        &self,
+        executor: E,
+        scope: Scope,
+        id: Uuid,
+    ) -> Result<bool, anyhow::Error>
+    where
+        E: Executor<'c, Database = Sqlite>,
+    {
+        let mut query = QueryBuilder::<Sqlite>::new("DELETE FROM ");
+        query.push(self.name);
+        push_where_id(&mut query, scope, id);
+        query.push(";");
+
+        let result = query.build().execute(executor).await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+impl<Model, ModelSql> Table<Model, ModelSql>
+where
+    Model: TryFrom<ModelSql, Error = anyhow::Error>,
+    ModelSql: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+{
+    /// Read one item with given ID, if it exists
+    pub async fn read_one_by_id<'c, E>(
+        &self,
+        executor: E,
+        scope: Scope,
+        id: Uuid,
+    ) -> Result<Option<Model>, anyhow::Error>
+    where
+        E: Executor<'c, Database = Sqlite>,
+    {
+        let mut query = QueryBuilder::<Sqlite>::new("SELECT * FROM ");
+        query.push(self.name);
+        push_where_id(&mut query, scope, id);
+        query.push(";");
+
+        query
+            .build_query_as::<ModelSql>()
+            .fetch_optional(executor)
+            .await?
+            .map(Model::try_from)
+            .transpose()
+    }
+
+    /// Read a page of items, sorted by the given columns
+    ///
+    /// Uses the given limit & offset for pagination, or defaults to 100 rows starting with the
+    /// first result.
+    pub async fn read_many<'c, E>(
+        &self,
+        executor: E,
+        scope: Scope,
+        order_by: &[OrderBy],
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Vec<Model>, anyhow::Error>
+    where
+        E: Executor<'c, Database = Sqlite>,
+    {
+        self.read_many_filtered(executor, scope, &(), order_by, limit, offset)
+            .await
+    }
+
+    /// Read a page of the items matching the given filter, sorted by the given columns
+    ///
+    /// Uses the given limit & offset for pagination, or defaults to 100 rows starting with the
+    /// first result.
+    pub async fn read_many_filtered<'c, E, F>(
+        &self,
+        executor: E,
+        scope: Scope,
+        filter: &F,
+        order_by: &[OrderBy],
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Vec<Model>, anyhow::Error>
+    where
+        E: Executor<'c, Database = Sqlite>,
+        F: Filter + ?Sized,
+    {
+        let mut query = QueryBuilder::<Sqlite>::new("SELECT * FROM ");
+        query.push(self.name);
+        query.push(" WHERE ").push(OWNER_ID).push(" = ");
+        query.push_bind(scope.owner_id().to_string());
+        filter.push_conditions(&mut query);
+        push_order_by(&mut query, order_by);
+        query.push(" LIMIT ").push_bind(limit.unwrap_or(100));
+        query.push(" OFFSET ").push_bind(offset.unwrap_or(0));
+        query.push(";");
+
+        query
+            .build_query_as::<ModelSql>()
+            .fetch_all(executor)
+            .await?
+            .into_iter()
+            .map(Model::try_from)
+            .collect()
+    }
+}
+
+impl<Model, ModelSql> Table<Model, ModelSql>
+where
+    ModelSql: From<Model> + SqlRow,
+{
+    /// Insert the given item, owned by the scope's user
+    pub async fn create_one<'c, E>(
+        &self,
+        executor: E,
+        scope: Scope,
+        value: Model,
+    ) -> Result<(), anyhow::Error>
+    where
+        E: Executor<'c, Database = Sqlite>,
+    {
+        let mut query = QueryBuilder::<Sqlite>::new("INSERT INTO ");
+        query.push(self.name);
+        push_columns(&mut query, ModelSql::COLUMNS, &[OWNER_ID]);
+        query.push(" VALUES (");
+        let mut values = query.separated(", ");
+        ModelSql::from(value).push_values(&mut values);
+        values.push_bind(scope.owner_id().to_string());
+        values.push_unseparated(");");
+
+        query.build().execute(executor).await?;
+
+        Ok(())
+    }
+
+    /// Insert every one of the given items, owned by the scope's user, up to `ROWS_PER_INSERT`
+    /// at a time
+    pub async fn create_many(
+        &self,
+        conn: &mut SqliteConnection,
+        scope: Scope,
+        values: Vec<Model>,
+    ) -> Result<(), anyhow::Error> {
+        let owner_id = scope.owner_id().to_string();
+        let mut rows = values.into_iter().map(ModelSql::from).peekable();
+
+        while rows.peek().is_some() {
+            let mut query = QueryBuilder::<Sqlite>::new("INSERT INTO ");
+            query.push(self.name);
+            push_columns(&mut query, ModelSql::COLUMNS, &[OWNER_ID]);
+            query.push(" ");
+            query.push_values(rows.by_ref().take(ROWS_PER_INSERT), |mut values, row| {
+                row.push_values(&mut values);
+                values.push_bind(owner_id.clone());
+            });
+            query.push(";");
+
+            query.build().execute(&mut *conn).await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<Model, ModelSql> Table<Model, ModelSql>
+where
+    Model: TryFrom<ModelSql, Error = anyhow::Error>,
+    ModelSql: From<Model> + SqlRow,
+{
+    /// Replace every column of the item with given ID, returning the updated item if it exists
+    ///
+    /// The ID on the given value is ignored in favor of the `id` argument, & the item's owner is
+    /// never changed.
+    pub async fn update_one_by_id<'c, E>(
+        &self,
+        executor: E,
+        scope: Scope,
+        id: Uuid,
+        value: Model,
+    ) -> Result<Option<Model>, anyhow::Error>
+    where
+        E: Executor<'c, Database = Sqlite>,
+    {
+        let mut query = QueryBuilder::<Sqlite>::new("UPDATE ");
+        query.push(self.name);
+        query.push(" SET");
+        push_columns(&mut query, ModelSql::COLUMNS, &[]);
+        query.push(" = (");
+        let mut values = query.separated(", ");
+        ModelSql::from(value).push_values(&mut values);
+        values.push_unseparated(")");
+        // SQLite ignores all but the rightmost assignment to a column, so setting the ID again
+        // here keeps the value's own ID from being written
+        query.push(", ").push(ID).push(" = ");
+        query.push_bind(id.to_string());
+        push_where_id(&mut query, scope, id);
+        query.push(" RETURNING *;");
+
+        query
+            .build_query_as::<ModelSql>()
+            .fetch_optional(executor)
+            .await?
+            .map(Model::try_from)
+            .transpose()
+    }
+}
+
+/// Push a parenthesized, comma separated list of columns followed by any extra ones, e.g.
+/// ` ("id", "txt", "owner_id")`
+fn push_columns(query: &mut QueryBuilder<'_, Sqlite>, columns: &[Ident], extra: &[Ident]) {
+    query.push(" (");
+    let mut separated = query.separated(", ");
+    for column in columns.iter().chain(extra) {
+        separated.push(column);
+    }
+    separated.push_unseparated(")");
+}
+
+/// Push a `WHERE` clause matching the item with given ID, if it's owned by the scope's user
+fn push_where_id(query: &mut QueryBuilder<'_, Sqlite>, scope: Scope, id: Uuid) {
+    query.push(" WHERE ").push(ID).push(" = ");
+    query.push_bind(id.to_string());
+    query.push(" AND ").push(OWNER_ID).push(" = ");
+    query.push_bind(scope.owner_id().to_string());
+}
+
+/// Push an `ORDER BY` clause for the given columns, if there are any
+fn push_order_by(query: &mut QueryBuilder<'_, Sqlite>, order_by: &[OrderBy]) {
+    if order_by.is_empty() {
+        return;
+    }
+
+    query.push(" ORDER BY ");
+    let mut separated = query.separated(", ");
+    for OrderBy { column, direction } in order_by {
+        separated.push(column);
+        separated.push_unseparated(direction);
+    }
+}
@@ -0,0 +1,131 @@
+use leptos::*;
+use leptos_router::*;
+
+use crate::accounts::model::{Account, AccountSummary, Balances};
+use crate::safe_to_spend::model::SafeToSpend;
+
+#[cfg(feature = "ssr")]
+use crate::{state::pool, users::scope};
+
+/// Server endpoint for calculating the Safe-to-Spend balance & its breakdown
+#[server(prefix = "/api", endpoint = "safe-to-spend/read")]
+pub async fn safe_to_spend_read() -> Result<SafeToSpend, ServerFnError> {
+    use crate::safe_to_spend::model::db_read;
+
+    let scope = scope()?;
+    let pool = &pool()?;
+
+    db_read(pool, scope)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// Context telling the Safe-to-Spend widget its balance needs to be fetched again
+#[derive(Clone, Copy)]
+pub struct BalancesChanged(Trigger);
+
+/// Provide the context used by [`refresh_after`] to the rest of the app
+pub fn provide_balances_changed() {
+    provide_context(BalancesChanged(create_trigger()));
+}
+
+/// Refetch the Safe-to-Spend balance every time an action with the given version is run
+pub fn refresh_after(version: RwSignal<usize>) {
+    let Some(BalancesChanged(trigger)) = use_context() else {
+        return;
+    };
+
+    create_effect(move |prev: Option<usize>| {
+        let version = version.get();
+        // the first run only sets up tracking, since nothing has changed yet
+        if prev.is_some() {
+            trigger.notify();
+        }
+        version
+    });
+}
+
+/// Header widget showing the Safe-to-Spend balance, which expands to show how it was calculated
+#[component]
+pub fn Widget() -> impl IntoView {
+    let changed = use_context::<BalancesChanged>();
+    let pathname = use_location().pathname;
+    let expanded = create_rw_signal(false);
+
+    // refetch on every page change as well, since transactions could have been added elsewhere
+    let safe_to_spend = create_resource(
+        move || {
+            if let Some(BalancesChanged(trigger)) = changed {
+                trigger.track();
+            }
+            pathname.get()
+        },
+        |_| safe_to_spend_read(),
+    );
+
+    view! {
+        <section class="safe-to-spend">
+            <Suspense fallback=move || view! {<p>Loading...</p>}.into_view()>
+                {move || safe_to_spend.get().map(|safe_to_spend| match safe_to_spend {
+                    Err(err) => {
+                        view! { <p class="error">Error fetching Safe-to-Spend: {err.to_string()}</p>}.into_view()
+                    },
+                    Ok(safe_to_spend) => {
+                        let total = safe_to_spend.total().to_string();
+
+                        view! {
+                            <button on:click=move |_| expanded.update(|e| *e = !*e)>
+                                "Safe-to-Spend: "{total}
+                            </button>
+                            {move || expanded.get().then(|| view! {
+                                <Breakdown safe_to_spend=safe_to_spend.clone() />
+                            })}
+                        }.into_view()
+                    },
+                })}
+            </Suspense>
+        </section>
+    }
+}
+
+/// The calculation of the Safe-to-Spend balance, account by account
+#[component]
+fn Breakdown(safe_to_spend: SafeToSpend) -> impl IntoView {
+    let SafeToSpend {
+        accounts,
+        allocated,
+    } = safe_to_spend;
+
+    view! {
+        <ul>
+            {accounts.into_iter().map(|summary| view! { <AccountItem summary /> }).collect_view()}
+            <li><A href="/hoops">"Allocated in hoops: "{(-allocated).to_string()}</A></li>
+        </ul>
+    }
+}
+
+/// One account's part of the Safe-to-Spend calculation
+#[component]
+fn AccountItem(summary: AccountSummary) -> impl IntoView {
+    let AccountSummary {
+        account: Account { id, name, .. },
+        balances:
+            Balances {
+                available,
+                scheduled,
+                pending,
+            },
+    } = summary;
+    let href = format!("/accounts/{id}");
+
+    view! {
+        <li>
+            {name}
+            <ul>
+                <li><A href=href.clone()>"Available balance: "{available.to_string()}</A></li>
+                <li><A href=href.clone()>"Scheduled transactions: "{scheduled.to_string()}</A></li>
+                <li><A href=href>"Pending transactions: "{pending.to_string()}</A></li>
+            </ul>
+        </li>
+    }
+}
@@ -0,0 +1,102 @@
+use cfg_if::cfg_if;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::accounts::model::AccountSummary;
+
+/// The money that can be spent without dipping into a hoop, along with how it was calculated
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SafeToSpend {
+    /// Every open account with its balances
+    pub accounts: Vec<AccountSummary>,
+    /// Sum of the balances of every hoop
+    pub allocated: Decimal,
+}
+
+impl SafeToSpend {
+    /// The open accounts' available balances, less the money allocated in hoops & the money
+    /// scheduled or pending to leave the accounts
+    ///
+    /// ```
+    /// use rust_decimal::Decimal;
+    /// use uuid::Uuid;
+    ///
+    /// use hoops_app::accounts::model::{Account, AccountSummary, Balances};
+    /// use hoops_app::safe_to_spend::model::SafeToSpend;
+    ///
+    /// let summary = AccountSummary {
+    ///     account: Account::new(String::from("Checking"), None),
+    ///     balances: Balances {
+    ///         available: Decimal::new(1000, 0),
+    ///         scheduled: Decimal::new(-200, 0),
+    ///         pending: Decimal::new(-50, 0),
+    ///     },
+    /// };
+    /// let safe_to_spend = SafeToSpend {
+    ///     accounts: vec![summary],
+    ///     allocated: Decimal::new(300, 0),
+    /// };
+    ///
+    /// assert_eq!(safe_to_spend.total(), Decimal::new(450, 0));
+    /// ```
+    pub fn total(&self) -> Decimal {
+        let remaining: Decimal = self
+            .accounts
+            .iter()
+            .map(|summary| summary.balances.remaining())
+            .sum();
+
+        remaining - self.allocated
+    }
+}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use sqlx::{Executor, Sqlite};
+
+        use crate::{accounts, hoops, models::Scope};
+
+        /// Read the balances of every open account & hoop owned by the scope's user needed to
+        /// calculate their Safe-to-Spend
+        ///
+        /// ```
+        /// use chrono::Utc;
+        /// use rust_decimal::Decimal;
+        ///
+        /// use hoops_app::hoops::model::{db_move_funds, Hoop};
+        /// use hoops_app::models::{doc_fixture_two_users, Create};
+        /// use hoops_app::safe_to_spend::model::db_read;
+        /// use hoops_app::transactions::model::{db_create_one_checked, Transaction};
+        ///
+        /// # #[tokio::main]
+        /// # async fn main() -> Result<(), anyhow::Error> {
+        /// let (pool, alice, bob, account_id) = doc_fixture_two_users().await?;
+        /// let mut conn = pool.acquire().await?;
+        ///
+        /// let income = Transaction::new(Decimal::new(500, 0), "Work".into(), Utc::now(), None, account_id, None);
+        /// db_create_one_checked(&mut conn, alice, income).await?;
+        /// let hoop = Hoop::new("Rent".into());
+        /// let hoop_id = hoop.id;
+        /// Hoop::create_one(&pool, alice, hoop).await?;
+        /// db_move_funds(&mut conn, alice, None, Some(hoop_id), Decimal::new(50, 0)).await?;
+        ///
+        /// // money set aside in a hoop isn't safe to spend
+        /// assert_eq!(db_read(&pool, alice).await?.total(), Decimal::new(450, 0));
+        /// // & nothing owned by alice counts towards bob's
+        /// let theirs = db_read(&pool, bob).await?;
+        /// assert!(theirs.accounts.is_empty());
+        /// assert_eq!(theirs.total(), Decimal::ZERO);
+        /// # Ok(())
+        /// # }
+        /// ```
+        pub async fn db_read<'c, E>(executor: E, scope: Scope) -> Result<SafeToSpend, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite> + Copy,
+        {
+            let accounts = accounts::model::db_read_many_summaries(executor, scope, false).await?;
+            let allocated = hoops::model::db_balances(executor, scope).await?.into_values().sum();
+
+            Ok(SafeToSpend { accounts, allocated })
+        }
+    }
+}
@@ -3,15 +3,25 @@ use cfg_if::cfg_if;
 cfg_if! {
     if #[cfg(feature = "ssr")] {
         use axum::extract::FromRef;
-        use leptos::LeptosOptions;
+        use leptos::{use_context, LeptosOptions, ServerFnError};
         use leptos_router::RouteListing;
         use sqlx::SqlitePool;
 
+        use crate::events::Events;
+
         #[derive(FromRef, Debug, Clone)]
         pub struct AppState {
             pub leptos_options: LeptosOptions,
             pub pool: SqlitePool,
             pub routes: Vec<RouteListing>,
+            /// Channel for telling each user's open tabs & devices about changes to their data
+            pub events: Events,
+        }
+
+        /// Get the database pool provided to server functions as context
+        pub fn pool() -> Result<SqlitePool, ServerFnError> {
+            use_context::<SqlitePool>()
+                .ok_or_else(|| ServerFnError::ServerError("Pool missing".into()))
         }
     }
 }
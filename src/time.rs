@@ -0,0 +1,131 @@
+//! Converting timestamps between UTC, which they're saved in, & the user's time zone, which
+//! they're entered & shown in
+//!
+//! The client & server both use these, so a timestamp is read the same way when it's optimistically
+//! rendered as when it's saved.
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// Format of the values used by `<input type="datetime-local">`
+pub const DATETIME_STR: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// Format of the values used by `<input type="datetime-local">` when the seconds are left out
+const DATETIME_NO_SECONDS_STR: &str = "%Y-%m-%dT%H:%M";
+
+/// Parse a local date & time, as entered in a `datetime-local` input, in the given time zone
+///
+/// Browsers leave the seconds out when they're zero, so they're optional. A time repeated when the
+/// clocks go back is read as the first of the two, & a time skipped when they go forward isn't
+/// valid at all.
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use chrono_tz::Tz;
+///
+/// use hoops_app::time::parse_local;
+///
+/// let sydney: Tz = "Australia/Sydney".parse().unwrap();
+/// assert_eq!(
+///     parse_local("2024-01-27T09:30:15", sydney),
+///     Some(Utc.with_ymd_and_hms(2024, 1, 26, 22, 30, 15).unwrap()),
+/// );
+/// assert_eq!(
+///     parse_local("2024-01-27T09:30", sydney),
+///     Some(Utc.with_ymd_and_hms(2024, 1, 26, 22, 30, 0).unwrap()),
+/// );
+/// assert_eq!(
+///     parse_local("2024-01-27T09:30", Tz::UTC),
+///     Some(Utc.with_ymd_and_hms(2024, 1, 27, 9, 30, 0).unwrap()),
+/// );
+///
+/// // Sydney's clocks went forward from 2:00 to 3:00 that morning
+/// assert_eq!(parse_local("2023-10-01T02:30", sydney), None);
+/// assert_eq!(parse_local("yesterday", sydney), None);
+/// ```
+pub fn parse_local(value: &str, time_zone: Tz) -> Option<DateTime<Utc>> {
+    let naive = NaiveDateTime::parse_from_str(value, DATETIME_STR)
+        .or_else(|_| NaiveDateTime::parse_from_str(value, DATETIME_NO_SECONDS_STR))
+        .ok()?;
+
+    time_zone
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+}
+
+/// Format a timestamp as a `datetime-local` input's value in the given time zone
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use chrono_tz::Tz;
+///
+/// use hoops_app::time::{format_input, parse_local};
+///
+/// let sydney: Tz = "Australia/Sydney".parse().unwrap();
+/// let timestamp = Utc.with_ymd_and_hms(2024, 1, 26, 22, 30, 0).unwrap();
+/// assert_eq!(format_input(timestamp, sydney), "2024-01-27T09:30:00");
+/// assert_eq!(parse_local(&format_input(timestamp, sydney), sydney), Some(timestamp));
+/// ```
+pub fn format_input(timestamp: DateTime<Utc>, time_zone: Tz) -> String {
+    timestamp
+        .with_timezone(&time_zone)
+        .format(DATETIME_STR)
+        .to_string()
+}
+
+/// Format a timestamp for showing to the user, in the given time zone
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use chrono_tz::Tz;
+///
+/// use hoops_app::time::format_local;
+///
+/// let timestamp = Utc.with_ymd_and_hms(2024, 1, 26, 22, 30, 0).unwrap();
+/// assert_eq!(
+///     format_local(timestamp, "Australia/Sydney".parse().unwrap()),
+///     "Sat, 27 Jan 2024 09:30 AEDT",
+/// );
+/// assert_eq!(format_local(timestamp, Tz::UTC), "Fri, 26 Jan 2024 22:30 UTC");
+/// ```
+pub fn format_local(timestamp: DateTime<Utc>, time_zone: Tz) -> String {
+    timestamp
+        .with_timezone(&time_zone)
+        .format("%a, %-d %b %Y %H:%M %Z")
+        .to_string()
+}
+
+/// The moment a day starts in the given time zone
+///
+/// A few time zones skip midnight when their clocks go forward, so the day starts an hour later on
+/// those days instead.
+///
+/// ```
+/// use chrono::{NaiveDate, TimeZone, Utc};
+/// use chrono_tz::Tz;
+///
+/// use hoops_app::time::start_of_day;
+///
+/// let date = NaiveDate::from_ymd_opt(2024, 1, 27).unwrap();
+/// assert_eq!(
+///     start_of_day(date, "Australia/Sydney".parse().unwrap()),
+///     Utc.with_ymd_and_hms(2024, 1, 26, 13, 0, 0).unwrap(),
+/// );
+/// assert_eq!(start_of_day(date, Tz::UTC), Utc.with_ymd_and_hms(2024, 1, 27, 0, 0, 0).unwrap());
+///
+/// // São Paulo's clocks went forward from midnight to 1:00
+/// let date = NaiveDate::from_ymd_opt(2018, 11, 4).unwrap();
+/// assert_eq!(
+///     start_of_day(date, "America/Sao_Paulo".parse().unwrap()),
+///     Utc.with_ymd_and_hms(2018, 11, 4, 3, 0, 0).unwrap(),
+/// );
+/// ```
+pub fn start_of_day(date: NaiveDate, time_zone: Tz) -> DateTime<Utc> {
+    let midnight = date.and_time(NaiveTime::MIN);
+
+    [midnight, midnight + Duration::hours(1)]
+        .iter()
+        .find_map(|start| time_zone.from_local_datetime(start).earliest())
+        .map(|start| start.with_timezone(&Utc))
+        .unwrap_or_else(|| midnight.and_utc())
+}
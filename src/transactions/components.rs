@@ -1,46 +1,122 @@
-use chrono::{DateTime, NaiveDateTime, Utc};
-use leptos::*;
+use chrono::{DateTime, NaiveDate, Utc};
+use leptos::{leptos_server::Submission, *};
 use leptos_router::*;
 use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
+use crate::accounts::{account_read_one, Select as AccountSelect};
 use crate::components::{
     datepicker::DateTimePicker,
-    input::{Input, InputAmount},
+    errors::{
+        action_error, action_server_error, action_validation_errors, error_message, field_errors,
+        multi_action_validation_errors, submission_validation_errors,
+    },
+    input::{Input, InputAmount, InputType},
+    load_more::LoadMore,
 };
-use crate::transactions::model::Transaction;
+use crate::errors::ValidationErrors;
+use crate::events::{use_data_events, Change, DataEvent, Entity};
+use crate::hoops::{hoops_read_many, model::HoopSummary, Select as HoopSelect};
+use crate::safe_to_spend::refresh_after;
+use crate::time::{format_local, parse_local};
+use crate::transactions::duplicates::DUPLICATE_FIELD;
+use crate::transactions::model::{
+    Highlighted, SortColumn, SpendError, Transaction, TransactionMatch, TransactionPage,
+};
+use crate::transactions::query::TransactionQuery;
+use crate::users::use_time_zone;
 
 #[cfg(feature = "ssr")]
-use crate::transactions::model::{db_read_many, pool};
+use crate::{
+    events::publish,
+    state::pool,
+    users::{current_user, scope},
+};
 
-const DATETIME_STR: &'static str = "%Y-%m-%dT%H:%M:%S";
+/// Add a raw query string to a path, if there is one
+///
+/// Used to carry the list of transactions' filters through the detail & edit pages, so the user
+/// goes back to the same list they left.
+fn with_search(path: &str, search: &str) -> String {
+    match search {
+        "" => path.to_string(),
+        _ => format!("{path}?{search}"),
+    }
+}
 
 /// UI for adding a transaction to the record
 ///
 /// TODO:
 ///
-/// - [ ] error handling
+/// - [x] error handling
 /// - [x] optimistic updates to a co-located list
-/// - [ ] add date/time picker for timestamp field
+/// - [x] add date/time picker for timestamp field
+///
+/// When given a `value`, the form is filled in with it, so a failed submission can be edited.
+/// The timestamp is entered in the user's time zone, & defaults to now.
+/// Fields are marked with the given validation `errors`, or otherwise with those from the action's
+/// last submission. If those say the transaction looks like a duplicate, the user can choose to
+/// save it anyway. `on_submit` is called once the form's transaction has been submitted.
 #[component]
-pub fn New(action: MultiAction<TransactionNew, Result<(), ServerFnError>>) -> impl IntoView {
-    let timestamp_value = Utc::now().naive_utc();
+pub fn New(
+    action: MultiAction<TransactionNew, Result<(), ServerFnError>>,
+    #[prop(optional)] value: Option<TransactionNew>,
+    #[prop(optional)] errors: Option<Signal<ValidationErrors>>,
+    #[prop(optional, into)] on_submit: Option<Callback<()>>,
+) -> impl IntoView {
+    let errors = errors.unwrap_or_else(|| multi_action_validation_errors(action));
+    let time_zone = use_time_zone();
+    let value = value.map(|value| value.form);
+    let entered_timestamp = value.as_ref().map(|value| value.timestamp.clone());
+    let timestamp_value = Signal::derive(move || {
+        entered_timestamp
+            .as_deref()
+            .and_then(|timestamp| parse_local(timestamp, time_zone.get()))
+            .unwrap_or_else(Utc::now)
+    });
+    let account_id = value.as_ref().map(|value| value.account_id);
+    let spent_from = value
+        .as_ref()
+        .and_then(|value| Uuid::parse_str(&value.spent_from).ok());
+    let pending = value
+        .as_ref()
+        .and_then(|value| value.pending)
+        .unwrap_or_default();
+    let (payee, description, amount) = value
+        .map(|value| (value.payee, value.description, value.amount.to_string()))
+        .unwrap_or_default();
+
+    // submitted the way `MultiActionForm` would, but calling `on_submit` after
+    let submit = move |ev: ev::SubmitEvent| {
+        let Ok(input) = TransactionNew::from_event(&ev) else {
+            return;
+        };
+        ev.prevent_default();
+        action.dispatch(input);
+        if let Some(on_submit) = on_submit {
+            on_submit(());
+        }
+    };
 
     view! {
-        <MultiActionForm action>
-            <Input name="payee".to_string() label="Payee:".to_string() attr:required=true />
-            <Input name="description".to_string() label="Description:".to_string() />
-            <InputAmount name="amount".to_string() label="Amount:".to_string() attr:required=true />
-            // FIXME: how to pass timestamp as UTC value?
-            // FIXME: try using ISO8601 formatting w/ `<input type="date">...</input>`--
-            //        see
-            //        https://developer.mozilla.org/en-US/docs/Web/HTML/Element/input/datetime-local
-            //        for more information.
-            //        This will force local time in the browser, but that can be pretty easily made
-            //        into a chrono::NaiveDateTime and then UTC via that struct's `.and_utc()`
-            //        method
-            <DateTimePicker name="timestamp".to_string() label="Timestamp:".to_string() value=timestamp_value attr:required=true />
+        <form method="POST" action=action.url() on:submit=submit>
+            <Input name="form[payee]".to_string() label="Payee:".to_string() value=payee errors attr:required=true />
+            <Input name="form[description]".to_string() label="Description:".to_string() value=description errors />
+            <InputAmount name="form[amount]".to_string() label="Amount:".to_string() value=amount errors attr:required=true />
+            <AccountSelect name="form[account_id]".to_string() label="Account:".to_string() value=account_id />
+            <HoopSelect name="form[spent_from]".to_string() label="Spent from:".to_string() value=spent_from none_label="None".to_string() />
+            {field_errors(errors, SpendError::FIELD.to_string())}
+            <label for="form[pending]">Pending:</label>
+            <input id="form[pending]" name="form[pending]" type="checkbox" value="true" checked=pending />
+            <DateTimePicker name="form[timestamp]".to_string() label="Timestamp:".to_string() value=timestamp_value errors attr:required=true />
+            {field_errors(errors, DUPLICATE_FIELD.to_string())}
+            {move || errors.with(|errors| !errors.get(DUPLICATE_FIELD).is_empty()).then(|| view! {
+                <label for="allow_duplicate">"Save it anyway:"</label>
+                <input id="allow_duplicate" name="allow_duplicate" type="checkbox" value="true" />
+            })}
             <button type="submit">Create</button>
-        </MultiActionForm>
+        </form>
     }
 }
 
@@ -54,123 +130,649 @@ pub fn New(action: MultiAction<TransactionNew, Result<(), ServerFnError>>) -> im
 /// </ul>
 /// ```
 ///
-/// Currently just displays all transactions in the database. Needs the following features
-/// implemented later:
+/// Displays the transactions it's given, which are already filtered, sorted, & paged.
+///
+/// Search matches are shown with the parts of their payee & description that matched highlighted.
 #[component]
-fn ListItems(transactions: Vec<Transaction>) -> impl IntoView {
+fn ListItems(transactions: Vec<TransactionMatch>) -> impl IntoView {
     transactions
         .into_iter()
-        .map(move |transaction| {
-            let Transaction {
-                payee,
-                amount,
-                description,
-                timestamp,
-                ..
-            } = transaction;
-            view! { <Item payee amount description timestamp /> }
-        })
+        .map(
+            move |TransactionMatch {
+                      transaction,
+                      payee,
+                      description,
+                  }| {
+                let Transaction {
+                    id,
+                    amount,
+                    timestamp,
+                    ..
+                } = transaction;
+                view! { <Item id payee amount description timestamp /> }
+            },
+        )
         .collect_view()
 }
 
+/// Renders text with the parts that matched a search wrapped in `<mark>`
+impl IntoView for Highlighted {
+    fn into_view(self) -> View {
+        self.0
+            .into_iter()
+            .map(|part| match part.matched {
+                true => view! { <mark>{part.text}</mark> }.into_view(),
+                false => part.text.into_view(),
+            })
+            .collect_view()
+    }
+}
+
 /// Component for rendering a single item in a transaction list
+///
+/// Links to the transaction's details, unless it has no ID because it hasn't been saved yet. Any
+/// children are rendered after the transaction's details, e.g. to show a submission's status. The
+/// timestamp is shown in the user's time zone.
 #[component]
 fn Item(
-    payee: String,
+    #[prop(optional)] id: Option<Uuid>,
+    #[prop(into)] payee: Highlighted,
     amount: Decimal,
-    description: Option<String>,
+    description: Option<Highlighted>,
     timestamp: DateTime<Utc>,
+    #[prop(optional)] children: Option<Children>,
 ) -> impl IntoView {
+    let search = use_location().search;
+    let time_zone = use_time_zone();
+    let payee = match id {
+        Some(id) => {
+            let href = move || with_search(&format!("/transactions/{id}"), &search.get());
+            view! { <A href>{payee}</A> }.into_view()
+        }
+        None => payee.into_view(),
+    };
+    let description = description.map(IntoView::into_view);
+
     view! {
         <li>
             <ul>
                 <li>{payee}</li>
                 <li>{amount.to_string()}</li>
                 <li>{description}</li>
-                <li>{timestamp.to_rfc2822()}</li>
+                <li>{move || format_local(timestamp, time_zone.get())}</li>
+                {children.map(|children| view! { <li>{children()}</li> })}
             </ul>
         </li>
     }
 }
 
+/// A transaction submitted through the new transaction form that hasn't shown up in the list yet
+///
+/// Pending submissions are shown as they'll appear once saved. A submission that fails shows its
+/// error along with options to retry it, to edit it in a form filled in with what the user typed,
+/// or to discard it, & to save it anyway if it failed for looking like a duplicate. Submissions
+/// that are saved render nothing, since they're in the list instead.
+#[component]
+fn Draft(
+    submission: Submission<TransactionNew, Result<(), ServerFnError>>,
+    action: MultiAction<TransactionNew, Result<(), ServerFnError>>,
+) -> impl IntoView {
+    // a submission's input is cleared once it resolves, so keep what the user typed to retry or
+    // edit it with
+    let input = store_value(submission.input.get_untracked());
+    let time_zone = use_time_zone();
+    let editing = create_rw_signal(false);
+    let discarded = create_rw_signal(false);
+
+    // retrying or editing sends the transaction as a new submission, which takes this one's place
+    let retry = move |_| {
+        if let Some(input) = input.get_value() {
+            action.dispatch(input);
+        }
+        discarded.set(true);
+    };
+    let save_anyway = move |_| {
+        if let Some(input) = input.get_value() {
+            action.dispatch(TransactionNew {
+                allow_duplicate: Some(true),
+                ..input
+            });
+        }
+        discarded.set(true);
+    };
+
+    move || {
+        let Some(input) = input.get_value().filter(|_| !discarded.get()) else {
+            return ().into_view();
+        };
+        let TransactionForm {
+            payee,
+            amount,
+            description,
+            timestamp,
+            ..
+        } = input.form.clone();
+        // convert empty strings to None, otherwise pass as Some(..)
+        let description = match description.as_str() {
+            "" => None,
+            _ => Some(Highlighted::from(description)),
+        };
+        // read the same way the server will read it when saving
+        let timestamp = parse_local(&timestamp, time_zone.get()).unwrap_or_else(Utc::now);
+
+        if submission.pending().get() {
+            return view! {
+                <Item payee amount description timestamp>
+                    <span class="pending">Saving...</span>
+                </Item>
+            }
+            .into_view();
+        }
+
+        match submission.value.get() {
+            Some(Err(err)) if editing.get() => view! {
+                <li class="error">
+                    <p class="error">{error_message(&err)}</p>
+                    // the form's own submission replaces this one
+                    <New
+                        action
+                        value=input
+                        errors=submission_validation_errors(submission)
+                        on_submit=move |_| discarded.set(true)
+                    />
+                    <button type="button" on:click=move |_| editing.set(false)>Cancel</button>
+                    <button type="button" on:click=move |_| discarded.set(true)>Discard</button>
+                </li>
+            }
+            .into_view(),
+            Some(Err(err)) => view! {
+                <Item payee amount description timestamp>
+                    <span class="error">Failed: {error_message(&err)}</span>
+                    {is_duplicate(&err).then(|| view! {
+                        <button type="button" on:click=save_anyway>Save anyway</button>
+                    })}
+                    <button type="button" on:click=retry>Retry</button>
+                    <button type="button" on:click=move |_| editing.set(true)>Edit</button>
+                    <button type="button" on:click=move |_| discarded.set(true)>Discard</button>
+                </Item>
+            }
+            .into_view(),
+            _ => ().into_view(),
+        }
+    }
+}
+
+/// Whether a submission failed for looking like a duplicate of a saved transaction
+fn is_duplicate(err: &ServerFnError) -> bool {
+    ValidationErrors::from_server_fn_error(err)
+        .is_some_and(|errors| !errors.get(DUPLICATE_FIELD).is_empty())
+}
+
+/// The fields of the new & edit transaction forms, as they're entered
+///
+/// The forms name each field as part of `form`, like `form[payee]`, while validation errors are
+/// reported under the field's own name.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TransactionForm {
+    pub payee: String,
+    pub description: String,
+    pub amount: Decimal,
+    /// Entered in the user's time zone
+    pub timestamp: String,
+    pub account_id: Uuid,
+    /// ID of the hoop spent from, or empty for none
+    pub spent_from: String,
+    /// Only sent when the pending checkbox is checked
+    pub pending: Option<bool>,
+}
+
+/// The values entered in the new or edit transaction forms, checked & converted for saving
+#[cfg(feature = "ssr")]
+struct FormValues {
+    payee: String,
+    description: Option<String>,
+    amount: Decimal,
+    timestamp: DateTime<Utc>,
+    account_id: Uuid,
+    spent_from: Option<Uuid>,
+    pending: bool,
+}
+
+/// Check the values entered in the new or edit transaction forms, collecting every problem found
+///
+/// The timestamp is entered in the user's time zone, & converted to UTC.
+#[cfg(feature = "ssr")]
+fn check_form(
+    form: TransactionForm,
+    time_zone: chrono_tz::Tz,
+) -> Result<FormValues, ValidationErrors> {
+    use crate::transactions::model::validate;
+
+    let TransactionForm {
+        payee,
+        description,
+        amount,
+        timestamp,
+        account_id,
+        spent_from,
+        pending,
+    } = form;
+    let mut errors = validate(&payee, amount);
+    let timestamp = parse_local(&timestamp, time_zone);
+    if timestamp.is_none() {
+        errors.add("timestamp", "Enter a valid date & time");
+    }
+    // convert empty strings to None, otherwise parse as a hoop's ID
+    let spent_from = match spent_from.as_str() {
+        "" => Ok(None),
+        _ => Uuid::parse_str(&spent_from).map(Some),
+    };
+    if spent_from.is_err() {
+        errors.add(SpendError::FIELD, "Choose a hoop to spend from");
+    }
+
+    // both are only missing when an error was added for them
+    let (Some(timestamp), Ok(spent_from)) = (timestamp, spent_from) else {
+        return Err(errors);
+    };
+    errors.check()?;
+
+    Ok(FormValues {
+        payee: payee.trim().to_string(),
+        // convert empty strings to None, otherwise pass as Some(..)
+        description: Some(description).filter(|d| !d.is_empty()),
+        amount,
+        timestamp,
+        account_id,
+        spent_from,
+        pending: pending.unwrap_or_default(),
+    })
+}
+
 /// add Transaction server endpoint
+///
+/// Fails with an error for the `duplicate` field if the transaction looks like one already saved,
+/// unless `allow_duplicate` is sent.
 #[server(prefix = "/api", endpoint = "transaction/new")]
 pub async fn transaction_new(
-    description: String,
-    payee: String,
-    amount: Decimal,
-    timestamp: String,
+    form: TransactionForm,
+    // only sent once the user has confirmed a likely duplicate
+    allow_duplicate: Option<bool>,
 ) -> Result<(), ServerFnError> {
-    use crate::models::Create;
+    use crate::transactions::duplicates::db_find_duplicates;
+    use crate::transactions::model::db_create_one_checked;
 
-    println!("timestamp is: {}", &timestamp);
-    // convert empty strings to None, otherwise pass as Some(..)
-    let description = match description.as_str() {
-        "" => None,
-        _ => Some(description),
-    };
-    // convert rfc_2822 datestring into DateTime
-    let timestamp = DateTime::<Utc>::from_naive_utc_and_offset(
-        NaiveDateTime::parse_from_str(&timestamp, DATETIME_STR)?,
-        Utc,
-    );
+    let time_zone = current_user()?.time_zone;
+    let FormValues {
+        payee,
+        description,
+        amount,
+        timestamp,
+        account_id,
+        spent_from,
+        pending,
+    } = check_form(form, time_zone)?;
+    let scope = scope()?;
     // if getting a pool fails, immediately return the error instead of proceeding
     let pool = &pool()?;
 
-    Transaction::create_one(
-        pool,
-        Transaction::new(amount, payee, timestamp, description),
-    )
-    .await
-    .map_err(|err| {
-        logging::log!("There was an error saving the transaction: {}", err);
-        ServerFnError::ServerError(err.to_string())
-    })
+    // the hoop's balance is checked in the same database transaction the new one is saved in, so
+    // it's rolled back if the hoop doesn't have enough funds
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    let mut transaction = Transaction::new(
+        amount,
+        payee,
+        timestamp,
+        description,
+        account_id,
+        spent_from,
+    );
+    transaction.pending = pending;
+    let id = transaction.id;
+    if !allow_duplicate.unwrap_or_default() {
+        let duplicates = db_find_duplicates(&mut *tx, scope, &transaction)
+            .await
+            .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+        if let Some(duplicate) = duplicates.first() {
+            let message = format!(
+                "This looks like the same transaction as {} for {} on {}",
+                duplicate.payee,
+                duplicate.amount,
+                format_local(duplicate.timestamp, time_zone),
+            );
+            return Err(ValidationErrors::field(DUPLICATE_FIELD, message).into());
+        }
+    }
+    db_create_one_checked(&mut tx, scope, transaction)
+        .await
+        .map_err(|err| match err.downcast::<SpendError>() {
+            Ok(err) => ServerFnError::from(ValidationErrors::field(SpendError::FIELD, err)),
+            Err(err) => {
+                logging::log!("There was an error saving the transaction: {}", err);
+                ServerFnError::ServerError(err.to_string())
+            }
+        })?;
+    tx.commit()
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+
+    publish(
+        scope,
+        DataEvent::new(Entity::Transaction, Change::Created, id),
+    );
+    Ok(())
 }
 
-/// Server endpoint for reading all transaction
+/// Server endpoint for reading a page of the transactions matching the list's filters, in the
+/// list's order
+///
+/// The filter's dates are read in the user's time zone. The page is read from the query's cursor,
+/// or from the top of the list if it has none or it can't be read, & holds `limit` transactions,
+/// or `PAGE_SIZE` by default, up to `MAX_PAGE_SIZE`.
 #[server(prefix = "/api", endpoint = "transactions/read/all")]
 pub async fn transactions_read_many(
+    query: TransactionQuery,
     limit: Option<u32>,
-    offset: Option<u32>,
-) -> Result<Vec<Transaction>, ServerFnError> {
+) -> Result<TransactionPage, ServerFnError> {
+    use crate::transactions::model::{db_read_page, page_size, Cursor};
+
+    let filter = query.filter(current_user()?.time_zone);
+    let cursor = query.cursor.as_deref().and_then(Cursor::from_token);
+    let scope = scope()?;
+    let pool = &pool()?;
+
+    db_read_page(pool, scope, &filter, query.sort, cursor, page_size(limit))
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// Server endpoint for searching the payees & descriptions of the transactions matching the list's
+/// filters, returning the best matches first with the parts that matched highlighted
+#[server(prefix = "/api", endpoint = "transactions/search")]
+pub async fn transactions_search(
+    search: String,
+    query: TransactionQuery,
+) -> Result<Vec<TransactionMatch>, ServerFnError> {
+    use crate::transactions::model::db_search;
+
+    let filter = query.filter(current_user()?.time_zone);
+    let scope = scope()?;
+    let pool = &pool()?;
+
+    db_search(pool, scope, &search, &filter)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// Server endpoint for reading a single transaction
+#[server(prefix = "/api", endpoint = "transaction/read/one")]
+pub async fn transaction_read_one(id: Uuid) -> Result<Transaction, ServerFnError> {
+    use crate::models::Read;
+
+    let scope = scope()?;
+    let pool = &pool()?;
+
+    Transaction::read_one_by_id(pool, scope, id)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?
+        .ok_or_else(|| ServerFnError::ServerError(format!("No transaction found with id {id}")))
+}
+
+/// Server endpoint for changing every field of a saved transaction
+#[server(prefix = "/api", endpoint = "transaction/update")]
+pub async fn transaction_update(id: Uuid, form: TransactionForm) -> Result<(), ServerFnError> {
+    use crate::transactions::model::{db_patch_one_checked, TransactionPatch};
+
+    let time_zone = current_user()?.time_zone;
+    let FormValues {
+        payee,
+        description,
+        amount,
+        timestamp,
+        account_id,
+        spent_from,
+        pending,
+    } = check_form(form, time_zone)?;
+    let scope = scope()?;
+    let pool = &pool()?;
+
+    let patch = TransactionPatch {
+        amount: Some(amount),
+        description: Some(description),
+        payee: Some(payee),
+        timestamp: Some(timestamp),
+        account_id: Some(account_id),
+        spent_from: Some(spent_from),
+        pending: Some(pending),
+    };
+
+    // the hoop's balance is checked in the same database transaction the change is saved in, so
+    // it's rolled back if the hoop doesn't have enough funds
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    db_patch_one_checked(&mut tx, scope, id, patch)
+        .await
+        .map_err(|err| match err.downcast::<SpendError>() {
+            Ok(err) => ServerFnError::from(ValidationErrors::field(SpendError::FIELD, err)),
+            Err(err) => ServerFnError::ServerError(err.to_string()),
+        })?
+        .ok_or_else(|| ServerFnError::ServerError(format!("No transaction found with id {id}")))?;
+    tx.commit()
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+
+    publish(
+        scope,
+        DataEvent::new(Entity::Transaction, Change::Updated, id),
+    );
+    Ok(())
+}
+
+/// Server endpoint for deleting a transaction
+#[server(prefix = "/api", endpoint = "transaction/delete")]
+pub async fn transaction_delete(id: Uuid) -> Result<(), ServerFnError> {
+    use crate::models::Delete;
+
+    let scope = scope()?;
+    let pool = &pool()?;
+
+    let deleted = Transaction::delete_one_by_id(pool, scope, id)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    if !deleted {
+        return Err(ServerFnError::ServerError(format!(
+            "No transaction found with id {id}"
+        )));
+    }
+
+    publish(
+        scope,
+        DataEvent::new(Entity::Transaction, Change::Deleted, id),
+    );
+    Ok(())
+}
+
+/// Server endpoint for reading the saved transactions a transaction looks like a duplicate of
+#[server(prefix = "/api", endpoint = "transaction/duplicates")]
+pub async fn transaction_duplicates(id: Uuid) -> Result<Vec<Transaction>, ServerFnError> {
+    use crate::models::Read;
+    use crate::transactions::duplicates::db_find_duplicates;
+
+    let scope = scope()?;
     let pool = &pool()?;
 
-    db_read_many(pool, limit, offset)
+    let transaction = Transaction::read_one_by_id(pool, scope, id)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?
+        .ok_or_else(|| ServerFnError::ServerError(format!("No transaction found with id {id}")))?;
+
+    db_find_duplicates(pool, scope, &transaction)
         .await
         .map_err(|e| ServerFnError::ServerError(e.to_string()))
 }
 
+/// Server endpoint for merging a duplicate into the transaction it duplicates, deleting the
+/// duplicate
+#[server(prefix = "/api", endpoint = "transaction/merge")]
+pub async fn transaction_merge(id: Uuid, duplicate_id: Uuid) -> Result<(), ServerFnError> {
+    use crate::transactions::duplicates::db_merge;
+
+    let scope = scope()?;
+    let pool = &pool()?;
+
+    // the hoop's balance is checked in the same database transaction as the merge, in case the
+    // duplicate's hoop is kept
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    db_merge(&mut tx, scope, id, duplicate_id)
+        .await
+        .map_err(|err| match err.downcast::<SpendError>() {
+            Ok(err) => ServerFnError::from(ValidationErrors::field(SpendError::FIELD, err)),
+            Err(err) => ServerFnError::ServerError(err.to_string()),
+        })?
+        .ok_or_else(|| ServerFnError::ServerError(format!("No transaction found with id {id}")))?;
+    tx.commit()
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+
+    publish(
+        scope,
+        DataEvent::new(Entity::Transaction, Change::Deleted, duplicate_id),
+    );
+    publish(
+        scope,
+        DataEvent::new(Entity::Transaction, Change::Updated, id),
+    );
+    Ok(())
+}
+
 /// A root component for rendering a list of transactions & a form for adding new ones to the list.
 /// Optimistically updates w/ pending transactions as new ones are created and before a response
 /// from the server is received.
 ///
 /// TODO:
 ///
-/// - [...] paginate or infinite scroll
+/// - [x] paginate or infinite scroll
 /// - [x] auto-update if new transactions are added in the currently visible range of transactions
-/// - [ ] auto-update a transaction's displayed info if it is updated in the db & it is currently
+/// - [x] auto-update a transaction's displayed info if it is updated in the db & it is currently
 ///       visible
-/// - [ ] sort by columns
-/// - [ ] filter by columns
+/// - [x] sort by columns
+/// - [x] filter by columns
+///
+/// The list's filters, sorting, & page are kept in the URL's query params, so leaving the list &
+/// coming back shows the same transactions. It's shown a page at a time with links to the pages on
+/// either side, or, when scrolling infinitely, loads the next page onto the end of the list as the
+/// user scrolls to it. Search results are only ever one page of the best matches.
+///
+/// Changes made in the user's other tabs & devices are streamed to the list, which updates or
+/// removes the transactions it's showing as they change, & reads the first page again when a
+/// transaction's added.
 #[component]
-pub fn All(
-    #[prop(optional)] num_on_page: Option<u32>,
-    #[prop(optional)] start_after: Option<u32>,
-) -> impl IntoView {
+pub fn All(#[prop(optional)] num_on_page: Option<u32>) -> impl IntoView {
     // action for adding new transactions & signal tracking pending submission on that action
     let new = create_server_multi_action::<TransactionNew>();
     let submissions = new.submissions();
+    refresh_after(new.version());
+    let query = use_query::<TransactionQuery>();
+    let query = Signal::derive(move || query.get().unwrap_or_default());
 
-    // resource for loading all transaction saved in the db
-    // updates every time the new action is executed
+    // resource for loading the transactions matching the list's filters, or its search
+    // updates every time the filters change or the new action is executed
     let transactions = create_resource(
-        move || new.version().get(),
-        move |_| transactions_read_many(num_on_page, start_after),
+        move || (query.get(), new.version().get()),
+        move |(query, _)| async move {
+            match query.search.clone() {
+                Some(search) => transactions_search(search, query).await.map(ListPage::from),
+                None => transactions_read_many(query, num_on_page)
+                    .await
+                    .map(ListPage::from),
+            }
+        },
     );
+    let filtered = move || query.with(|query| *query != query.unfiltered());
+    let searching = move || query.with(|query| query.search.is_some());
+
+    // pages loaded onto the end of the list while scrolling infinitely, which start over whenever
+    // the first page is read again
+    let more_pages = create_rw_signal(Vec::<ListPage>::new());
+    let load_more = create_action(move |cursor: &String| {
+        let query = query.get_untracked().at_page(Some(cursor.clone()));
+        async move {
+            let page = transactions_read_many(query.clone(), num_on_page).await;
+            (query, page.map(ListPage::from))
+        }
+    });
+    create_effect(move |_| {
+        query.track();
+        new.version().track();
+        more_pages.set(Vec::new());
+    });
+    create_effect(move |_| {
+        if let Some((loaded_for, Ok(page))) = load_more.value().get() {
+            // a page that was still loading when the list changed belongs to the old list
+            if loaded_for.at_page(None) == query.get_untracked().at_page(None) {
+                more_pages.update(|pages| pages.push(page));
+            }
+        }
+    });
+    let more_loading = load_more.pending();
+
+    // keep the list in step with changes made in the user's other tabs & devices, reading a shown
+    // transaction again when it's changed, & the first page when one's added
+    let events = use_data_events();
+    let reread = create_action(|id: &Uuid| transaction_read_one(*id));
+    let shown = move |id: Uuid| {
+        let on_first =
+            transactions.with(|page| matches!(page, Some(Ok(page)) if page.contains(id)));
+        on_first || more_pages.with(|pages| pages.iter().any(|page| page.contains(id)))
+    };
+    let patch = move |change: &dyn Fn(&mut ListPage)| {
+        transactions.update(|page| {
+            if let Some(Ok(page)) = page {
+                change(page);
+            }
+        });
+        more_pages.update(|pages| pages.iter_mut().for_each(change));
+    };
+    create_effect(move |_| {
+        let Some(DataEvent {
+            entity: Entity::Transaction,
+            change,
+            id,
+        }) = events.get()
+        else {
+            return;
+        };
+
+        match change {
+            Change::Created if query.with_untracked(|query| query.cursor.is_none()) => {
+                more_pages.set(Vec::new());
+                transactions.refetch();
+            }
+            Change::Updated if shown(id) => reread.dispatch(id),
+            Change::Deleted if shown(id) => patch(&|page| page.remove(id)),
+            _ => {}
+        }
+    });
+    create_effect(move |_| {
+        if let Some(Ok(transaction)) = reread.value().get() {
+            patch(&|page| page.replace(&transaction));
+        }
+    });
 
     view! {
         <New action=new />
+        <Filters query />
+        <Show when=move || !searching() fallback=|| view! { <p>Best matches first</p> }>
+            <SortLinks query />
+        </Show>
         <Suspense fallback=move || view! {<p>Loading...</p>}.into_view()>
             {move || {
                 let existing_transactions = move || {
@@ -178,8 +780,12 @@ pub fn All(
                         Err(err) => {
                             view! { <pre>Error fetching transactions: {err.to_string()}</pre>}.into_view()
                         },
-                        Ok(transactions) => {
-                            if transactions.is_empty() {
+                        Ok(ListPage { transactions, .. }) => {
+                            if transactions.is_empty() && searching() {
+                                view! {<p>No transactions match this search</p>}.into_view()
+                            } else if transactions.is_empty() && filtered() {
+                                view! {<p>No transactions match these filters</p>}.into_view()
+                            } else if transactions.is_empty() {
                                 view! {<p>No transactions yet...</p>}.into_view()
                             } else {
                                 view! { <ListItems transactions /> }.into_view()
@@ -189,38 +795,443 @@ pub fn All(
                 };
 
                 // optimistically render transactions that have been submitted, but not yet
-                // received back from the server
-                let pending_transactions = move || {
-                    submissions
-                        .get()
-                        .into_iter()
-                        .filter(|s| s.pending().get())
-                        .map(|s| s.input.get().map(|submission| {
-                            let TransactionNew {payee, amount, description, timestamp} = submission;
-                            // convert empty strings to None, otherwise pass as Some(..)
-                            let desc_option = match description.as_str() {
-                                "" => None,
-                                _ => Some(description),
-                            };
-                            // FIXME: there's a bug here where the time stamp somehow isn't being
-                            // parsed correctly for the first submission on a fresh page load.
-                            // interestingly enough, the  log below appears in the browser console
-                            // for the first press that fails, but in the server console for
-                            // subsequent (successful) attempts.
-                            logging::log!("timestamp string value is: {:?}", &timestamp);
-                            let timestamp = DateTime::<Utc>::from(DateTime::parse_from_str(&timestamp, DATETIME_STR).unwrap());
-
-                            view! { <Item payee amount description=desc_option timestamp /> }
-                        })).collect_view()
+                // received back from the server, along with any that failed to save
+                let pending_transactions = view! {
+                    <For
+                        each=move || submissions.get().into_iter().enumerate()
+                        key=|(i, _)| *i
+                        children=move |(_, submission)| view! { <Draft submission action=new /> }
+                    />
+                };
+
+                let loaded_transactions = view! {
+                    <For
+                        each=move || more_pages.get().into_iter().enumerate()
+                        key=|(i, _)| *i
+                        children=move |(_, page)| view! { <ListItems transactions=page.transactions /> }
+                    />
                 };
 
+                // the page after the last one shown, whether it's the first or one loaded since
+                let next = move || {
+                    let first = transactions.with(|page| {
+                        page.as_ref().and_then(|page| page.as_ref().ok()?.next.clone())
+                    });
+                    more_pages.with(|pages| pages.last().map(|page| page.next.clone())).unwrap_or(first)
+                };
+                let previous = move || {
+                    transactions.with(|page| {
+                        page.as_ref().and_then(|page| page.as_ref().ok()?.previous.clone())
+                    })
+                };
+                let infinite_scroll = move || query.with(|query| query.infinite_scroll);
+                let on_load = Callback::new(move |_| {
+                    if let Some(cursor) = next() {
+                        load_more.dispatch(cursor);
+                    }
+                });
+
                 view! {
                     <ul>
                         {pending_transactions}
                         {existing_transactions}
+                        {loaded_transactions}
                     </ul>
+                    <Show
+                        when=infinite_scroll
+                        fallback=move || view! { <PageLinks query next=Signal::derive(next) previous=Signal::derive(previous) /> }
+                    >
+                        <Show when=move || next().is_some()>
+                            <LoadMore on_load loading=more_loading />
+                        </Show>
+                    </Show>
                 }
             }}
         </Suspense>
+        <ScrollLink query />
+    }
+}
+
+/// A page of the transactions in the list, as they're rendered
+///
+/// Search results are shown as a single page, while a page of the list links to the ones on
+/// either side of it.
+#[derive(Clone, Default, Deserialize, Serialize)]
+struct ListPage {
+    transactions: Vec<TransactionMatch>,
+    next: Option<String>,
+    previous: Option<String>,
+}
+
+impl ListPage {
+    fn contains(&self, id: Uuid) -> bool {
+        self.transactions.iter().any(|t| t.transaction.id == id)
+    }
+
+    /// Show the transaction as it's now saved, in the same place in the list
+    ///
+    /// Any highlighted search matches in it are lost, since they're not known for the new text.
+    fn replace(&mut self, transaction: &Transaction) {
+        for shown in &mut self.transactions {
+            if shown.transaction.id == transaction.id {
+                *shown = transaction.clone().into();
+            }
+        }
+    }
+
+    fn remove(&mut self, id: Uuid) {
+        self.transactions.retain(|t| t.transaction.id != id);
+    }
+}
+
+impl From<Vec<TransactionMatch>> for ListPage {
+    fn from(transactions: Vec<TransactionMatch>) -> Self {
+        ListPage {
+            transactions,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<TransactionPage> for ListPage {
+    fn from(page: TransactionPage) -> Self {
+        ListPage {
+            transactions: page.transactions.into_iter().map(Into::into).collect(),
+            next: page.next,
+            previous: page.previous,
+        }
+    }
+}
+
+/// Links to the pages before & after the one shown, for whichever of them there are
+#[component]
+fn PageLinks(
+    query: Signal<TransactionQuery>,
+    #[prop(into)] next: Signal<Option<String>>,
+    #[prop(into)] previous: Signal<Option<String>>,
+) -> impl IntoView {
+    let pathname = use_location().pathname;
+    let href = move |cursor: String| {
+        with_search(
+            &pathname.get(),
+            &query.get().at_page(Some(cursor)).to_search(),
+        )
+    };
+
+    view! {
+        <nav class="pages">
+            {move || previous.get().map(|cursor| view! { <A href=href(cursor)>"← Previous"</A> })}
+            {move || next.get().map(|cursor| view! { <A href=href(cursor)>"Next →"</A> })}
+        </nav>
+    }
+}
+
+/// Link for switching the list between showing a page at a time & loading more as the user
+/// scrolls, starting again from its first page
+#[component]
+fn ScrollLink(query: Signal<TransactionQuery>) -> impl IntoView {
+    let pathname = use_location().pathname;
+    let infinite_scroll = move || query.with(|query| query.infinite_scroll);
+    let href = move || {
+        with_search(
+            &pathname.get(),
+            &query.get().scrolling(!infinite_scroll()).to_search(),
+        )
+    };
+
+    view! {
+        <A href>
+            {move || match infinite_scroll() {
+                true => "Show a page at a time",
+                false => "Load more while scrolling",
+            }}
+        </A>
+    }
+}
+
+/// Form for searching & filtering the list of transactions, filled in with its current search &
+/// filters
+///
+/// Submitting it changes the URL's query params, which the list is read from. The list's sorting
+/// & scrolling are kept when filtering, while it starts again from the first page.
+#[component]
+fn Filters(query: Signal<TransactionQuery>) -> impl IntoView {
+    let pathname = use_location().pathname;
+    let clear = move || with_search(&pathname.get(), &query.get().unfiltered().to_search());
+
+    move || {
+        let query = query.get();
+        let TransactionQuery {
+            search,
+            from,
+            to,
+            min_amount,
+            max_amount,
+            payee,
+            account_id,
+            spent_from,
+            has_description,
+            ..
+        } = query.clone();
+        // convert None to an empty field, otherwise show the value
+        let date = |date: Option<NaiveDate>| date.map(|date| date.to_string()).unwrap_or_default();
+        let amount = |amount: Option<Decimal>| amount.map(|a| a.to_string()).unwrap_or_default();
+
+        view! {
+            <Form method="GET" action=move || pathname.get() class="filters">
+                <Input name="search".to_string() label="Search:".to_string() input_type=InputType::Search value=search.unwrap_or_default() />
+                <Input name="payee_contains".to_string() label="Payee contains:".to_string() value=payee.unwrap_or_default() />
+                <Input name="from".to_string() label="From:".to_string() input_type=InputType::Date value=date(from) />
+                <Input name="to".to_string() label="To:".to_string() input_type=InputType::Date value=date(to) />
+                <InputAmount name="min_amount".to_string() label="Amount from:".to_string() value=amount(min_amount) />
+                <InputAmount name="max_amount".to_string() label="Amount to:".to_string() value=amount(max_amount) />
+                <AccountSelect name="account".to_string() label="Account:".to_string() value=account_id none_label="Any".to_string() />
+                <HoopSelect name="hoop".to_string() label="Spent from:".to_string() value=spent_from none_label="Any".to_string() />
+                <label for="has_description">Description:</label>
+                <select id="has_description" name="has_description">
+                    <option value="" selected=has_description.is_none()>Any</option>
+                    <option value="true" selected=has_description == Some(true)>With a description</option>
+                    <option value="false" selected=has_description == Some(false)>Without a description</option>
+                </select>
+                {query
+                    .view_params()
+                    .into_iter()
+                    .map(|(name, value)| view! { <input type="hidden" name=name value=value /> })
+                    .collect_view()}
+                <button type="submit">Filter</button>
+            </Form>
+            <A href=clear>Clear filters</A>
+        }
+    }
+}
+
+/// Links for sorting the list of transactions by each column, marking the one it's sorted by with
+/// the order it's in
+#[component]
+fn SortLinks(query: Signal<TransactionQuery>) -> impl IntoView {
+    let pathname = use_location().pathname;
+
+    let links = SortColumn::ALL
+        .into_iter()
+        .map(|column| {
+            let href =
+                move || with_search(&pathname.get(), &query.get().sorted_by(column).to_search());
+            let name = match column {
+                SortColumn::Timestamp => "Date",
+                SortColumn::Amount => "Amount",
+                SortColumn::Payee => "Payee",
+            };
+            let label = move || match query.get().sort {
+                sort if sort.column != column => name.to_string(),
+                sort if sort.descending => format!("{name} ↓"),
+                _ => format!("{name} ↑"),
+            };
+
+            view! { <A href>{label}</A> }
+        })
+        .collect_view();
+
+    view! {
+        <nav class="sort">
+            "Sort by: "
+            {links}
+        </nav>
+    }
+}
+
+/// Read the transaction with the ID from the current route's `id` param
+fn use_transaction() -> Resource<String, Result<Transaction, ServerFnError>> {
+    let params = use_params_map();
+    let id = move || params.with(|p| p.get("id").cloned().unwrap_or_default());
+
+    create_resource(id, |id| async move {
+        let id = Uuid::parse_str(&id).map_err(|e| ServerFnError::Args(e.to_string()))?;
+        transaction_read_one(id).await
+    })
+}
+
+/// Go to the page at `path`, keeping the filters the list of transactions had when the user left
+/// it, once the given action succeeds
+fn navigate_after<I: 'static>(
+    action: Action<I, Result<(), ServerFnError>>,
+    path: impl Fn() -> String + 'static,
+) {
+    let search = use_location().search;
+    let navigate = use_navigate();
+
+    create_effect(move |_| {
+        if let Some(Ok(())) = action.value().get() {
+            navigate(
+                &with_search(&path(), &search.get_untracked()),
+                Default::default(),
+            );
+        }
+    });
+}
+
+/// Shows a single transaction's details, with options to edit or delete it, & to merge in any
+/// transactions it looks like a duplicate of
+///
+/// Transactions don't have notes or attachments yet, so only their description is shown. Both
+/// need saving on the model first, & attachments need somewhere to store uploaded files.
+#[component]
+pub fn Detail() -> impl IntoView {
+    let search = use_location().search;
+    let delete = create_server_action::<TransactionDelete>();
+    refresh_after(delete.version());
+    navigate_after(delete, || "/transactions".to_string());
+
+    let transaction = use_transaction();
+    let merge = create_server_action::<TransactionMerge>();
+    refresh_after(merge.version());
+    // merging can fill in details the transaction was missing
+    create_effect(move |_| {
+        if let Some(Ok(())) = merge.value().get() {
+            transaction.refetch();
+        }
+    });
+    let hoops = create_resource(|| false, hoops_read_many);
+    let time_zone = use_time_zone();
+
+    view! {
+        <Suspense fallback=move || view! {<p>Loading...</p>}.into_view()>
+            {move || transaction.get().map(|transaction| match transaction {
+                Err(err) => {
+                    view! { <pre>Error fetching transaction: {err.to_string()}</pre>}.into_view()
+                },
+                Ok(Transaction { id, amount, description, payee, timestamp, account_id, spent_from, pending, .. }) => {
+                    let account = create_resource(move || account_id, account_read_one);
+                    // archived hoops aren't listed, but can still have been spent from
+                    let hoop_name = move |hoop_id: Uuid| {
+                        hoops
+                            .get()
+                            .and_then(Result::ok)
+                            .and_then(|hoops| hoops.into_iter().find(|HoopSummary { hoop, .. }| hoop.id == hoop_id))
+                            .map(|HoopSummary { hoop, .. }| hoop.name)
+                            .unwrap_or_else(|| String::from("An archived hoop"))
+                    };
+
+                    view! {
+                        <h1>{payee}</h1>
+                        <ul>
+                            <li>Amount: {amount.to_string()}</li>
+                            <li>Description: {description}</li>
+                            <li>Date: {move || format_local(timestamp, time_zone.get())}</li>
+                            <li>
+                                Account:
+                                <A href=format!("/accounts/{account_id}")>
+                                    {move || account.get().and_then(Result::ok).map(|summary| summary.account.name)}
+                                </A>
+                            </li>
+                            <li>Spent from: {move || spent_from.map(hoop_name).unwrap_or_else(|| String::from("None"))}</li>
+                            <li>Status: {if pending { "Pending" } else { "Cleared" }}</li>
+                        </ul>
+                        <A href=move || with_search(&format!("/transactions/{id}/edit"), &search.get())>Edit</A>
+                        {action_error(delete)}
+                        <ActionForm action=delete>
+                            <input type="hidden" name="id" value=id.to_string() />
+                            <button type="submit" onclick="return confirm('Are you sure you want to delete this transaction?')">
+                                Delete
+                            </button>
+                        </ActionForm>
+                        <Duplicates id merge />
+                    }.into_view()
+                },
+            })}
+        </Suspense>
+        <A href=move || with_search("/transactions", &search.get())>Back to transactions</A>
+    }
+}
+
+/// The saved transactions a transaction looks like a duplicate of, each with a button to merge it
+/// into the transaction
+#[component]
+fn Duplicates(
+    id: Uuid,
+    merge: Action<TransactionMerge, Result<(), ServerFnError>>,
+) -> impl IntoView {
+    let duplicates = create_resource(
+        move || (id, merge.version().get()),
+        |(id, _)| transaction_duplicates(id),
+    );
+    let time_zone = use_time_zone();
+
+    view! {
+        <Transition fallback=move || view! {<p>Loading...</p>}.into_view()>
+            {move || duplicates.get().map(|duplicates| match duplicates {
+                Err(err) => view! { <pre>Error fetching duplicates: {err.to_string()}</pre> }.into_view(),
+                Ok(duplicates) if duplicates.is_empty() => ().into_view(),
+                Ok(duplicates) => view! {
+                    <h2>Possible duplicates</h2>
+                    {action_error(merge)}
+                    <ul>
+                        {duplicates
+                            .into_iter()
+                            .map(|Transaction { id: duplicate_id, payee, amount, timestamp, .. }| view! {
+                                <li>
+                                    <A href=format!("/transactions/{duplicate_id}")>{payee}</A>
+                                    " " {amount.to_string()} " "
+                                    {move || format_local(timestamp, time_zone.get())}
+                                    <ActionForm action=merge>
+                                        <input type="hidden" name="id" value=id.to_string() />
+                                        <input type="hidden" name="duplicate_id" value=duplicate_id.to_string() />
+                                        <button type="submit">Merge into this one</button>
+                                    </ActionForm>
+                                </li>
+                            })
+                            .collect_view()}
+                    </ul>
+                }.into_view(),
+            })}
+        </Transition>
+    }
+}
+
+/// Form for changing a saved transaction, filled in with its current details
+///
+/// Takes the user back to the transaction's details once it's saved.
+#[component]
+pub fn Edit() -> impl IntoView {
+    let search = use_location().search;
+    let params = use_params_map();
+    let update = create_server_action::<TransactionUpdate>();
+    let errors = action_validation_errors(update);
+    refresh_after(update.version());
+    navigate_after(update, move || {
+        params.with_untracked(|p| {
+            format!("/transactions/{}", p.get("id").cloned().unwrap_or_default())
+        })
+    });
+
+    let transaction = use_transaction();
+
+    view! {
+        <h1>Edit transaction</h1>
+        <Suspense fallback=move || view! {<p>Loading...</p>}.into_view()>
+            {move || transaction.get().map(|transaction| match transaction {
+                Err(err) => {
+                    view! { <pre>Error fetching transaction: {err.to_string()}</pre>}.into_view()
+                },
+                Ok(Transaction { id, amount, description, payee, timestamp, account_id, spent_from, pending, .. }) => {
+                    view! {
+                        {action_server_error(update)}
+                        <ActionForm action=update>
+                            <input type="hidden" name="id" value=id.to_string() />
+                            <Input name="form[payee]".to_string() label="Payee:".to_string() value=payee errors attr:required=true />
+                            <Input name="form[description]".to_string() label="Description:".to_string() value=description.unwrap_or_default() errors />
+                            <InputAmount name="form[amount]".to_string() label="Amount:".to_string() value=amount.to_string() errors attr:required=true />
+                            <AccountSelect name="form[account_id]".to_string() label="Account:".to_string() value=Some(account_id) />
+                            <HoopSelect name="form[spent_from]".to_string() label="Spent from:".to_string() value=spent_from none_label="None".to_string() />
+                            {field_errors(errors, SpendError::FIELD.to_string())}
+                            <label for="form[pending]">Pending:</label>
+                            <input id="form[pending]" name="form[pending]" type="checkbox" value="true" checked=pending />
+                            <DateTimePicker name="form[timestamp]".to_string() label="Timestamp:".to_string() value=timestamp errors attr:required=true />
+                            <button type="submit">Save</button>
+                        </ActionForm>
+                        <A href=move || with_search(&format!("/transactions/{id}"), &search.get())>Cancel</A>
+                    }.into_view()
+                },
+            })}
+        </Suspense>
     }
 }
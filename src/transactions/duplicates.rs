@@ -0,0 +1,306 @@
+//! Finding transactions that are likely the same as one already saved, like a purchase entered by
+//! hand & then imported from the bank's statement, or one entered twice
+//!
+//! Duplicates are made from the same account for the same amount, a few days apart at most since
+//! banks take a while to post transactions, to payees with similar names, since banks add store
+//! numbers & locations to them.
+use std::collections::HashSet;
+
+use cfg_if::cfg_if;
+use chrono::Duration;
+
+use crate::transactions::model::Transaction;
+
+/// Most days apart two transactions can be for one to be a duplicate of the other
+pub const DUPLICATE_WINDOW_DAYS: i64 = 3;
+
+/// Least similar two payees can be for them to be the same, from 0 to 1
+pub const MIN_PAYEE_SIMILARITY: f64 = 0.6;
+
+/// Name of the form field a likely duplicate is reported under, which is confirmed by sending
+/// `allow_duplicate`
+pub const DUPLICATE_FIELD: &str = "duplicate";
+
+/// How similar two payees are, from 0 for nothing in common to 1 for the same payee
+///
+/// Case & punctuation are ignored, & a payee with every word of another is the same payee. Other
+/// payees are compared by the pairs of letters they share.
+///
+/// ```
+/// use hoops_app::transactions::duplicates::payee_similarity;
+///
+/// assert_eq!(payee_similarity("Corner Store", "CORNER-STORE"), 1.0);
+/// assert_eq!(payee_similarity("Corner Store", "CORNER STORE #1234 SYDNEY"), 1.0);
+/// assert!(payee_similarity("Corner Store", "Corner Stores") > 0.9);
+/// assert!(payee_similarity("Corner Store", "Corner Bakery") < 0.6);
+/// assert!(payee_similarity("Corner Store", "Landlord") < 0.2);
+/// assert_eq!(payee_similarity("", "Landlord"), 0.0);
+/// ```
+pub fn payee_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (words(a), words(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let (shorter, longer) = if a.len() <= b.len() {
+        (&a, &b)
+    } else {
+        (&b, &a)
+    };
+    if shorter.iter().all(|word| longer.contains(word)) {
+        return 1.0;
+    }
+
+    let (a, b) = (bigrams(&a.join(" ")), bigrams(&b.join(" ")));
+    let mut unmatched = b.clone();
+    let shared = a
+        .iter()
+        .filter(
+            |bigram| match unmatched.iter().position(|other| other == *bigram) {
+                Some(i) => {
+                    unmatched.swap_remove(i);
+                    true
+                }
+                None => false,
+            },
+        )
+        .count();
+
+    2.0 * shared as f64 / (a.len() + b.len()) as f64
+}
+
+/// Whether one transaction is likely a duplicate of another
+///
+/// ```
+/// use chrono::{Duration, Utc};
+/// use rust_decimal::Decimal;
+/// use uuid::Uuid;
+///
+/// use hoops_app::transactions::duplicates::is_likely_duplicate;
+/// use hoops_app::transactions::model::Transaction;
+///
+/// let account_id = Uuid::new_v4();
+/// let now = Utc::now();
+/// let transaction = |payee: &str, amount, timestamp| {
+///     Transaction::new(amount, payee.into(), timestamp, None, account_id, None)
+/// };
+/// let entered = transaction("Corner Store", Decimal::new(-450, 2), now);
+///
+/// assert!(is_likely_duplicate(&entered, &transaction("CORNER STORE 1234", Decimal::new(-45, 1), now + Duration::days(2))));
+/// assert!(!is_likely_duplicate(&entered, &transaction("Corner Store", Decimal::new(-450, 2), now + Duration::days(4))));
+/// assert!(!is_likely_duplicate(&entered, &transaction("Corner Store", Decimal::new(-460, 2), now)));
+/// assert!(!is_likely_duplicate(&entered, &transaction("Bakery", Decimal::new(-450, 2), now)));
+/// // a transaction isn't a duplicate of itself
+/// assert!(!is_likely_duplicate(&entered, &entered));
+/// ```
+pub fn is_likely_duplicate(transaction: &Transaction, other: &Transaction) -> bool {
+    transaction.id != other.id
+        && transaction.account_id == other.account_id
+        && transaction.amount == other.amount
+        && (transaction.timestamp - other.timestamp).abs() <= window()
+        && payee_similarity(&transaction.payee, &other.payee) >= MIN_PAYEE_SIMILARITY
+}
+
+/// Pair each new transaction with the saved transaction it's likely a duplicate of, if any
+///
+/// New transactions are paired in order, each with the closest in time of the saved transactions
+/// that haven't been paired yet, so two identical purchases aren't both paired with a single saved
+/// one.
+///
+/// ```
+/// use chrono::{Duration, Utc};
+/// use rust_decimal::Decimal;
+/// use uuid::Uuid;
+///
+/// use hoops_app::transactions::duplicates::find_duplicates;
+/// use hoops_app::transactions::model::Transaction;
+///
+/// let account_id = Uuid::new_v4();
+/// let now = Utc::now();
+/// let coffee = |days| {
+///     Transaction::new(Decimal::new(-4, 0), "Cafe".into(), now + Duration::days(days), None, account_id, None)
+/// };
+/// let saved = [coffee(0), coffee(2)];
+/// let new = [coffee(1), coffee(1), coffee(1)];
+///
+/// let found: Vec<_> = find_duplicates(&new, &saved).into_iter().map(|t| t.map(|t| t.id)).collect();
+/// assert_eq!(found, [Some(saved[0].id), Some(saved[1].id), None]);
+/// ```
+pub fn find_duplicates<'a>(
+    transactions: &[Transaction],
+    saved: &'a [Transaction],
+) -> Vec<Option<&'a Transaction>> {
+    let mut paired = HashSet::new();
+
+    transactions
+        .iter()
+        .map(|transaction| {
+            let found = saved
+                .iter()
+                .filter(|other| {
+                    !paired.contains(&other.id) && is_likely_duplicate(transaction, other)
+                })
+                .min_by_key(|other| (other.timestamp - transaction.timestamp).abs());
+            if let Some(found) = found {
+                paired.insert(found.id);
+            }
+            found
+        })
+        .collect()
+}
+
+fn window() -> Duration {
+    Duration::days(DUPLICATE_WINDOW_DAYS)
+}
+
+/// The words of a payee, in lower case & without punctuation
+fn words(payee: &str) -> Vec<String> {
+    payee
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Every pair of neighbouring characters in the text
+fn bigrams(text: &str) -> Vec<(char, char)> {
+    let chars: Vec<char> = text.chars().collect();
+    chars.windows(2).map(|pair| (pair[0], pair[1])).collect()
+}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use anyhow::bail;
+        use chrono::{DateTime, Utc};
+        use sqlx::{Executor, Sqlite, SqliteConnection};
+        use uuid::Uuid;
+
+        use crate::models::{Delete, Read, Scope};
+        use crate::transactions::model::{db_patch_one_checked, db_set_import_id, TransactionFilter, TransactionPatch};
+
+        /// Every saved transaction in the account that could be a duplicate of one made between
+        /// the two timestamps
+        pub async fn db_read_nearby<'c, E>(
+            executor: E,
+            scope: Scope,
+            account_id: Uuid,
+            earliest: DateTime<Utc>,
+            latest: DateTime<Utc>,
+        ) -> Result<Vec<Transaction>, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite>,
+        {
+            let filter = TransactionFilter {
+                account_id: Some(account_id),
+                from: Some(earliest - window()),
+                // the filter stops before its end, while the window includes it
+                until: Some(latest + window() + Duration::seconds(1)),
+                ..Default::default()
+            };
+
+            Transaction::read_many_filtered(executor, scope, &filter, Transaction::ORDER_BY, None, None).await
+        }
+
+        /// Every saved transaction the transaction is likely a duplicate of
+        pub async fn db_find_duplicates<'c, E>(
+            executor: E,
+            scope: Scope,
+            transaction: &Transaction,
+        ) -> Result<Vec<Transaction>, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite>,
+        {
+            let nearby = db_read_nearby(
+                executor,
+                scope,
+                transaction.account_id,
+                transaction.timestamp,
+                transaction.timestamp,
+            )
+            .await?;
+
+            Ok(nearby.into_iter().filter(|other| is_likely_duplicate(transaction, other)).collect())
+        }
+
+        /// Merge a duplicate into the transaction it duplicates, deleting the duplicate & keeping
+        /// the details the other transaction is missing
+        ///
+        /// The merged transaction keeps its description, hoop, & import ID if it has them, &
+        /// takes the duplicate's otherwise. It's only pending if both were. Only a likely duplicate,
+        /// as found by [`is_likely_duplicate`], can be merged. This must be run inside a database
+        /// transaction, which should be rolled back on error.
+        ///
+        /// ```
+        /// use chrono::Utc;
+        /// use rust_decimal::Decimal;
+        ///
+        /// use hoops_app::models::{doc_fixture, Create, Read};
+        /// use hoops_app::transactions::duplicates::db_merge;
+        /// use hoops_app::transactions::model::Transaction;
+        ///
+        /// # #[tokio::main]
+        /// # async fn main() -> Result<(), anyhow::Error> {
+        /// let (pool, scope, account_id) = doc_fixture().await?;
+        ///
+        /// let entered = Transaction::new(Decimal::new(-450, 2), "Corner Store".into(), Utc::now(), None, account_id, None);
+        /// let imported = Transaction {
+        ///     import_id: Some("FITID-1".into()),
+        ///     ..Transaction::new(Decimal::new(-450, 2), "CORNER STORE".into(), Utc::now(), Some("Milk".into()), account_id, None)
+        /// };
+        /// let (entered_id, imported_id) = (entered.id, imported.id);
+        /// Transaction::create_many(&mut *pool.acquire().await?, scope, vec![entered, imported]).await?;
+        ///
+        /// let mut tx = pool.begin().await?;
+        /// let merged = db_merge(&mut tx, scope, entered_id, imported_id).await?.unwrap();
+        /// tx.commit().await?;
+        ///
+        /// assert_eq!(merged.payee, "Corner Store");
+        /// assert_eq!(merged.description.as_deref(), Some("Milk"));
+        /// assert_eq!(merged.import_id.as_deref(), Some("FITID-1"));
+        /// assert!(Transaction::read_one_by_id(&pool, scope, imported_id).await?.is_none());
+        ///
+        /// // a transaction for a different amount isn't a duplicate, so it's never merged
+        /// let other = Transaction::new(Decimal::new(-900, 2), "Corner Store".into(), Utc::now(), None, account_id, None);
+        /// let other_id = other.id;
+        /// Transaction::create_one(&pool, scope, other).await?;
+        /// let mut tx = pool.begin().await?;
+        /// assert!(db_merge(&mut tx, scope, entered_id, other_id).await.is_err());
+        /// tx.rollback().await?;
+        /// assert!(Transaction::read_one_by_id(&pool, scope, other_id).await?.is_some());
+        /// # Ok(())
+        /// # }
+        /// ```
+        pub async fn db_merge(
+            conn: &mut SqliteConnection,
+            scope: Scope,
+            id: Uuid,
+            duplicate_id: Uuid,
+        ) -> Result<Option<Transaction>, anyhow::Error> {
+            let Some(transaction) = Transaction::read_one_by_id(&mut *conn, scope, id).await? else {
+                return Ok(None);
+            };
+            let Some(duplicate) = Transaction::read_one_by_id(&mut *conn, scope, duplicate_id).await? else {
+                return Ok(None);
+            };
+            if !is_likely_duplicate(&transaction, &duplicate) {
+                bail!("Only a likely duplicate of a transaction can be merged into it");
+            }
+
+            // deleted first, so its import ID is free to be moved
+            Transaction::delete_one_by_id(&mut *conn, scope, duplicate_id).await?;
+            let patch = TransactionPatch {
+                description: transaction.description.is_none().then_some(duplicate.description),
+                spent_from: transaction.spent_from.is_none().then_some(duplicate.spent_from),
+                pending: Some(transaction.pending && duplicate.pending),
+                ..Default::default()
+            };
+            let merged = db_patch_one_checked(conn, scope, id, patch).await?;
+            match (transaction.import_id, duplicate.import_id, merged) {
+                (None, Some(import_id), Some(merged)) => {
+                    db_set_import_id(&mut *conn, scope, id, &import_id).await?;
+                    Ok(Some(Transaction { import_id: Some(import_id), ..merged }))
+                }
+                (_, _, merged) => Ok(merged),
+            }
+        }
+    }
+}
@@ -1,3 +1,5 @@
 mod components;
 pub use components::*;
+pub mod duplicates;
 pub mod model;
+pub mod query;
@@ -1,26 +1,44 @@
+use std::fmt;
+
 use cfg_if::cfg_if;
 use chrono::{DateTime, Utc};
-use leptos::*;
+use hoops_macros::Model;
 use rust_decimal::prelude::*;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 use uuid::Uuid;
 
+use crate::errors::ValidationErrors;
+
 /// Data type for modeling a transaction's information
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize, Model)]
+#[model(table = "transactions")]
 pub struct Transaction {
     pub id: Uuid,
     pub amount: Decimal,
     pub description: Option<String>,
     pub payee: String,
     pub timestamp: DateTime<Utc>,
+    /// The account this transaction was made from
+    pub account_id: Uuid,
+    /// The hoop this transaction was spent from, if any
+    pub spent_from: Option<Uuid>,
+    /// Whether the transaction has yet to clear the bank
+    pub pending: bool,
+    /// The ID the bank gave the transaction in the file it was imported from, or a fingerprint of
+    /// its details if the bank didn't give one
+    pub import_id: Option<String>,
 }
 
 impl Transaction {
+    /// Make a new transaction that has already cleared the bank
     pub fn new(
         amount: Decimal,
         payee: String,
         timestamp: DateTime<Utc>,
         description: Option<String>,
+        account_id: Uuid,
+        spent_from: Option<Uuid>,
     ) -> Self {
         Transaction {
             id: Uuid::new_v4(),
@@ -28,155 +46,1089 @@ impl Transaction {
             description,
             payee,
             timestamp,
+            account_id,
+            spent_from,
+            pending: false,
+            import_id: None,
         }
     }
 }
 
+/// Longest payee a transaction can be given
+pub const MAX_PAYEE_LENGTH: usize = 100;
+
+/// Most decimal places an amount can be given with
+pub const AMOUNT_DECIMAL_PLACES: u32 = 2;
+
+/// Check the payee & amount a user entered for a transaction, collecting every problem found
+/// under the name of the form field it belongs to
+///
+/// ```
+/// use rust_decimal::Decimal;
+///
+/// use hoops_app::transactions::model::validate;
+///
+/// assert!(validate("Grocery store", Decimal::new(-1250, 2)).is_empty());
+///
+/// let errors = validate("  ", Decimal::new(-12505, 3));
+/// assert_eq!(errors.get("payee"), ["Enter a payee"]);
+/// assert_eq!(errors.get("amount"), ["Amounts can't have more than 2 decimal places"]);
+///
+/// // trailing zeros don't count towards an amount's decimal places
+/// assert!(validate("Paycheck", Decimal::new(100000, 3)).is_empty());
+/// assert_eq!(
+///     validate("Paycheck", Decimal::ZERO).get("amount"),
+///     ["Enter an amount other than zero"],
+/// );
+/// ```
+pub fn validate(payee: &str, amount: Decimal) -> ValidationErrors {
+    let mut errors = ValidationErrors::default();
+
+    let payee = payee.trim();
+    if payee.is_empty() {
+        errors.add("payee", "Enter a payee");
+    } else if payee.chars().count() > MAX_PAYEE_LENGTH {
+        errors.add(
+            "payee",
+            format!("Payees can be at most {MAX_PAYEE_LENGTH} characters long"),
+        );
+    }
+
+    // amounts are signed by which way the money moves, so zero doesn't move any
+    if amount.is_zero() {
+        errors.add("amount", "Enter an amount other than zero");
+    }
+    if amount.normalize().scale() > AMOUNT_DECIMAL_PLACES {
+        errors.add(
+            "amount",
+            format!("Amounts can't have more than {AMOUNT_DECIMAL_PLACES} decimal places"),
+        );
+    }
+
+    errors
+}
+
+/// A partial set of a transaction's information, used for changing only some of a saved
+/// transaction's fields. Any field left as `None` is not changed.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct TransactionPatch {
+    pub amount: Option<Decimal>,
+    /// `Some(None)` clears the description, while `None` leaves it as is
+    pub description: Option<Option<String>>,
+    pub payee: Option<String>,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub account_id: Option<Uuid>,
+    /// `Some(None)` clears the hoop spent from, while `None` leaves it as is
+    pub spent_from: Option<Option<Uuid>>,
+    pub pending: Option<bool>,
+}
+
+/// Which transactions to list
+///
+/// Every condition that's set has to match, while any left as `None` matches every transaction.
+///
+/// ```
+/// use chrono::{Duration, Utc};
+/// use rust_decimal::Decimal;
+///
+/// use hoops_app::models::{doc_fixture, Create, Read};
+/// use hoops_app::transactions::model::{SortColumn, Transaction, TransactionFilter, TransactionSort};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), anyhow::Error> {
+/// let (pool, scope, account_id) = doc_fixture().await?;
+///
+/// let now = Utc::now();
+/// for (amount, payee, days_ago, description) in [
+///     (Decimal::new(-950, 2), "Corner Store", 3, None),
+///     (Decimal::new(-12000, 2), "Grocery store", 2, Some("Weekly shop")),
+///     (Decimal::new(250000, 2), "Paycheck", 1, None),
+/// ] {
+///     let timestamp = now - Duration::days(days_ago);
+///     let description = description.map(String::from);
+///     let transaction = Transaction::new(amount, payee.into(), timestamp, description, account_id, None);
+///     Transaction::create_one(&pool, scope, transaction).await?;
+/// }
+/// let payees = |transactions: Vec<Transaction>| -> Vec<String> {
+///     transactions.into_iter().map(|t| t.payee).collect()
+/// };
+///
+/// // payees are matched ignoring case
+/// let filter = TransactionFilter { payee: Some("STORE".into()), ..Default::default() };
+/// let order_by = TransactionSort::default().order_by();
+/// let found = Transaction::read_many_filtered(&pool, scope, &filter, &order_by, None, None).await?;
+/// assert_eq!(payees(found), ["Grocery store", "Corner Store"]);
+///
+/// // amounts are compared as numbers, not as the text they're saved as
+/// let filter = TransactionFilter { min_amount: Some(Decimal::new(-100, 0)), ..Default::default() };
+/// let order_by = TransactionSort { column: SortColumn::Amount, descending: false }.order_by();
+/// let found = Transaction::read_many_filtered(&pool, scope, &filter, &order_by, None, None).await?;
+/// assert_eq!(payees(found), ["Corner Store", "Paycheck"]);
+///
+/// let filter = TransactionFilter {
+///     from: Some(now - Duration::days(2)),
+///     has_description: Some(false),
+///     ..Default::default()
+/// };
+/// let found = Transaction::read_many_filtered(&pool, scope, &filter, &order_by, None, None).await?;
+/// assert_eq!(payees(found), ["Paycheck"]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct TransactionFilter {
+    /// Earliest timestamp to include
+    pub from: Option<DateTime<Utc>>,
+    /// Timestamp to stop before
+    pub until: Option<DateTime<Utc>>,
+    pub min_amount: Option<Decimal>,
+    pub max_amount: Option<Decimal>,
+    /// Text the payee contains, ignoring case
+    pub payee: Option<String>,
+    pub account_id: Option<Uuid>,
+    /// The hoop transactions were spent from
+    pub spent_from: Option<Uuid>,
+    pub has_description: Option<bool>,
+}
+
+/// Columns the list of transactions can be sorted by
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum SortColumn {
+    #[default]
+    Timestamp,
+    Amount,
+    Payee,
+}
+
+impl SortColumn {
+    pub const ALL: [SortColumn; 3] = [SortColumn::Timestamp, SortColumn::Amount, SortColumn::Payee];
+
+    /// Name of the column, as used in the list's query params
+    pub const fn name(&self) -> &'static str {
+        match self {
+            SortColumn::Timestamp => "timestamp",
+            SortColumn::Amount => "amount",
+            SortColumn::Payee => "payee",
+        }
+    }
+
+    /// Find the column with the given name, if there is one
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|column| column.name() == name)
+    }
+}
+
+/// How to sort the list of transactions, which is newest first by default
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TransactionSort {
+    pub column: SortColumn,
+    pub descending: bool,
+}
+
+impl Default for TransactionSort {
+    fn default() -> Self {
+        Self {
+            column: SortColumn::Timestamp,
+            descending: true,
+        }
+    }
+}
+
+/// Number of transactions shown on each page of the list
+pub const PAGE_SIZE: u32 = 50;
+
+/// Most transactions a client can ask for in a single page
+pub const MAX_PAGE_SIZE: u32 = 500;
+
+/// The number of transactions to read for a page a client asked for, which is `PAGE_SIZE` unless
+/// it asked for between 1 & `MAX_PAGE_SIZE`
+///
+/// ```
+/// use hoops_app::transactions::model::{page_size, MAX_PAGE_SIZE, PAGE_SIZE};
+///
+/// assert_eq!(page_size(None), PAGE_SIZE);
+/// assert_eq!(page_size(Some(10)), 10);
+/// assert_eq!(page_size(Some(0)), 1);
+/// assert_eq!(page_size(Some(u32::MAX)), MAX_PAGE_SIZE);
+/// ```
+pub fn page_size(limit: Option<u32>) -> u32 {
+    limit.unwrap_or(PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+}
+
+/// A page of the list of transactions, with tokens for reading the pages on either side of it
+///
+/// The tokens are opaque to the client, which only hands them back to read the next or previous
+/// page. They're `None` when there's no page that way.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct TransactionPage {
+    pub transactions: Vec<Transaction>,
+    pub next: Option<String>,
+    pub previous: Option<String>,
+}
+
+/// Text with the parts that matched a search marked, so they can be highlighted
+///
+/// ```
+/// use hoops_app::transactions::model::{Highlighted, HighlightedPart};
+///
+/// let (start, end) = (Highlighted::START, Highlighted::END);
+/// let highlighted = Highlighted::parse(&format!("Weekly {start}shop{end} at the {start}shops{end}"));
+/// let matches: Vec<_> = highlighted.0.iter().filter(|part| part.matched).collect();
+/// assert_eq!(matches.len(), 2);
+/// assert_eq!((matches[0].text.as_str(), matches[1].text.as_str()), ("shop", "shops"));
+/// assert_eq!(highlighted.to_string(), "Weekly shop at the shops");
+///
+/// // plain text has nothing marked
+/// assert_eq!(
+///     Highlighted::from(String::from("Paycheck")).0,
+///     [HighlightedPart { text: "Paycheck".into(), matched: false }],
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Highlighted(pub Vec<HighlightedPart>);
+
+/// A part of some [`Highlighted`] text
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HighlightedPart {
+    pub text: String,
+    /// Whether this part matched the search
+    pub matched: bool,
+}
+
+impl Highlighted {
+    /// Marks the start of a match in text returned by the search index. A private use character,
+    /// so it can't clash with text a user entered
+    pub const START: char = '\u{E000}';
+    /// Marks the end of a match in text returned by the search index
+    pub const END: char = '\u{E001}';
+
+    /// Split text with its matches marked by [`Self::START`] & [`Self::END`] into parts
+    pub fn parse(marked: &str) -> Self {
+        let mut parts = Vec::new();
+        let mut matched = false;
+        for text in marked.split([Self::START, Self::END]) {
+            if !text.is_empty() {
+                parts.push(HighlightedPart {
+                    text: text.to_string(),
+                    matched,
+                });
+            }
+            matched = !matched;
+        }
+
+        Self(parts)
+    }
+}
+
+impl From<String> for Highlighted {
+    fn from(text: String) -> Self {
+        Self(vec![HighlightedPart {
+            text,
+            matched: false,
+        }])
+    }
+}
+
+impl fmt::Display for Highlighted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|part| f.write_str(&part.text))
+    }
+}
+
+/// A transaction found by a search, with the parts of its payee & description that matched marked
+#[derive(Clone, Deserialize, Serialize)]
+pub struct TransactionMatch {
+    pub transaction: Transaction,
+    pub payee: Highlighted,
+    /// An excerpt of the description around its matches, if it has one
+    pub description: Option<Highlighted>,
+}
+
+impl From<Transaction> for TransactionMatch {
+    fn from(transaction: Transaction) -> Self {
+        TransactionMatch {
+            payee: transaction.payee.clone().into(),
+            description: transaction.description.clone().map(Highlighted::from),
+            transaction,
+        }
+    }
+}
+
+/// Reasons a transaction can't be spent from the hoop it was given
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, Error)]
+pub enum SpendError {
+    #[error("No hoop found with id {0}")]
+    HoopNotFound(Uuid),
+    /// Holds the name of the archived hoop
+    #[error("{0} has been archived")]
+    HoopArchived(String),
+    #[error("{hoop} only has {available} available")]
+    InsufficientFunds {
+        /// Name of the hoop spent from
+        hoop: String,
+        /// The hoop's balance without the transaction, including anything it had already spent
+        /// from the hoop before it was changed
+        available: Decimal,
+    },
+}
+
+impl SpendError {
+    /// Name of the form field holding the hoop a transaction is spent from
+    pub const FIELD: &'static str = "spent_from";
+}
+
 // TODO:
 //
-// - [ ] Generalize db_* methods into a collection of Traits to impl a Table<Model, ModelSql> type that can contain
-//       the most common CRUD methods needed (e.g. create_one, read_one_by_id, read_many,
-//       update_one_by_id, delete_one_by_id). Table struct might look like this:
-//       ```
-//       struct Table<Model, ModelSql>
-//       where
-//          Model: Into<ModelSql>,
-//          ModelSql: TryInto<Model>,
-//      {
-//          // used to hold a table name to use when dynamically building queries
-//          table_name: String
-//      }
-//       ```
-// - [ ] add ORDER_BY clause w/ default ordering to db_read_many
-// - [ ] build account feature & add account_id as foreign key
-// - [ ] build envelope feature & add spent_from as nullable foreign key
+// - [x] Generalize db_* methods into a collection of Traits backed by a generic
+//       `models::repository::Table<Model, ModelSql>` type
+// - [x] add ORDER_BY clause w/ default ordering to read_many
+// - [x] build account feature & add account_id as foreign key
+// - [x] build envelope feature & add spent_from as nullable foreign key
 cfg_if! {
     if #[cfg(feature ="ssr")] {
-        use std::convert::TryFrom;
-        use sqlx::{ FromRow, SqlitePool };
+        use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+        use anyhow::bail;
+        use sqlx::SqliteConnection;
+
+        use crate::accounts::model::Account;
+        use crate::hoops::model::{db_balance, Hoop};
+        use sqlx::{Executor, QueryBuilder, Sqlite};
 
-        use crate::models::{Create, Table};
+        use crate::models::{repository::{ID, OWNER_ID}, Create, Delete, Direction, Filter, Ident, OrderBy, Read, Scope, Update};
 
-        pub fn pool() -> Result<SqlitePool, ServerFnError> {
-            use_context::<SqlitePool>()
-                .ok_or_else(|| ServerFnError::ServerError("Pool missing".into()))
+        const TIMESTAMP: Ident = Ident::new("timestamp");
+        /// Numeric copy of the amount, since amounts are saved as text
+        const AMOUNT_VALUE: Ident = Ident::new("amount_value");
+        const PAYEE: Ident = Ident::new("payee");
+        const DESCRIPTION: Ident = Ident::new("description");
+        const ACCOUNT_ID: Ident = Ident::new("account_id");
+        const SPENT_FROM: Ident = Ident::new("spent_from");
+
+        impl Filter for TransactionFilter {
+            fn push_conditions(&self, query: &mut QueryBuilder<'_, Sqlite>) {
+                let TransactionFilter {
+                    from,
+                    until,
+                    min_amount,
+                    max_amount,
+                    payee,
+                    account_id,
+                    spent_from,
+                    has_description,
+                } = self;
+
+                // timestamps are saved as RFC 3339 strings in UTC, so they sort the same as text
+                if let Some(from) = from {
+                    query.push(" AND ").push(TIMESTAMP).push(" >= ");
+                    query.push_bind(from.to_rfc3339());
+                }
+                if let Some(until) = until {
+                    query.push(" AND ").push(TIMESTAMP).push(" < ");
+                    query.push_bind(until.to_rfc3339());
+                }
+                if let Some(min_amount) = min_amount.and_then(|amount| amount.to_f64()) {
+                    query.push(" AND ").push(AMOUNT_VALUE).push(" >= ");
+                    query.push_bind(min_amount);
+                }
+                if let Some(max_amount) = max_amount.and_then(|amount| amount.to_f64()) {
+                    query.push(" AND ").push(AMOUNT_VALUE).push(" <= ");
+                    query.push_bind(max_amount);
+                }
+                if let Some(payee) = payee.as_deref().filter(|payee| !payee.is_empty()) {
+                    // the payee is matched literally, so LIKE's wildcards are escaped
+                    let escaped = payee.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+                    query.push(" AND ").push(PAYEE).push(" LIKE ");
+                    query.push_bind(format!("%{escaped}%"));
+                    query.push(" ESCAPE '\\'");
+                }
+                if let Some(account_id) = account_id {
+                    query.push(" AND ").push(ACCOUNT_ID).push(" = ");
+                    query.push_bind(account_id.to_string());
+                }
+                if let Some(spent_from) = spent_from {
+                    query.push(" AND ").push(SPENT_FROM).push(" = ");
+                    query.push_bind(spent_from.to_string());
+                }
+                match has_description {
+                    Some(true) => query.push(" AND ").push(DESCRIPTION).push(" IS NOT NULL"),
+                    Some(false) => query.push(" AND ").push(DESCRIPTION).push(" IS NULL"),
+                    None => query,
+                };
+            }
         }
 
-        #[derive(FromRow, Clone)]
-        pub struct TransactionSql {
-            id: String,
-            amount: String,
-            description: Option<String>,
-            payee: String,
+        /// Most matches a search returns
+        pub const MAX_SEARCH_RESULTS: u32 = 50;
+
+        /// Turn what a user typed into a search box into a full-text query matching every word,
+        /// or the start of it, or `None` if there aren't any words
+        ///
+        /// Each word is quoted, so nothing a user types is read as the search index's own syntax.
+        ///
+        /// ```
+        /// use hoops_app::transactions::model::fts_query;
+        ///
+        /// assert_eq!(fts_query("groc store").as_deref(), Some("\"groc\"* \"store\"*"));
+        /// assert_eq!(fts_query("say \"hi\" OR").as_deref(), Some("\"say\"* \"\"\"hi\"\"\"* \"OR\"*"));
+        /// assert_eq!(fts_query("   "), None);
+        /// ```
+        pub fn fts_query(search: &str) -> Option<String> {
+            let terms: Vec<_> = search
+                .split_whitespace()
+                .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+                .collect();
+
+            (!terms.is_empty()).then(|| terms.join(" "))
+        }
+
+        /// Row read by [`db_search`], holding the match's highlighted text along with it
+        #[derive(sqlx::FromRow)]
+        struct MatchSql {
+            #[sqlx(flatten)]
+            transaction: TransactionSql,
+            payee_highlight: String,
+            description_snippet: Option<String>,
+        }
+
+        /// Search the user's transactions' payees & descriptions, returning the best matches
+        /// first
+        ///
+        /// Only transactions matching the filter are searched. Each word searched for has to be
+        /// found, though it can just be the start of a longer word.
+        ///
+        /// ```
+        /// use chrono::Utc;
+        /// use rust_decimal::Decimal;
+        ///
+        /// use hoops_app::models::{doc_fixture_two_users, Create, Delete, Update};
+        /// use hoops_app::transactions::model::{db_search, Transaction, TransactionFilter, TransactionPatch};
+        ///
+        /// # #[tokio::main]
+        /// # async fn main() -> Result<(), anyhow::Error> {
+        /// let (pool, alice, bob, account_id) = doc_fixture_two_users().await?;
+        ///
+        /// let amount = Decimal::new(-2000, 2);
+        /// let new = |payee: &str, description: &str| {
+        ///     let description = Some(description.to_string()).filter(|d| !d.is_empty());
+        ///     Transaction::new(amount, payee.into(), Utc::now(), description, account_id, None)
+        /// };
+        /// let groceries = new("Grocery store", "Weekly shop");
+        /// let groceries_id = groceries.id;
+        /// Transaction::create_one(&pool, alice, groceries).await?;
+        /// let cafe = new("Café Luna", "");
+        /// let cafe_id = cafe.id;
+        /// Transaction::create_one(&pool, alice, cafe).await?;
+        ///
+        /// let filter = TransactionFilter::default();
+        /// let found = db_search(&pool, alice, "week", &filter).await?;
+        /// assert_eq!(found.len(), 1);
+        /// assert_eq!(found[0].transaction.id, groceries_id);
+        /// assert_eq!(found[0].description.as_ref().unwrap().to_string(), "Weekly shop");
+        ///
+        /// // accents don't need to be typed, & changes are kept in the search index
+        /// assert_eq!(db_search(&pool, alice, "cafe", &filter).await?.len(), 1);
+        /// let patch = TransactionPatch { payee: Some("Bakery".into()), ..Default::default() };
+        /// Transaction::patch_one_by_id(&pool, alice, cafe_id, patch).await?;
+        /// assert!(db_search(&pool, alice, "cafe", &filter).await?.is_empty());
+        /// assert_eq!(db_search(&pool, alice, "bak", &filter).await?.len(), 1);
+        ///
+        /// // other users' transactions aren't searched
+        /// assert!(db_search(&pool, bob, "grocery", &filter).await?.is_empty());
+        ///
+        /// Transaction::delete_one_by_id(&pool, alice, groceries_id).await?;
+        /// assert!(db_search(&pool, alice, "grocery", &filter).await?.is_empty());
+        /// # Ok(())
+        /// # }
+        /// ```
+        pub async fn db_search<'c, E>(
+            executor: E,
+            scope: Scope,
+            search: &str,
+            filter: &TransactionFilter,
+        ) -> Result<Vec<TransactionMatch>, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite>,
+        {
+            let Some(fts_query) = fts_query(search) else {
+                return Ok(Vec::new());
+            };
+            let (start, end) = (Highlighted::START.to_string(), Highlighted::END.to_string());
+
+            // the index's columns are named so the filter's columns only match the transaction's
+            let mut query = QueryBuilder::<Sqlite>::new("SELECT transactions.*, highlight(transactions_fts, 1, ");
+            query.push_bind(start.clone()).push(", ").push_bind(end.clone());
+            query.push(") AS payee_highlight, snippet(transactions_fts, 2, ");
+            query.push_bind(start).push(", ").push_bind(end);
+            query.push(", '…', 12) AS description_snippet");
+            query.push(" FROM transactions_fts JOIN transactions ON transactions.id = transactions_fts.transaction_id");
+            query.push(" WHERE transactions_fts MATCH ").push_bind(fts_query);
+            query.push(" AND transactions.").push(OWNER_ID).push(" = ");
+            query.push_bind(scope.owner_id().to_string());
+            filter.push_conditions(&mut query);
+            query.push(" ORDER BY rank LIMIT ").push_bind(MAX_SEARCH_RESULTS);
+            query.push(";");
+
+            query
+                .build_query_as::<MatchSql>()
+                .fetch_all(executor)
+                .await?
+                .into_iter()
+                .map(|MatchSql { transaction, payee_highlight, description_snippet }| {
+                    let transaction = Transaction::try_from(transaction)?;
+                    // the snippet is empty when there's no description
+                    let description = description_snippet
+                        .filter(|_| transaction.description.is_some())
+                        .map(|snippet| Highlighted::parse(&snippet));
+
+                    Ok(TransactionMatch {
+                        payee: Highlighted::parse(&payee_highlight),
+                        description,
+                        transaction,
+                    })
+                })
+                .collect()
+        }
+
+        impl TransactionSort {
+            /// Columns to sort by in a query, breaking ties by newest first so the order is stable
+            pub fn order_by(&self) -> Vec<OrderBy> {
+                let column = match self.column {
+                    SortColumn::Timestamp => TIMESTAMP,
+                    SortColumn::Amount => AMOUNT_VALUE,
+                    SortColumn::Payee => PAYEE,
+                };
+                let first = match self.descending {
+                    true => OrderBy::desc(column),
+                    false => OrderBy::asc(column),
+                };
+
+                match self.column {
+                    SortColumn::Timestamp => vec![first, OrderBy::desc(ID)],
+                    _ => vec![first, OrderBy::desc(TIMESTAMP), OrderBy::desc(ID)],
+                }
+            }
+        }
+
+        /// A place in the sorted list of transactions, just after or just before a transaction,
+        /// that a page of the list is read from
+        ///
+        /// Pages are found by the sorted columns' values instead of an offset, so transactions
+        /// added or removed earlier in the list don't shift which ones a page shows. The client
+        /// only ever sees a cursor as the opaque token made by `to_token`, which holds the filter
+        /// & sort of the list it was made for.
+        #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+        pub struct Cursor {
+            filter: TransactionFilter,
+            sort: TransactionSort,
+            /// Whether the page is the one before the transaction, instead of after it
+            backwards: bool,
+            /// The transaction's value in the sorted column, as it's saved
+            key: String,
             timestamp: String,
+            id: String,
         }
 
-        impl TryFrom<TransactionSql> for Transaction {
-            type Error = anyhow::Error;
+        impl Cursor {
+            /// The place just after the transaction, in the list with the given filter & sort
+            pub fn after(filter: &TransactionFilter, sort: TransactionSort, transaction: &Transaction) -> Self {
+                let key = match sort.column {
+                    SortColumn::Timestamp => transaction.timestamp.to_rfc3339(),
+                    SortColumn::Amount => transaction.amount.to_string(),
+                    SortColumn::Payee => transaction.payee.clone(),
+                };
+
+                Self {
+                    filter: filter.clone(),
+                    sort,
+                    backwards: false,
+                    key,
+                    timestamp: transaction.timestamp.to_rfc3339(),
+                    id: transaction.id.to_string(),
+                }
+            }
+
+            /// The place just before the transaction, in the list with the given filter & sort
+            pub fn before(filter: &TransactionFilter, sort: TransactionSort, transaction: &Transaction) -> Self {
+                Self {
+                    backwards: true,
+                    ..Self::after(filter, sort, transaction)
+                }
+            }
+
+            /// Write the cursor as a token for the client to hand back
+            pub fn to_token(&self) -> String {
+                hex::encode(serde_json::to_vec(self).unwrap_or_default())
+            }
+
+            /// Read a cursor from a token, or `None` if it isn't one
+            pub fn from_token(token: &str) -> Option<Self> {
+                serde_json::from_slice(&hex::decode(token).ok()?).ok()
+            }
+
+            /// Columns to sort by when reading the page, which is backwards from the list's order
+            /// for the page before the transaction
+            fn order_by(&self) -> Vec<OrderBy> {
+                let order_by = self.sort.order_by();
+                match self.backwards {
+                    true => order_by.iter().map(OrderBy::reversed).collect(),
+                    false => order_by,
+                }
+            }
+
+            /// Push a comparison of the column with the cursor's value for it
+            fn push_comparison(&self, query: &mut QueryBuilder<'_, Sqlite>, column: Ident, operator: &str) {
+                query.push(column).push(operator);
+                match column {
+                    TIMESTAMP => query.push_bind(self.timestamp.clone()),
+                    ID => query.push_bind(self.id.clone()),
+                    // read the same way the numeric copy of the amount is made from its text
+                    AMOUNT_VALUE => query.push("CAST(").push_bind(self.key.clone()).push(" AS REAL)"),
+                    _ => query.push_bind(self.key.clone()),
+                };
+            }
+        }
 
-            fn try_from(value: TransactionSql) -> Result<Self, Self::Error> {
-                let TransactionSql { id, amount, description, payee, timestamp } = value;
-                // any of these conversions can fail, return early if one does
-                let id = Uuid::parse_str(&id)?;
-                let amount = Decimal::from_str_exact(&amount)?;
-                let timestamp = DateTime::from(DateTime::parse_from_rfc3339(&timestamp)?);
+        /// Rows further along the page's order than the cursor's transaction, i.e. past it in the
+        /// first column, or tied on the first & past it in the second, & so on
+        impl Filter for Cursor {
+            fn push_conditions(&self, query: &mut QueryBuilder<'_, Sqlite>) {
+                let order_by = self.order_by();
 
-                Ok(Transaction { id, amount, description, payee, timestamp })
+                query.push(" AND (");
+                for (i, OrderBy { column, direction }) in order_by.iter().enumerate() {
+                    if i > 0 {
+                        query.push(" OR ");
+                    }
+                    query.push("(");
+                    for tied in &order_by[..i] {
+                        self.push_comparison(query, tied.column, " = ");
+                        query.push(" AND ");
+                    }
+                    let operator = match direction {
+                        Direction::Asc => " > ",
+                        Direction::Desc => " < ",
+                    };
+                    self.push_comparison(query, *column, operator);
+                    query.push(")");
+                }
+                query.push(")");
             }
         }
 
-        impl From<Transaction> for TransactionSql {
-            fn from(value: Transaction) -> Self {
-                let Transaction { id, amount, description, payee, timestamp } = value;
-                let amount = amount.to_string();
-                let id = id
-                    .hyphenated()
-                    .encode_lower(&mut Uuid::encode_buffer())
-                    .to_string();
-                let timestamp = timestamp.to_rfc3339();
+        /// Read a page of the transactions matching the filter, in the sort's order, from the
+        /// cursor or from the top of the list without one
+        ///
+        /// A cursor made for a list with a different filter or sort is ignored, starting the list
+        /// from the top again.
+        ///
+        /// ```
+        /// use chrono::{Duration, Utc};
+        /// use rust_decimal::Decimal;
+        ///
+        /// use hoops_app::models::{doc_fixture, Create};
+        /// use hoops_app::transactions::model::{
+        ///     db_read_page, Cursor, Transaction, TransactionFilter, TransactionPage, TransactionSort,
+        /// };
+        ///
+        /// # #[tokio::main]
+        /// # async fn main() -> Result<(), anyhow::Error> {
+        /// let (pool, scope, account_id) = doc_fixture().await?;
+        ///
+        /// // two of the transactions happened at the same moment, so the ID breaks the tie
+        /// let now = Utc::now();
+        /// for (payee, days_ago) in [("A", 5), ("B", 4), ("C", 4), ("D", 2), ("E", 1)] {
+        ///     let timestamp = now - Duration::days(days_ago);
+        ///     let transaction = Transaction::new(Decimal::ONE, payee.into(), timestamp, None, account_id, None);
+        ///     Transaction::create_one(&pool, scope, transaction).await?;
+        /// }
+        /// let payees = |page: &TransactionPage| -> Vec<String> {
+        ///     page.transactions.iter().map(|t| t.payee.clone()).collect()
+        /// };
+        /// let (filter, sort) = (TransactionFilter::default(), TransactionSort::default());
+        /// let read = |token: &Option<String>| {
+        ///     let cursor = token.as_deref().and_then(Cursor::from_token);
+        ///     db_read_page(&pool, scope, &filter, sort, cursor, 2)
+        /// };
+        ///
+        /// let first = read(&None).await?;
+        /// assert_eq!(payees(&first), ["E", "D"]);
+        /// assert!(first.previous.is_none());
+        ///
+        /// // a new transaction at the top of the list doesn't shift the later pages
+        /// let transaction = Transaction::new(Decimal::ONE, "F".into(), now, None, account_id, None);
+        /// Transaction::create_one(&pool, scope, transaction).await?;
+        ///
+        /// let second = read(&first.next).await?;
+        /// assert_eq!(payees(&second).len(), 2);
+        /// assert!(payees(&second).iter().all(|payee| payee == "B" || payee == "C"));
+        /// let third = read(&second.next).await?;
+        /// assert_eq!(payees(&third), ["A"]);
+        /// assert!(third.next.is_none());
+        ///
+        /// // going back a page shows the page before it, including the new transaction
+        /// assert_eq!(payees(&read(&third.previous).await?), payees(&second));
+        /// let back = read(&second.previous).await?;
+        /// assert_eq!(payees(&back), ["E", "D"]);
+        /// assert_eq!(payees(&read(&back.previous).await?), ["F"]);
+        ///
+        /// // a token from a differently filtered or sorted list starts the list from the top again
+        /// let payee = TransactionFilter { payee: Some("A".into()), ..Default::default() };
+        /// let cursor = first.next.as_deref().and_then(Cursor::from_token);
+        /// let page = db_read_page(&pool, scope, &payee, sort, cursor, 2).await?;
+        /// assert_eq!(payees(&page), ["A"]);
+        ///
+        /// // a page can't be read past the end of the numbers
+        /// assert_eq!(db_read_page(&pool, scope, &filter, sort, None, u32::MAX).await?.transactions.len(), 6);
+        ///
+        /// // tokens aren't readable as anything else
+        /// assert_eq!(Cursor::from_token("not a cursor"), None);
+        /// # Ok(())
+        /// # }
+        /// ```
+        pub async fn db_read_page<'c, E>(
+            executor: E,
+            scope: Scope,
+            filter: &TransactionFilter,
+            sort: TransactionSort,
+            cursor: Option<Cursor>,
+            limit: u32,
+        ) -> Result<TransactionPage, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite>,
+        {
+            let cursor = cursor.filter(|cursor| cursor.filter == *filter && cursor.sort == sort);
+            let backwards = cursor.as_ref().is_some_and(|cursor| cursor.backwards);
+            let order_by = match &cursor {
+                Some(cursor) => cursor.order_by(),
+                None => sort.order_by(),
+            };
+            let had_cursor = cursor.is_some();
 
-                Self { id, amount, description, payee, timestamp }
+            // reading one more than the page holds shows whether there's another page after it
+            let conditions = (filter.clone(), cursor);
+            let mut transactions =
+                Transaction::read_many_filtered(executor, scope, &conditions, &order_by, Some(limit.saturating_add(1)), None)
+                    .await?;
+            let more = transactions.len() > limit as usize;
+            transactions.truncate(limit as usize);
+            if backwards {
+                transactions.reverse();
             }
+
+            // the page a cursor came from is always on the other side of the one it leads to
+            let (has_next, has_previous) = match backwards {
+                true => (true, more),
+                false => (more, had_cursor),
+            };
+            let next = transactions
+                .last()
+                .filter(|_| has_next)
+                .map(|last| Cursor::after(filter, sort, last).to_token());
+            let previous = transactions
+                .first()
+                .filter(|_| has_previous)
+                .map(|first| Cursor::before(filter, sort, first).to_token());
+
+            Ok(TransactionPage {
+                transactions,
+                next,
+                previous,
+            })
         }
 
-        impl Table for Transaction {
-            const TABLE: &'static str = "transactions";
+        impl Create for Transaction {
+            type SqlType = TransactionSql;
         }
 
-        impl Create<'_> for Transaction {
+        impl Read for Transaction {
             type SqlType = TransactionSql;
 
-            async fn create_one(pool: &SqlitePool, value: Self) -> Result<(), anyhow::Error> {
-                let TransactionSql {id, amount, description, payee, timestamp} = value.into();
-
-                // TODO:
-                //
-                // I've been looking for a way to generalize this so Create can provide a default
-                // impl or an internal default impl that is intended to be used by the implementor
-                // to make creating this function easier.
-                //
-                // Seems like I could get all the struct's field names using macros, but not sure I
-                // want to go that route. A super naive version here: https://stackoverflow.com/questions/29986057/is-there-a-way-to-get-the-field-names-of-a-struct-in-a-macro
-                // But that just returns a vec of strs, so not exactly useful still?
-                //
-                // Could also look at a proc macro, but that's a much hairier beast...
-                sqlx::query!(
-                    r#"
-                    INSERT INTO transactions (id, amount, description, payee, timestamp)
-                    VALUES (?, ?, ?, ?, ?);
-                    "#,
-                    id,
+            const ORDER_BY: &'static [OrderBy] = &[OrderBy::desc(TIMESTAMP)];
+        }
+
+        impl Update for Transaction {
+            type Patch = TransactionPatch;
+
+            fn apply_patch(&mut self, patch: TransactionPatch) {
+                let TransactionPatch {
                     amount,
                     description,
                     payee,
                     timestamp,
-                )
-                    .execute(pool)
-                    .await
-                    .map(|_| ())
-                    .map_err(|e| e.into())
-            }
-        }
-
-        // TODO:
-        // ```
-        // pub trait Read<T, Id> {
-        //   /// Read one item with given ID from the database, if it exists
-        //   async fn read_one_by_id(self, item: Id) -> Result<Option<T>, anyhow::Error>;
-        //   /// Read many items from the database
-        //   async fn read_many(self) -> Result<Vec<T>, anyhow::Error>;
-        // }
-        // ```
-        pub async fn db_read_many(pool: &SqlitePool, limit: Option<u32>, offset: Option<u32>) -> Result<Vec<Transaction>, anyhow::Error> {
-            // needed to enable try_next on returned rows stream
-            use futures::TryStreamExt;
-
-            // use provided limit & offset for pagination, or default to 100 rows starting with the
-            // first result
-            let limit = limit.unwrap_or(100);
-            let offset = offset.unwrap_or(0);
-
-            let mut transactions: Vec<Transaction> = Vec::new();
-            // rows must be mutable here...
-            let mut rows = sqlx::query_as::<_, TransactionSql>(
-                r#"
-                SELECT * FROM transactions
-                ORDER BY timestamp DESC
-                LIMIT ?
-                OFFSET ?;
-                "#
+                    account_id,
+                    spent_from,
+                    pending,
+                } = patch;
+
+                if let Some(amount) = amount {
+                    self.amount = amount;
+                }
+                if let Some(description) = description {
+                    self.description = description;
+                }
+                if let Some(payee) = payee {
+                    self.payee = payee;
+                }
+                if let Some(timestamp) = timestamp {
+                    self.timestamp = timestamp;
+                }
+                if let Some(account_id) = account_id {
+                    self.account_id = account_id;
+                }
+                if let Some(spent_from) = spent_from {
+                    self.spent_from = spent_from;
+                }
+                if let Some(pending) = pending {
+                    self.pending = pending;
+                }
+            }
+        }
+
+        impl Delete for Transaction {}
+
+        /// Save a new transaction to one of the user's open accounts, failing with a
+        /// [`SpendError`] if it's spent from a hoop that doesn't have enough funds for it
+        ///
+        /// This must be run inside a database transaction, which should be rolled back on error.
+        pub async fn db_create_one_checked(
+            conn: &mut SqliteConnection,
+            scope: Scope,
+            value: Transaction,
+        ) -> Result<(), anyhow::Error> {
+            check_account_open(conn, scope, value.account_id).await?;
+            check_hoop_open(conn, scope, value.spent_from).await?;
+            Transaction::create_one(&mut *conn, scope, value.clone()).await?;
+            check_spend(conn, scope, &value).await
+        }
+
+        /// Save many new transactions at once, failing if any is made from an account that isn't
+        /// open, or spent from a hoop that can't be spent from or doesn't have enough funds for it
+        ///
+        /// Each account & hoop is only checked once, with the hoops' balances checked after every
+        /// transaction is saved. This must be run inside a database transaction, which should be
+        /// rolled back on error.
+        pub async fn db_create_many_checked(
+            conn: &mut SqliteConnection,
+            scope: Scope,
+            values: Vec<Transaction>,
+        ) -> Result<(), anyhow::Error> {
+            let accounts: BTreeSet<Uuid> = values.iter().map(|t| t.account_id).collect();
+            for account_id in accounts {
+                check_account_open(conn, scope, account_id).await?;
+            }
+
+            let hoops: BTreeSet<Uuid> = values.iter().filter_map(|t| t.spent_from).collect();
+            for hoop_id in hoops {
+                check_hoop_open(conn, scope, Some(hoop_id)).await?;
+            }
+
+            // one transaction spending from each hoop stands in for the rest when checking its
+            // balance
+            let mut spending = BTreeMap::new();
+            for transaction in values.iter().filter(|t| t.amount < Decimal::ZERO) {
+                if let Some(hoop_id) = transaction.spent_from {
+                    spending.entry(hoop_id).or_insert_with(|| transaction.clone());
+                }
+            }
+
+            Transaction::create_many(&mut *conn, scope, values).await?;
+            for transaction in spending.values() {
+                check_spend(conn, scope, transaction).await?;
+            }
+
+            Ok(())
+        }
+
+        /// Read every transaction owned by the scope's user, oldest first
+        pub async fn db_read_all<'c, E>(executor: E, scope: Scope) -> Result<Vec<Transaction>, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite>,
+        {
+            sqlx::query_as::<_, TransactionSql>(
+                "SELECT * FROM transactions WHERE owner_id = ? ORDER BY timestamp ASC, id ASC;"
+            )
+                .bind(scope.owner_id().to_string())
+                .fetch_all(executor)
+                .await?
+                .into_iter()
+                .map(Transaction::try_from)
+                .collect()
+        }
+
+        /// The import IDs of every transaction already imported into one of the user's accounts
+        pub async fn db_read_import_ids<'c, E>(
+            executor: E,
+            scope: Scope,
+            account_id: Uuid,
+        ) -> Result<HashSet<String>, sqlx::Error>
+        where
+            E: Executor<'c, Database = Sqlite>,
+        {
+            sqlx::query_scalar(
+                "SELECT import_id FROM transactions WHERE owner_id = ? AND account_id = ? AND import_id IS NOT NULL;"
             )
-                .bind(limit)
-                .bind(offset)
-                .fetch(pool);
+                .bind(scope.owner_id().to_string())
+                .bind(account_id.to_string())
+                .fetch_all(executor)
+                .await
+                .map(|ids| ids.into_iter().collect())
+        }
+
+        /// Give a saved transaction the import ID of one it's a duplicate of, if it doesn't have one,
+        /// so importing the duplicate again skips it
+        pub async fn db_set_import_id<'c, E>(
+            executor: E,
+            scope: Scope,
+            id: Uuid,
+            import_id: &str,
+        ) -> Result<(), sqlx::Error>
+        where
+            E: Executor<'c, Database = Sqlite>,
+        {
+            sqlx::query("UPDATE transactions SET import_id = ? WHERE owner_id = ? AND id = ? AND import_id IS NULL;")
+                .bind(import_id)
+                .bind(scope.owner_id().to_string())
+                .bind(id.to_string())
+                .execute(executor)
+                .await
+                .map(|_| ())
+        }
+
+        /// Change part of a saved transaction, failing with a [`SpendError`] if it ends up spent
+        /// from a hoop that doesn't have enough funds for it
+        ///
+        /// A transaction in an account that's since been closed, or already spent from a hoop
+        /// that's since been archived, can still be changed, as long as it stays in that account &
+        /// spent from that hoop. This must be run inside a database transaction, which should be
+        /// rolled back on error.
+        ///
+        /// What the transaction already spent from the hoop counts as available when its amount
+        /// is changed.
+        ///
+        /// ```
+        /// use chrono::Utc;
+        /// use rust_decimal::Decimal;
+        ///
+        /// use hoops_app::hoops::model::{db_move_funds, Hoop};
+        /// use hoops_app::models::{doc_fixture, Create};
+        /// use hoops_app::transactions::model::{db_create_one_checked, db_patch_one_checked, SpendError, Transaction, TransactionPatch};
+        ///
+        /// # #[tokio::main]
+        /// # async fn main() -> Result<(), anyhow::Error> {
+        /// let (pool, scope, account_id) = doc_fixture().await?;
+        /// let hoop = Hoop::new("Groceries".into());
+        /// let hoop_id = hoop.id;
+        /// Hoop::create_one(&pool, scope, hoop).await?;
+        /// db_move_funds(&mut *pool.acquire().await?, scope, None, Some(hoop_id), Decimal::new(25, 0)).await?;
+        ///
+        /// // 10 of the hoop's 25 is spent, leaving 15 in it
+        /// let transaction = Transaction::new(Decimal::new(-10, 0), "Grocery store".into(), Utc::now(), None, account_id, Some(hoop_id));
+        /// let id = transaction.id;
+        /// db_create_one_checked(&mut *pool.acquire().await?, scope, transaction).await?;
+        ///
+        /// // the 10 it already spent is available to it again, so it can spend 25 in all
+        /// let patch = |amount| TransactionPatch { amount: Some(Decimal::new(amount, 0)), ..Default::default() };
+        /// let mut tx = pool.begin().await?;
+        /// let err = db_patch_one_checked(&mut tx, scope, id, patch(-30)).await.err().unwrap();
+        /// assert_eq!(
+        ///     err.downcast::<SpendError>()?,
+        ///     SpendError::InsufficientFunds { hoop: "Groceries".into(), available: Decimal::new(25, 0) },
+        /// );
+        /// tx.rollback().await?;
+        ///
+        /// let mut tx = pool.begin().await?;
+        /// let patched = db_patch_one_checked(&mut tx, scope, id, patch(-25)).await?.unwrap();
+        /// tx.commit().await?;
+        /// assert_eq!(patched.amount, Decimal::new(-25, 0));
+        /// # Ok(())
+        /// # }
+        /// ```
+        pub async fn db_patch_one_checked(
+            conn: &mut SqliteConnection,
+            scope: Scope,
+            id: Uuid,
+            patch: TransactionPatch,
+        ) -> Result<Option<Transaction>, anyhow::Error> {
+            // only an account or hoop the transaction's being moved to has to be open
+            let saved = Transaction::read_one_by_id(&mut *conn, scope, id).await?;
+            if let Some(account_id) = patch.account_id {
+                if saved.as_ref().map(|saved| saved.account_id) != Some(account_id) {
+                    check_account_open(conn, scope, account_id).await?;
+                }
+            }
+            if let Some(spent_from) = patch.spent_from {
+                if saved.as_ref().map(|saved| saved.spent_from) != Some(spent_from) {
+                    check_hoop_open(conn, scope, spent_from).await?;
+                }
+            }
+            let Some(transaction) = Transaction::patch_one_by_id(&mut *conn, scope, id, patch).await? else {
+                return Ok(None);
+            };
+            check_spend(conn, scope, &transaction).await?;
+
+            Ok(Some(transaction))
+        }
+
+        /// Check the account a transaction is being made from is one of the user's open accounts
+        async fn check_account_open(
+            conn: &mut SqliteConnection,
+            scope: Scope,
+            account_id: Uuid,
+        ) -> Result<(), anyhow::Error> {
+            match Account::read_one_by_id(conn, scope, account_id).await? {
+                Some(account) if !account.closed => Ok(()),
+                _ => bail!("No open account found with id {account_id}"),
+            }
+        }
+
+        /// Check the hoop a transaction is being spent from can still be spent from
+        async fn check_hoop_open(
+            conn: &mut SqliteConnection,
+            scope: Scope,
+            hoop_id: Option<Uuid>,
+        ) -> Result<(), anyhow::Error> {
+            let Some(hoop_id) = hoop_id else {
+                return Ok(());
+            };
 
-            // ...because it is destructively consumed here
-            while let Some(row) = rows.try_next().await? {
-                let tran: Transaction = row.try_into()?;
-                transactions.push(tran);
+            match Hoop::read_one_by_id(conn, scope, hoop_id).await? {
+                None => Err(SpendError::HoopNotFound(hoop_id).into()),
+                Some(hoop) if hoop.archived => Err(SpendError::HoopArchived(hoop.name).into()),
+                Some(_) => Ok(()),
             }
+        }
+
+        /// Check the hoop a saved transaction is spent from wasn't overdrawn by it
+        ///
+        /// The check is made after the transaction is written, so the database's write lock is
+        /// already held & no other spending can change the balance before it's committed.
+        async fn check_spend(
+            conn: &mut SqliteConnection,
+            scope: Scope,
+            transaction: &Transaction,
+        ) -> Result<(), anyhow::Error> {
+            // money coming back into a hoop can't overdraw it
+            let Some(hoop_id) = transaction.spent_from.filter(|_| transaction.amount < Decimal::ZERO) else {
+                return Ok(());
+            };
 
-            Ok(transactions)
+            // read after the transaction's written, so the balance holds its amount as it's saved
+            // now, & none of the amount it had before any change
+            let balance = db_balance(&mut *conn, scope, hoop_id).await?;
+            if balance >= Decimal::ZERO {
+                return Ok(());
+            }
+
+            let hoop = Hoop::read_one_by_id(conn, scope, hoop_id)
+                .await?
+                .ok_or(SpendError::HoopNotFound(hoop_id))?;
+
+            Err(SpendError::InsufficientFunds {
+                hoop: hoop.name,
+                available: balance - transaction.amount,
+            }
+            .into())
         }
     }
 }
@@ -0,0 +1,276 @@
+//! The filters, sorting, & page of the list of transactions, kept in the URL's query params so
+//! navigating away from the list & back shows the same transactions
+use std::str::FromStr;
+
+use chrono::{Duration, NaiveDate};
+use chrono_tz::Tz;
+use leptos_router::{Params, ParamsError, ParamsMap};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::time::start_of_day;
+use crate::transactions::model::{SortColumn, TransactionFilter, TransactionSort};
+
+/// The list of transactions' state, as read from & written to its query params
+///
+/// Dates are entered in the user's time zone, so they're only turned into a [`TransactionFilter`]
+/// on the server, where it's known. Params that are empty or can't be read are ignored, so a hand
+/// edited URL still shows a list.
+///
+/// The filter form's fields are named after the params, so they're named to not clash with the
+/// ids of the new transaction form's fields.
+///
+/// ```
+/// use chrono::{NaiveDate, TimeZone, Utc};
+/// use chrono_tz::Tz;
+/// use leptos_router::ParamsMap;
+///
+/// use hoops_app::transactions::model::{SortColumn, TransactionSort};
+/// use hoops_app::transactions::query::TransactionQuery;
+///
+/// let mut params = ParamsMap::new();
+/// params.insert("payee_contains".into(), "Bread & Butter".into());
+/// params.insert("to".into(), "2024-01-27".into());
+/// params.insert("min_amount".into(), "".into());
+/// params.insert("max_amount".into(), "lots".into());
+/// params.insert("sort".into(), "amount".into());
+/// params.insert("dir".into(), "asc".into());
+///
+/// let query = TransactionQuery::from_params(&params);
+/// assert_eq!(query.payee.as_deref(), Some("Bread & Butter"));
+/// assert_eq!(query.to, NaiveDate::from_ymd_opt(2024, 1, 27));
+/// assert_eq!((query.min_amount, query.max_amount), (None, None));
+/// assert_eq!(query.sort, TransactionSort { column: SortColumn::Amount, descending: false });
+/// assert_eq!(
+///     query.to_search(),
+///     "to=2024-01-27&payee_contains=Bread%20%26%20Butter&sort=amount&dir=asc",
+/// );
+///
+/// // the end date is included, up until the next day starts in the user's time zone
+/// let filter = query.filter("Australia/Sydney".parse().unwrap());
+/// assert_eq!(filter.until, Some(Utc.with_ymd_and_hms(2024, 1, 27, 13, 0, 0).unwrap()));
+///
+/// // the default sort is left out
+/// assert_eq!(TransactionQuery::default().to_search(), "");
+///
+/// // changing the sort goes back to the first page, but keeps loading more as the user scrolls
+/// params.insert("cursor".into(), "7b7d".into());
+/// params.insert("scroll".into(), "infinite".into());
+/// let query = TransactionQuery::from_params(&params);
+/// assert_eq!(query.cursor.as_deref(), Some("7b7d"));
+/// let sorted = query.sorted_by(SortColumn::Payee);
+/// assert_eq!((sorted.cursor, sorted.infinite_scroll), (None, true));
+/// assert_eq!(query.unfiltered().to_search(), "sort=amount&dir=asc&scroll=infinite");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TransactionQuery {
+    /// Words to search payees & descriptions for, which lists the best matches first instead of
+    /// using the sort
+    pub search: Option<String>,
+    /// First day to list transactions from
+    pub from: Option<NaiveDate>,
+    /// Last day to list transactions from
+    pub to: Option<NaiveDate>,
+    pub min_amount: Option<Decimal>,
+    pub max_amount: Option<Decimal>,
+    /// Text the payee contains, ignoring case
+    pub payee: Option<String>,
+    pub account_id: Option<Uuid>,
+    /// The hoop transactions were spent from
+    pub spent_from: Option<Uuid>,
+    pub has_description: Option<bool>,
+    pub sort: TransactionSort,
+    /// Token for the page of the list to show, or the first page if unset
+    pub cursor: Option<String>,
+    /// Whether the list loads the next page as the user scrolls to the end of it, instead of
+    /// linking to it
+    pub infinite_scroll: bool,
+}
+
+impl TransactionQuery {
+    /// Read the list's state from the current URL's query params
+    pub fn from_params(params: &ParamsMap) -> Self {
+        let sort = TransactionSort::default();
+
+        Self {
+            search: parse(params, "search"),
+            from: parse(params, "from"),
+            to: parse(params, "to"),
+            min_amount: parse(params, "min_amount"),
+            max_amount: parse(params, "max_amount"),
+            payee: parse(params, "payee_contains"),
+            account_id: parse(params, "account"),
+            spent_from: parse(params, "hoop"),
+            has_description: parse(params, "has_description"),
+            sort: TransactionSort {
+                column: params
+                    .get("sort")
+                    .and_then(|column| SortColumn::from_name(column))
+                    .unwrap_or(sort.column),
+                descending: params
+                    .get("dir")
+                    .map(|dir| dir != "asc")
+                    .unwrap_or(sort.descending),
+            },
+            cursor: parse(params, "cursor"),
+            infinite_scroll: params
+                .get("scroll")
+                .is_some_and(|scroll| scroll == "infinite"),
+        }
+    }
+
+    /// Write the list's state as a query string, without the leading `?`, leaving out everything
+    /// that's unset
+    pub fn to_search(&self) -> String {
+        let TransactionQuery {
+            search,
+            from,
+            to,
+            min_amount,
+            max_amount,
+            payee,
+            account_id,
+            spent_from,
+            has_description,
+            sort: _,
+            cursor,
+            infinite_scroll: _,
+        } = self;
+
+        let filters = [
+            ("search", search.clone()),
+            ("from", from.map(|from| from.to_string())),
+            ("to", to.map(|to| to.to_string())),
+            ("min_amount", min_amount.map(|amount| amount.to_string())),
+            ("max_amount", max_amount.map(|amount| amount.to_string())),
+            ("payee_contains", payee.clone()),
+            ("account", account_id.map(|id| id.to_string())),
+            ("hoop", spent_from.map(|id| id.to_string())),
+            (
+                "has_description",
+                has_description.map(|has| has.to_string()),
+            ),
+        ];
+        let view = self
+            .view_params()
+            .into_iter()
+            .map(|(name, value)| (name, Some(value.to_string())));
+
+        filters
+            .into_iter()
+            .chain(view)
+            .chain([("cursor", cursor.clone())])
+            .filter_map(|(name, value)| value.map(|value| format!("{name}={}", encode(&value))))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// The params for how the list is shown, i.e. its sorting & whether it scrolls infinitely,
+    /// leaving out those that are the default
+    pub fn view_params(&self) -> Vec<(&'static str, &'static str)> {
+        let mut params = Vec::new();
+        if self.sort != TransactionSort::default() {
+            let dir = if self.sort.descending { "desc" } else { "asc" };
+            params.extend([("sort", self.sort.column.name()), ("dir", dir)]);
+        }
+        if self.infinite_scroll {
+            params.push(("scroll", "infinite"));
+        }
+        params
+    }
+
+    /// The same list, sorted by the given column
+    ///
+    /// Choosing the column it's already sorted by reverses the order, while a new column starts
+    /// with payees A to Z & the newest or largest first. Either way, the list starts from its
+    /// first page.
+    pub fn sorted_by(&self, column: SortColumn) -> Self {
+        let descending = match column == self.sort.column {
+            true => !self.sort.descending,
+            false => column != SortColumn::Payee,
+        };
+
+        Self {
+            sort: TransactionSort { column, descending },
+            cursor: None,
+            ..self.clone()
+        }
+    }
+
+    /// The same list shown a page at a time, or loading more as the user scrolls, starting from
+    /// its first page
+    pub fn scrolling(&self, infinite_scroll: bool) -> Self {
+        Self {
+            infinite_scroll,
+            cursor: None,
+            ..self.clone()
+        }
+    }
+
+    /// The same page of the same list
+    pub fn at_page(&self, cursor: Option<String>) -> Self {
+        Self {
+            cursor,
+            ..self.clone()
+        }
+    }
+
+    /// The same sorting & scrolling, without any filters or search, from the first page
+    pub fn unfiltered(&self) -> Self {
+        Self {
+            sort: self.sort,
+            infinite_scroll: self.infinite_scroll,
+            ..Default::default()
+        }
+    }
+
+    /// The filter for reading the listed transactions, with days starting & ending in the given
+    /// time zone
+    pub fn filter(&self, time_zone: Tz) -> TransactionFilter {
+        TransactionFilter {
+            from: self.from.map(|from| start_of_day(from, time_zone)),
+            until: self
+                .to
+                .map(|to| start_of_day(to + Duration::days(1), time_zone)),
+            min_amount: self.min_amount,
+            max_amount: self.max_amount,
+            payee: self.payee.clone(),
+            account_id: self.account_id,
+            spent_from: self.spent_from,
+            has_description: self.has_description,
+        }
+    }
+}
+
+impl Params for TransactionQuery {
+    fn from_map(map: &ParamsMap) -> Result<Self, ParamsError> {
+        Ok(Self::from_params(map))
+    }
+}
+
+/// Parse a query param, treating it as unset if it's empty or invalid
+fn parse<T: FromStr>(params: &ParamsMap, name: &str) -> Option<T> {
+    params
+        .get(name)
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+        .and_then(|value| value.parse().ok())
+}
+
+/// Percent-encode a query param's value
+///
+/// Done here instead of by the router, since it only escapes a value's reserved characters on the
+/// server, which would give the client different links to hydrate.
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
@@ -0,0 +1,369 @@
+use chrono_tz::{Tz, TZ_VARIANTS};
+use leptos::*;
+use leptos_router::*;
+
+use crate::components::{
+    errors::{action_error, action_server_error, action_validation_errors, field_errors},
+    input::{Input, InputType},
+};
+use crate::users::model::CurrentUser;
+
+#[cfg(feature = "ssr")]
+use crate::state::pool;
+
+/// Shortest password a user can register with
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Get the logged in user the current request was made by
+///
+/// The user is provided as context by the server, from the request's session cookie.
+#[cfg(feature = "ssr")]
+pub fn current_user() -> Result<CurrentUser, ServerFnError> {
+    use_context::<CurrentUser>()
+        .ok_or_else(|| ServerFnError::ServerError("You need to log in first".into()))
+}
+
+/// Get the scope limiting queries to the data owned by the logged in user
+#[cfg(feature = "ssr")]
+pub fn scope() -> Result<crate::models::Scope, ServerFnError> {
+    current_user().map(|user| crate::models::Scope::new(user.id))
+}
+
+/// The time zone timestamps are entered & shown in, which is UTC until the logged in user's is
+/// loaded
+///
+/// Reading it inside a `<Suspense>` waits for the user's time zone, so timestamps are rendered in
+/// it on the server too.
+pub fn use_time_zone() -> Signal<Tz> {
+    let context = use_context::<TimeZoneContext>();
+
+    Signal::derive(move || {
+        context
+            .and_then(|TimeZoneContext { user, .. }| user.get())
+            .and_then(Result::ok)
+            .flatten()
+            .map(|user| user.time_zone)
+            .unwrap_or_default()
+    })
+}
+
+/// Context holding the logged in user, read by [`use_time_zone`], & a trigger for refetching them
+/// once their time zone has been changed
+#[derive(Clone, Copy)]
+struct TimeZoneContext {
+    user: Resource<(), Result<Option<CurrentUser>, ServerFnError>>,
+    changed: Trigger,
+}
+
+/// Provide the context used by [`use_time_zone`] to the rest of the app
+pub fn provide_time_zone() {
+    let changed = create_trigger();
+    let user = create_resource(move || changed.track(), |_| current_user_read());
+    provide_context(TimeZoneContext { user, changed });
+}
+
+/// The IANA name of the browser's time zone, e.g. `Australia/Sydney`
+///
+/// Only known once the app is running in the browser.
+fn browser_time_zone() -> Option<String> {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "hydrate")] {
+            use js_sys::{Array, Intl::DateTimeFormat, Object, Reflect};
+
+            let options = DateTimeFormat::new(&Array::new(), &Object::new()).resolved_options();
+            Reflect::get(&options, &"timeZone".into()).ok()?.as_string()
+        } else {
+            None
+        }
+    }
+}
+
+/// Parse the IANA name of a time zone entered in a form
+#[cfg(feature = "ssr")]
+fn parse_time_zone(time_zone: &str) -> Option<Tz> {
+    time_zone.parse().ok()
+}
+
+/// Server endpoint for registering a new user
+///
+/// Logs the new user in & takes them to the home page. Their time zone is the browser's, sent in a
+/// hidden field.
+#[server(prefix = "/api", endpoint = "user/register")]
+pub async fn register(
+    username: String,
+    password: String,
+    password_confirmation: String,
+    time_zone: String,
+) -> Result<(), ServerFnError> {
+    use crate::errors::ValidationErrors;
+    use crate::users::model::{
+        db_create_session, db_create_user, db_read_one_by_username, hash_password, User,
+    };
+    use crate::users::session::set_session_cookie;
+
+    let username = username.trim().to_string();
+    let mut errors = ValidationErrors::default();
+    if username.is_empty() {
+        errors.add("username", "Enter a username");
+    }
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        errors.add(
+            "password",
+            format!("Passwords must be at least {MIN_PASSWORD_LENGTH} characters long"),
+        );
+    }
+    if password != password_confirmation {
+        errors.add("password_confirmation", "Passwords don't match");
+    }
+    let time_zone = parse_time_zone(&time_zone);
+    if time_zone.is_none() {
+        errors.add("time_zone", "Choose a valid time zone");
+    }
+    errors.check()?;
+
+    let pool = &pool()?;
+
+    let taken = || {
+        ServerFnError::from(ValidationErrors::field(
+            "username",
+            "That username is already taken",
+        ))
+    };
+    let exists = db_read_one_by_username(pool, &username)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?
+        .is_some();
+    if exists {
+        return Err(taken());
+    }
+
+    let password_hash = hash_password(password)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    let user = User::new(username, password_hash, time_zone.unwrap_or_default());
+    let user_id = user.id;
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    // the username can still be taken by someone registering at the same moment
+    db_create_user(&mut tx, user).await.map_err(|err| {
+        let unique = err
+            .downcast_ref::<sqlx::Error>()
+            .and_then(sqlx::Error::as_database_error)
+            .is_some_and(|err| err.is_unique_violation());
+        if unique {
+            return taken();
+        }
+        logging::log!("There was an error saving the user: {}", err);
+        ServerFnError::ServerError(err.to_string())
+    })?;
+    tx.commit()
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+
+    let token = db_create_session(pool, user_id)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    set_session_cookie(&token)?;
+
+    leptos_axum::redirect("/");
+    Ok(())
+}
+
+/// Server endpoint for logging in, taking the user to the home page
+#[server(prefix = "/api", endpoint = "user/login")]
+pub async fn login(username: String, password: String) -> Result<(), ServerFnError> {
+    use crate::users::model::{
+        db_create_session, db_read_one_by_username, verify_password, DUMMY_PASSWORD_HASH,
+    };
+    use crate::users::session::set_session_cookie;
+
+    let pool = &pool()?;
+    // the same error is given for an unknown user as for a wrong password, & a password is checked
+    // either way so both take as long, so usernames can't be discovered by logging in
+    let invalid = || ServerFnError::ServerError("Incorrect username or password".into());
+
+    let user = db_read_one_by_username(pool, username.trim())
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    let hash = match &user {
+        Some(user) => user.password_hash.clone(),
+        None => DUMMY_PASSWORD_HASH.to_string(),
+    };
+    let verified = verify_password(password, hash)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    let Some(user) = user.filter(|_| verified) else {
+        return Err(invalid());
+    };
+
+    let token = db_create_session(pool, user.id)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    set_session_cookie(&token)?;
+
+    leptos_axum::redirect("/");
+    Ok(())
+}
+
+/// Server endpoint for logging out, taking the user to the login page
+#[server(prefix = "/api", endpoint = "user/logout")]
+pub async fn logout() -> Result<(), ServerFnError> {
+    use crate::users::model::db_delete_session;
+    use crate::users::session::{clear_session_cookie, request_session_token};
+
+    let pool = &pool()?;
+
+    if let Some(token) = request_session_token() {
+        db_delete_session(pool, &token)
+            .await
+            .map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    }
+    clear_session_cookie()?;
+
+    leptos_axum::redirect("/login");
+    Ok(())
+}
+
+/// Server endpoint for reading the logged in user, if there is one
+#[server(prefix = "/api", endpoint = "user/read/current")]
+pub async fn current_user_read() -> Result<Option<CurrentUser>, ServerFnError> {
+    Ok(use_context::<CurrentUser>())
+}
+
+/// Server endpoint for changing the time zone the logged in user enters & reads timestamps in
+#[server(prefix = "/api", endpoint = "user/time_zone")]
+pub async fn time_zone_update(time_zone: String) -> Result<(), ServerFnError> {
+    use crate::errors::ValidationErrors;
+    use crate::users::model::db_update_time_zone;
+
+    let time_zone = parse_time_zone(&time_zone)
+        .ok_or_else(|| ValidationErrors::field("time_zone", "Choose a valid time zone"))?;
+    let user = current_user()?;
+    let pool = &pool()?;
+
+    db_update_time_zone(pool, user.id, time_zone)
+        .await
+        .map_err(|e| ServerFnError::ServerError(e.to_string()))
+}
+
+/// Form for registering a new user
+#[component]
+pub fn Register() -> impl IntoView {
+    let action = create_server_action::<Register>();
+    let errors = action_validation_errors(action);
+    // UTC until the browser's time zone is known, which can be changed later in the settings
+    let time_zone = create_rw_signal(String::from("UTC"));
+    create_effect(move |_| {
+        if let Some(browser) = browser_time_zone() {
+            time_zone.set(browser);
+        }
+    });
+
+    view! {
+        <h1>Register</h1>
+        {action_server_error(action)}
+        <ActionForm action>
+            <Input name="username".to_string() label="Username:".to_string() errors attr:required=true />
+            <Input name="password".to_string() label="Password:".to_string() input_type=InputType::Password errors attr:required=true attr:minlength=MIN_PASSWORD_LENGTH />
+            <Input name="password_confirmation".to_string() label="Confirm password:".to_string() input_type=InputType::Password errors attr:required=true />
+            <input type="hidden" name="time_zone" prop:value=time_zone />
+            {field_errors(errors, "time_zone".to_string())}
+            <button type="submit">Register</button>
+        </ActionForm>
+        <A href="/login">Already registered? Log in</A>
+    }
+}
+
+/// Form for logging in
+#[component]
+pub fn Login() -> impl IntoView {
+    let action = create_server_action::<Login>();
+
+    view! {
+        <h1>Log in</h1>
+        {action_error(action)}
+        <ActionForm action>
+            <Input name="username".to_string() label="Username:".to_string() attr:required=true />
+            <Input name="password".to_string() label="Password:".to_string() input_type=InputType::Password attr:required=true />
+            <button type="submit">Log in</button>
+        </ActionForm>
+        <A href="/register">Register a new user</A>
+    }
+}
+
+/// Form for changing the logged in user's settings
+#[component]
+pub fn Settings() -> impl IntoView {
+    let update = create_server_action::<TimeZoneUpdate>();
+    let errors = action_validation_errors(update);
+    let current = use_time_zone();
+    let browser = create_rw_signal(None);
+    create_effect(move |_| browser.set(browser_time_zone()));
+
+    // everything showing a timestamp is re-rendered in the new time zone once it's saved
+    if let Some(TimeZoneContext { changed, .. }) = use_context() {
+        create_effect(move |prev: Option<usize>| {
+            let version = update.version().get();
+            if prev.is_some() {
+                changed.notify();
+            }
+            version
+        });
+    }
+
+    view! {
+        <h1>Settings</h1>
+        {action_server_error(update)}
+        <ActionForm action=update>
+            <label for="time_zone">Time zone:</label>
+            <Suspense fallback=move || view! {<p>Loading...</p>}.into_view()>
+                {move || {
+                    let current = current.get();
+                    view! {
+                        <select id="time_zone" name="time_zone" required=true>
+                            {TZ_VARIANTS
+                                .iter()
+                                .map(|tz| view! {
+                                    <option value=tz.name() selected=*tz == current>{tz.name()}</option>
+                                })
+                                .collect_view()}
+                        </select>
+                    }
+                }}
+            </Suspense>
+            {field_errors(errors, "time_zone".to_string())}
+            {move || browser.get().map(|browser| view! { <p>"Your browser's time zone is " {browser}</p> })}
+            <button type="submit">Save</button>
+        </ActionForm>
+    }
+}
+
+/// Header menu showing the logged in user with an option to log out, or links to log in or
+/// register if nobody is logged in
+#[component]
+pub fn Menu() -> impl IntoView {
+    let logout = create_server_action::<Logout>();
+    let user = create_resource(move || logout.version().get(), |_| current_user_read());
+
+    view! {
+        <Suspense fallback=move || ().into_view()>
+            {move || user.get().map(|user| match user {
+                Ok(Some(CurrentUser { username, .. })) => view! {
+                    <span>{username}</span>
+                    <A href="/settings">Settings</A>
+                    <A href="/export">Export</A>
+                    <A href="/backup">Backup</A>
+                    <ActionForm action=logout>
+                        <button type="submit">Log out</button>
+                    </ActionForm>
+                }.into_view(),
+                _ => view! {
+                    <A href="/login">Log in</A>
+                    <A href="/register">Register</A>
+                }.into_view(),
+            })}
+        </Suspense>
+    }
+}
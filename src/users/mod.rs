@@ -0,0 +1,5 @@
+mod components;
+pub use components::*;
+pub mod model;
+#[cfg(feature = "ssr")]
+pub mod session;
@@ -0,0 +1,308 @@
+use cfg_if::cfg_if;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use hoops_macros::Model;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Data type for modeling a user's login information
+///
+/// Never sent to the client, since it holds the user's password hash.
+#[derive(Clone, Model)]
+#[model(table = "users")]
+pub struct User {
+    pub id: Uuid,
+    pub username: String,
+    /// Argon2 hash of the user's password, in PHC string format
+    pub password_hash: String,
+    /// IANA name of the time zone the user enters & reads timestamps in
+    pub time_zone: String,
+}
+
+impl User {
+    pub fn new(username: String, password_hash: String, time_zone: Tz) -> Self {
+        User {
+            id: Uuid::new_v4(),
+            username,
+            password_hash,
+            time_zone: time_zone.name().to_string(),
+        }
+    }
+}
+
+/// The logged in user a request was made by
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CurrentUser {
+    pub id: Uuid,
+    pub username: String,
+    pub time_zone: Tz,
+}
+
+impl From<User> for CurrentUser {
+    fn from(user: User) -> Self {
+        CurrentUser {
+            id: user.id,
+            username: user.username,
+            // time zones are checked before they're saved, so this only falls back for a zone that
+            // has since been removed from the time zone database
+            time_zone: user.time_zone.parse().unwrap_or_default(),
+        }
+    }
+}
+
+/// Data type for modeling a user's login session
+#[derive(Clone, Model)]
+#[model(table = "sessions")]
+pub struct Session {
+    /// SHA-256 hash of the session's token, so the token itself is only ever kept in the cookie
+    pub id: String,
+    pub user_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+}
+
+cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use std::convert::TryFrom;
+        use anyhow::anyhow;
+        use argon2::{
+            password_hash::{rand_core::{OsRng, RngCore}, PasswordHasher, PasswordVerifier, SaltString},
+            Argon2, PasswordHash,
+        };
+        use chrono::Duration;
+        use sha2::{Digest, Sha256};
+        use sqlx::{Executor, Sqlite, SqliteConnection};
+
+        use crate::models::Ident;
+
+        /// How many days a session lasts before the user has to log in again
+        pub const SESSION_DAYS: i64 = 30;
+
+        /// Hash checked against when logging in as a user that doesn't exist, made with the same
+        /// parameters as every saved hash, so it takes as long as checking a real user's password
+        pub const DUMMY_PASSWORD_HASH: &str =
+            "$argon2id$v=19$m=19456,t=2,p=1$rz3plsWbe7+YVQRTZblj7g$grI5DXf8MTeFt0aKQAttfBGdjFHApGTl+ghxVGigAeM";
+
+        /// Tables holding data owned by a user, whose unowned rows are claimed by `db_claim_unowned`
+        const OWNED_TABLES: &[Ident] = &[
+            Ident::new("accounts"),
+            Ident::new("hoops"),
+            Ident::new("hoop_allocations"),
+            Ident::new("transactions"),
+        ];
+
+        /// Hash a password with a new random salt
+        ///
+        /// Hashing is slow on purpose, so it's done on a blocking thread.
+        pub async fn hash_password(password: String) -> Result<String, anyhow::Error> {
+            tokio::task::spawn_blocking(move || {
+                let salt = SaltString::generate(&mut OsRng);
+
+                Argon2::default()
+                    .hash_password(password.as_bytes(), &salt)
+                    .map(|hash| hash.to_string())
+                    .map_err(|e| anyhow!(e))
+            })
+            .await?
+        }
+
+        /// Check a password against the hash saved for a user
+        pub async fn verify_password(password: String, hash: String) -> Result<bool, anyhow::Error> {
+            tokio::task::spawn_blocking(move || {
+                let hash = PasswordHash::new(&hash).map_err(|e| anyhow!(e))?;
+
+                Ok(Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+            })
+            .await?
+        }
+
+        // users & sessions aren't owned by a user themselves, so they're queried directly instead of
+        // through the scoped model traits
+
+        /// Save a new user
+        pub async fn db_create_user(conn: &mut SqliteConnection, user: User) -> Result<(), anyhow::Error> {
+            let UserSql { id, username, password_hash, time_zone } = user.into();
+            sqlx::query("INSERT INTO users (id, username, password_hash, time_zone) VALUES (?, ?, ?, ?);")
+                .bind(&id)
+                .bind(username)
+                .bind(password_hash)
+                .bind(time_zone)
+                .execute(&mut *conn)
+                .await?;
+
+            Ok(())
+        }
+
+        /// Give the user every row saved before users were added, which belongs to nobody,
+        /// returning how many rows they were given
+        ///
+        /// Unowned rows can't be seen by anyone until they're claimed, which is only done when the
+        /// server's run with `claim-unowned <username>`, so a stranger registering first can't take
+        /// them. This should be run inside a database transaction, so every table is claimed
+        /// together.
+        ///
+        /// ```
+        /// use chrono_tz::Tz;
+        /// use sqlx::SqlitePool;
+        ///
+        /// use hoops_app::accounts::model::Account;
+        /// use hoops_app::models::{Read, Scope};
+        /// use hoops_app::users::model::{db_claim_unowned, db_create_user, User};
+        ///
+        /// # #[tokio::main]
+        /// # async fn main() -> Result<(), anyhow::Error> {
+        /// let pool = SqlitePool::connect("sqlite::memory:").await?;
+        /// sqlx::migrate!().run(&pool).await?;
+        /// sqlx::query("INSERT INTO accounts (id, name, closed) VALUES ('9a7c4b7e-8d7e-4bd6-9a52-6b2f0c1f7d10', 'Checking', 0);")
+        ///     .execute(&pool)
+        ///     .await?;
+        ///
+        /// let user = User::new("alice".into(), String::new(), Tz::UTC);
+        /// let alice = Scope::new(user.id);
+        /// let mut tx = pool.begin().await?;
+        /// db_create_user(&mut tx, user).await?;
+        /// // registering doesn't give a user anything
+        /// assert!(Account::read_many(&mut *tx, alice, None, None).await?.is_empty());
+        ///
+        /// assert_eq!(db_claim_unowned(&mut tx, alice.owner_id()).await?, 1);
+        /// tx.commit().await?;
+        /// assert_eq!(Account::read_many(&pool, alice, None, None).await?.len(), 1);
+        /// # Ok(())
+        /// # }
+        /// ```
+        pub async fn db_claim_unowned(conn: &mut SqliteConnection, user_id: Uuid) -> Result<u64, anyhow::Error> {
+            let mut claimed = 0;
+            for table in OWNED_TABLES {
+                claimed += sqlx::query(&format!("UPDATE {table} SET owner_id = ? WHERE owner_id IS NULL;"))
+                    .bind(user_id.to_string())
+                    .execute(&mut *conn)
+                    .await?
+                    .rows_affected();
+            }
+
+            Ok(claimed)
+        }
+
+        /// Read the user with the given username, ignoring case, if they exist
+        pub async fn db_read_one_by_username<'c, E>(
+            executor: E,
+            username: &str,
+        ) -> Result<Option<User>, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite>,
+        {
+            sqlx::query_as::<_, UserSql>("SELECT * FROM users WHERE username = ?;")
+                .bind(username)
+                .fetch_optional(executor)
+                .await?
+                .map(User::try_from)
+                .transpose()
+        }
+
+        /// Start a new session for the given user, returning the token to keep in their cookie
+        pub async fn db_create_session<'c, E>(executor: E, user_id: Uuid) -> Result<String, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite>,
+        {
+            let mut bytes = [0u8; 32];
+            OsRng.fill_bytes(&mut bytes);
+            let token = hex::encode(bytes);
+
+            let session = Session {
+                id: hash_token(&token),
+                user_id,
+                expires_at: Utc::now() + Duration::days(SESSION_DAYS),
+            };
+            let SessionSql { id, user_id, expires_at } = session.into();
+            sqlx::query("INSERT INTO sessions (id, user_id, expires_at) VALUES (?, ?, ?);")
+                .bind(id)
+                .bind(user_id)
+                .bind(expires_at)
+                .execute(executor)
+                .await?;
+
+            Ok(token)
+        }
+
+        /// Read the user logged in with the given session token, if the session hasn't expired
+        pub async fn db_read_session_user<'c, E>(
+            executor: E,
+            token: &str,
+        ) -> Result<Option<CurrentUser>, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite>,
+        {
+            let row = sqlx::query_as::<_, (String, String, String, String)>(
+                r#"
+                SELECT users.id, users.username, users.time_zone, sessions.expires_at
+                FROM sessions
+                JOIN users ON users.id = sessions.user_id
+                WHERE sessions.id = ?;
+                "#
+            )
+                .bind(hash_token(token))
+                .fetch_optional(executor)
+                .await?;
+            let Some((id, username, time_zone, expires_at)) = row else {
+                return Ok(None);
+            };
+
+            if DateTime::parse_from_rfc3339(&expires_at)? <= Utc::now() {
+                return Ok(None);
+            }
+
+            Ok(Some(CurrentUser {
+                id: Uuid::parse_str(&id)?,
+                username,
+                time_zone: time_zone.parse().unwrap_or_default(),
+            }))
+        }
+
+        /// Change the time zone a user enters & reads timestamps in
+        pub async fn db_update_time_zone<'c, E>(
+            executor: E,
+            user_id: Uuid,
+            time_zone: Tz,
+        ) -> Result<(), anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite>,
+        {
+            sqlx::query("UPDATE users SET time_zone = ? WHERE id = ?;")
+                .bind(time_zone.name())
+                .bind(user_id.to_string())
+                .execute(executor)
+                .await?;
+
+            Ok(())
+        }
+
+        /// End the session with the given token
+        pub async fn db_delete_session<'c, E>(executor: E, token: &str) -> Result<(), anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite>,
+        {
+            sqlx::query("DELETE FROM sessions WHERE id = ?;")
+                .bind(hash_token(token))
+                .execute(executor)
+                .await?;
+
+            Ok(())
+        }
+
+        /// Delete every session that has expired, returning how many there were
+        pub async fn db_delete_expired_sessions<'c, E>(executor: E) -> Result<u64, anyhow::Error>
+        where
+            E: Executor<'c, Database = Sqlite>,
+        {
+            let deleted = sqlx::query("DELETE FROM sessions WHERE julianday(expires_at) <= julianday(?);")
+                .bind(Utc::now().to_rfc3339())
+                .execute(executor)
+                .await?;
+
+            Ok(deleted.rows_affected())
+        }
+
+        fn hash_token(token: &str) -> String {
+            hex::encode(Sha256::digest(token.as_bytes()))
+        }
+    }
+}
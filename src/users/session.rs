@@ -0,0 +1,120 @@
+//! Reading & writing the cookie holding a user's session token
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::{header, request::Parts, HeaderValue, StatusCode},
+};
+use leptos::{use_context, ServerFnError};
+use leptos_axum::{RequestParts, ResponseOptions};
+use sqlx::SqlitePool;
+
+use crate::users::model::{db_read_session_user, CurrentUser, SESSION_DAYS};
+
+/// Name of the cookie holding the session token
+pub const SESSION_COOKIE: &str = "hoops_session";
+
+/// Extracts the logged in user from a request's session cookie
+///
+/// Rejects the request with `401 Unauthorized` if there's no valid session, so use
+/// `Option<CurrentUser>` for requests that don't need a user.
+#[async_trait]
+impl<S> FromRequestParts<S> for CurrentUser
+where
+    SqlitePool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let token = session_token(&parts.headers).ok_or(StatusCode::UNAUTHORIZED)?;
+        let pool = SqlitePool::from_ref(state);
+
+        db_read_session_user(&pool, &token)
+            .await
+            .map_err(|err| {
+                log::error!("There was an error reading the session: {}", err);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .ok_or(StatusCode::UNAUTHORIZED)
+    }
+}
+
+/// Get the session token from a request's cookies, if there is one
+pub fn session_token(headers: &http::HeaderMap) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, token)| token.to_string())
+}
+
+/// Get the session token sent with the request a server function is handling
+pub fn request_session_token() -> Option<String> {
+    use_context::<RequestParts>().and_then(|parts| session_token(&parts.headers))
+}
+
+/// Set the session cookie on the response to a server function
+pub fn set_session_cookie(token: &str) -> Result<(), ServerFnError> {
+    let max_age = SESSION_DAYS * 24 * 60 * 60;
+    append_cookie(format!(
+        "{SESSION_COOKIE}={token}; Path=/; Max-Age={max_age}{}",
+        cookie_attributes()
+    ))
+}
+
+/// Remove the session cookie from the browser with the response to a server function
+pub fn clear_session_cookie() -> Result<(), ServerFnError> {
+    append_cookie(format!(
+        "{SESSION_COOKIE}=; Path=/; Max-Age=0{}",
+        cookie_attributes()
+    ))
+}
+
+/// Whether a request was made over HTTPS, either straight to the app or through a proxy that says
+/// so with `X-Forwarded-Proto`
+///
+/// ```
+/// use axum::http::Request;
+/// use hoops_app::users::session::is_https;
+///
+/// let proxied = Request::get("/").header("X-Forwarded-Proto", "https").body(()).unwrap();
+/// assert!(is_https(proxied.uri(), proxied.headers()));
+/// let direct = Request::get("https://hoops.example/").body(()).unwrap();
+/// assert!(is_https(direct.uri(), direct.headers()));
+/// let plain = Request::get("/").body(()).unwrap();
+/// assert!(!is_https(plain.uri(), plain.headers()));
+/// ```
+pub fn is_https(uri: &http::Uri, headers: &http::HeaderMap) -> bool {
+    let forwarded = headers
+        .get("x-forwarded-proto")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|protos| protos.split(',').next())
+        .is_some_and(|proto| proto.trim().eq_ignore_ascii_case("https"));
+
+    forwarded || uri.scheme_str() == Some("https")
+}
+
+/// Attributes every session cookie is set with, marking it `Secure` when the request was made over
+/// HTTPS so the browser never sends it without encryption
+fn cookie_attributes() -> &'static str {
+    let https =
+        use_context::<RequestParts>().is_some_and(|parts| is_https(&parts.uri, &parts.headers));
+
+    match https {
+        true => "; HttpOnly; SameSite=Lax; Secure",
+        false => "; HttpOnly; SameSite=Lax",
+    }
+}
+
+fn append_cookie(cookie: String) -> Result<(), ServerFnError> {
+    let response = use_context::<ResponseOptions>()
+        .ok_or_else(|| ServerFnError::ServerError("Response options missing".into()))?;
+    let value =
+        HeaderValue::from_str(&cookie).map_err(|e| ServerFnError::ServerError(e.to_string()))?;
+    response.append_header(header::SET_COOKIE, value);
+
+    Ok(())
+}